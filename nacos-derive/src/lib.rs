@@ -0,0 +1,99 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Error, Ident, LitStr};
+
+/// Derives `nacos::NacosConfig` and adds `load(&client)` / `watch(&client)` methods.
+///
+/// ```ignore
+/// #[derive(Deserialize, NacosConfig)]
+/// #[nacos(data_id = "app.yaml", group = "APP_GROUP", format = "yaml")]
+/// struct AppConfig { ... }
+/// ```
+///
+/// `group` defaults to the client's group and `format` to the config type reported by the
+/// server.
+#[proc_macro_derive(NacosConfig, attributes(nacos))]
+pub fn derive_nacos_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut data_id = None;
+    let mut group = None;
+    let mut format = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("nacos"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("data_id") {
+                data_id = Some(value);
+            } else if meta.path.is_ident("group") {
+                group = Some(value);
+            } else if meta.path.is_ident("format") {
+                format = Some(format_variant(&value)?);
+            } else {
+                return Err(meta.error("expected `data_id`, `group` or `format`"));
+            }
+            Ok(())
+        })?;
+    }
+    let data_id = data_id.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "missing `#[nacos(data_id = \"...\")]` attribute",
+        )
+    })?;
+    let group = match group {
+        Some(group) => quote!(::std::option::Option::Some(#group)),
+        None => quote!(::std::option::Option::None),
+    };
+    let format = match format {
+        Some(format) => quote!(::std::option::Option::Some(::nacos::ConfigFormat::#format)),
+        None => quote!(::std::option::Option::None),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::nacos::NacosConfig for #name #ty_generics #where_clause {
+            const DATA_ID: &'static str = #data_id;
+            const GROUP: ::std::option::Option<&'static str> = #group;
+            const FORMAT: ::std::option::Option<::nacos::ConfigFormat> = #format;
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Fetches this config from `client`.
+            pub async fn load(client: &::nacos::Nacos) -> ::nacos::Result<Self> {
+                client.load::<Self>().await
+            }
+
+            /// Waits for a new version of this config from `client`.
+            pub async fn watch(client: &::nacos::Nacos) -> ::nacos::Result<Self> {
+                client.watch::<Self>().await
+            }
+        }
+    })
+}
+
+fn format_variant(format: &LitStr) -> syn::Result<Ident> {
+    let variant = match format.value().to_ascii_lowercase().as_str() {
+        "json" => "Json",
+        "yaml" | "yml" => "Yaml",
+        "toml" => "Toml",
+        "properties" => "Properties",
+        _ => {
+            return Err(Error::new(
+                format.span(),
+                "expected `json`, `yaml`, `toml` or `properties`",
+            ))
+        }
+    };
+    Ok(Ident::new(variant, format.span()))
+}
@@ -0,0 +1,119 @@
+// Requests aren't logged on wasm32.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
+
+use std::time::Duration;
+
+use reqwest::{Method, RequestBuilder, Response};
+
+use crate::{Nacos, Result};
+
+/// What the access log needs to know about a request before it is sent.
+pub(crate) struct Access {
+    method: Method,
+    path: String,
+}
+
+impl Nacos {
+    /// Emits a `tracing` event with the `nacos::access` target for every request to the
+    /// server: its method, path, data id, status, latency and retry number. Failed requests
+    /// are logged at the `WARN` level, others at `INFO`. Requests aren't logged on wasm32.
+    pub fn with_access_log(mut self) -> Self {
+        self.access_log = true;
+        self
+    }
+
+    /// Notes the method and path of a request if the access log is enabled. Requests aren't
+    /// logged on wasm32, whose std has no clock to time them with.
+    pub(crate) fn access(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<(RequestBuilder, Option<Access>), reqwest::Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if !self.access_log {
+                return Ok((request, None));
+            }
+            let (client, request) = request.build_split();
+            let request = request?;
+            let access = Access {
+                method: request.method().clone(),
+                path: request.url().path().to_string(),
+            };
+            Ok((RequestBuilder::from_parts(client, request), Some(access)))
+        }
+        #[cfg(target_arch = "wasm32")]
+        Ok((request, None))
+    }
+}
+
+pub(crate) fn record(
+    access: Access,
+    attributes: &[(&'static str, &str)],
+    request_id: &str,
+    retry: u32,
+    latency: Duration,
+    response: &Result<Response>,
+) {
+    let data_id = attributes
+        .iter()
+        .find(|(key, _)| *key == "nacos.data_id")
+        .map_or("", |(_, value)| *value);
+    let status = match response {
+        Ok(response) => Some(response.status()),
+        Err(e) => e.status(),
+    }
+    .map(|status| status.as_u16());
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    match response {
+        Ok(_) => tracing::info!(
+            target: "nacos::access",
+            method = %access.method,
+            path = access.path,
+            data_id,
+            status,
+            latency_ms,
+            retry,
+            request_id,
+        ),
+        Err(e) => tracing::warn!(
+            target: "nacos::access",
+            method = %access.method,
+            path = access.path,
+            data_id,
+            status,
+            latency_ms,
+            retry,
+            request_id,
+            error = %e,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn access() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let request = nacos.client.post("http://a/nacos/v1/cs/configs?dataId=a");
+        assert!(nacos.access(request).unwrap().1.is_none());
+
+        let nacos = nacos.with_access_log();
+        let request = nacos.client.post("http://a/nacos/v1/cs/configs?dataId=a");
+        let (request, access) = nacos.access(request).unwrap();
+        let access = access.unwrap();
+        assert_eq!(access.method, Method::POST);
+        assert_eq!(access.path, "/nacos/v1/cs/configs");
+        // The request is unchanged.
+        let request = request.build().unwrap();
+        assert_eq!(request.url().query(), Some("dataId=a"));
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Deserialize;
+use sha1::Sha1;
+
+use crate::{Kms, KmsFuture};
+
+/// Alibaba Cloud KMS, which decrypts `cipher-` configs with the `Decrypt` API.
+#[derive(Clone)]
+pub struct AliyunKms {
+    client: Client,
+    endpoint: String,
+    access_key_id: String,
+    access_key_secret: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DecryptResponse {
+    plaintext: Option<String>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl AliyunKms {
+    /// Uses the public endpoint of `region`, like `cn-hangzhou`.
+    pub fn new(
+        region: &str,
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+    ) -> Self {
+        Self::with_endpoint(
+            format!("https://kms.{region}.aliyuncs.com"),
+            access_key_id,
+            access_key_secret,
+        )
+    }
+
+    /// Uses another endpoint, like a VPC one.
+    pub fn with_endpoint(
+        endpoint: impl Into<String>,
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            access_key_id: access_key_id.into(),
+            access_key_secret: access_key_secret.into(),
+        }
+    }
+
+    /// Adds the common parameters and the signature to API parameters.
+    fn signed_query(&self, params: &[(&str, &str)], timestamp: &str, nonce: &str) -> String {
+        let params: Vec<(&str, &str)> = params
+            .iter()
+            .copied()
+            .chain([
+                ("AccessKeyId", self.access_key_id.as_str()),
+                ("Format", "JSON"),
+                ("SignatureMethod", "HMAC-SHA1"),
+                ("SignatureNonce", nonce),
+                ("SignatureVersion", "1.0"),
+                ("Timestamp", timestamp),
+                ("Version", "2016-01-20"),
+            ])
+            .collect();
+        sign(&self.access_key_secret, params)
+    }
+}
+
+/// Signs RPC style API parameters with signature version 1.0, returning the query string.
+fn sign(access_key_secret: &str, mut params: Vec<(&str, &str)>) -> String {
+    params.sort();
+    let query = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let string_to_sign = format!("GET&%2F&{}", percent_encode(&query));
+    let mut mac = Hmac::<Sha1>::new_from_slice(format!("{access_key_secret}&").as_bytes())
+        .expect("HMAC takes keys of any length");
+    mac.update(string_to_sign.as_bytes());
+    let signature = STANDARD.encode(mac.finalize().into_bytes());
+    format!("{query}&Signature={}", percent_encode(&signature))
+}
+
+impl Kms for AliyunKms {
+    fn decrypt(&self, _: &str, ciphertext: String) -> KmsFuture {
+        static NONCE: AtomicU64 = AtomicU64::new(0);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let nonce = format!(
+            "{}-{}",
+            now.as_nanos(),
+            NONCE.fetch_add(1, Ordering::Relaxed)
+        );
+        let query = self.signed_query(
+            &[("Action", "Decrypt"), ("CiphertextBlob", &ciphertext)],
+            &iso8601(now.as_secs()),
+            &nonce,
+        );
+        let request = self.client.get(format!("{}/?{query}", self.endpoint));
+        Box::pin(async move {
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            let status = response.status();
+            let response: DecryptResponse = response.json().await.map_err(|e| e.to_string())?;
+            match response.plaintext {
+                Some(plaintext) if status.is_success() => Ok(plaintext),
+                _ => Err(format!(
+                    "{}: {}",
+                    response.code.unwrap_or_else(|| status.to_string()),
+                    response.message.unwrap_or_default()
+                )),
+            }
+        })
+    }
+}
+
+/// RFC 3986 encoding, as the signature requires.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Formats seconds since the epoch as `2023-11-14T22:13:20Z`.
+fn iso8601(secs: u64) -> String {
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm.
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(percent_encode("a b*~/+="), "a%20b%2A~%2F%2B%3D");
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn signature() {
+        // The example of the Alibaba Cloud signature documentation.
+        let query = sign(
+            "testsecret",
+            vec![
+                ("Action", "DescribeRegions"),
+                ("AccessKeyId", "testid"),
+                ("Format", "XML"),
+                ("SignatureMethod", "HMAC-SHA1"),
+                ("SignatureNonce", "3ee8c1b8-83d3-44af-a94f-4e0ad82fd6cf"),
+                ("SignatureVersion", "1.0"),
+                ("Timestamp", "2016-02-23T12:46:24Z"),
+                ("Version", "2014-05-26"),
+            ],
+        );
+        assert!(query.starts_with("AccessKeyId=testid&Action=DescribeRegions&"));
+        assert!(query.ends_with("&Signature=OLeaidS1JvxuMvnyHOwuJ%2BuX5qY%3D"));
+    }
+}
@@ -0,0 +1,263 @@
+use std::{sync::RwLock, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{runtime::Instant, Error, Nacos, Result};
+
+/// Credentials of a client and the access token they were last exchanged for, shared by
+/// clones.
+#[derive(Default)]
+pub(crate) struct Auth {
+    /// Username and password, with a generation that changes whenever they are replaced.
+    credentials: RwLock<(u64, Option<Credentials>)>,
+    token: tokio::sync::Mutex<Option<Token>>,
+    #[cfg(feature = "access-key")]
+    pub(crate) access_key: RwLock<Option<crate::sign::AccessKey>>,
+    /// Milliseconds from the local clock to the server's, for signatures.
+    #[cfg(feature = "access-key")]
+    pub(crate) clock_offset: std::sync::atomic::AtomicI64,
+}
+
+#[derive(Clone)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+#[derive(Clone)]
+struct Token {
+    access_token: String,
+    refresh_at: Instant,
+    /// Of the credentials it was obtained with.
+    generation: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Login {
+    access_token: String,
+    /// Seconds.
+    token_ttl: u64,
+}
+
+/// Messages of servers refusing an access token, as opposed to the permissions of its user.
+const REFUSED_TOKEN: [&str; 2] = ["token expired", "token invalid"];
+
+/// Whether the server refused a request for its access token, which logging in again renews.
+pub(crate) fn is_refused_token(error: &Error) -> bool {
+    let Error::Forbidden { message, .. } = error else {
+        return false;
+    };
+    let message = message.to_ascii_lowercase();
+    REFUSED_TOKEN
+        .iter()
+        .any(|refused| message.contains(refused))
+}
+
+impl Nacos {
+    /// Logs in with a username and password, as required by servers with auth enabled. The
+    /// access token is shared by clones and renewed before it expires.
+    pub fn with_credentials(
+        self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.set_credentials(username, password);
+        self
+    }
+
+    /// Replaces the credentials of this client and its clones, e.g. after a password rotation.
+    /// Requests log in again with the new credentials.
+    pub fn set_credentials(&self, username: impl Into<String>, password: impl Into<String>) {
+        let mut credentials = self.auth.credentials.write().unwrap();
+        credentials.0 += 1;
+        credentials.1 = Some(Credentials {
+            username: username.into(),
+            password: password.into(),
+        });
+    }
+
+    /// A valid access token, or `None` without credentials.
+    pub(crate) async fn access_token(&self) -> Result<Option<String>> {
+        let mut token = self.auth.token.lock().await;
+        let (generation, Some(credentials)) = self.auth.credentials.read().unwrap().clone() else {
+            return Ok(None);
+        };
+        match &*token {
+            Some(token) if token.refresh_at > Instant::now() && token.generation == generation => {}
+            _ => *token = Some(self.login(&credentials, generation).await?),
+        }
+        Ok(token.as_ref().map(|token| token.access_token.clone()))
+    }
+
+    /// Forgets `access_token` after the server refused it, unless it was renewed already.
+    pub(crate) async fn invalidate_token(&self, access_token: &str) {
+        let mut token = self.auth.token.lock().await;
+        if token
+            .as_ref()
+            .is_some_and(|token| token.access_token == access_token)
+        {
+            *token = None;
+        }
+    }
+
+    async fn login(&self, credentials: &Credentials, generation: u64) -> Result<Token> {
+        let url = self.make_url("/nacos/v1/auth/login");
+        let form = [
+            ("username", &credentials.username),
+            ("password", &credentials.password),
+        ];
+        let request = self.client.post(url).form(&form);
+        let attributes = [("nacos.username", credentials.username.as_str())];
+        let requested = Instant::now();
+        let response = self
+            .send_unauthenticated("nacos.auth.login", request, &attributes, 0)
+            .await?;
+        let login: Login = response.json().await?;
+        Ok(Token {
+            access_token: login.access_token,
+            // Renew early so that requests in flight don't carry an expired token.
+            refresh_at: requested + Duration::from_secs(login.token_ttl) * 9 / 10,
+            generation,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::{HttpRequest, HttpResponse, Transport, TransportFuture};
+
+    /// Refuses requests with the access token `abc` with `message`, keeping the requested
+    /// paths.
+    struct Refusing {
+        message: &'static str,
+        paths: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Transport for Refusing {
+        fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+            self.paths
+                .lock()
+                .unwrap()
+                .push(request.url.path().to_string());
+            let response = if request.url.path() == "/nacos/v1/auth/login" {
+                HttpResponse::ok(r#"{"accessToken":"def","tokenTtl":60}"#)
+            } else if request
+                .url
+                .query()
+                .unwrap_or("")
+                .contains("accessToken=abc")
+            {
+                HttpResponse {
+                    status: reqwest::StatusCode::FORBIDDEN,
+                    ..HttpResponse::ok(format!(r#"{{"status":403,"message":"{}"}}"#, self.message))
+                }
+            } else {
+                HttpResponse::ok("a: 1")
+            };
+            Box::pin(async { Ok(response) })
+        }
+    }
+
+    async fn refused(message: &'static str) -> (Result<String>, Vec<String>) {
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_credentials("nacos", "secret")
+        .with_transport(Refusing {
+            message,
+            paths: paths.clone(),
+        });
+        *nacos.auth.token.lock().await = Some(Token {
+            access_token: "abc".into(),
+            refresh_at: Instant::now() + Duration::from_secs(60),
+            generation: 1,
+        });
+        let request = nacos.client.get(nacos.make_url("/nacos/v1/cs/configs"));
+        let result = match nacos.send("nacos.config.fetch", request, &[]).await {
+            Ok(response) => Ok(response.text().await.unwrap()),
+            Err(e) => Err(e),
+        };
+        let paths = paths.lock().unwrap().clone();
+        (result, paths)
+    }
+
+    #[tokio::test]
+    async fn refused_token() {
+        let (result, paths) = refused("token expired!").await;
+        assert_eq!(result.unwrap(), "a: 1");
+        assert_eq!(
+            paths,
+            [
+                "/nacos/v1/cs/configs",
+                "/nacos/v1/auth/login",
+                "/nacos/v1/cs/configs"
+            ]
+        );
+
+        // Without permission, logging in again doesn't help.
+        let (result, paths) = refused("authorization failed!").await;
+        assert!(
+            matches!(&result, Err(Error::Forbidden { message, .. }) if message == "authorization failed!"),
+            "{:?}",
+            result
+        );
+        assert_eq!(paths, ["/nacos/v1/cs/configs"]);
+    }
+
+    #[tokio::test]
+    async fn token() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        assert_eq!(nacos.access_token().await.unwrap(), None);
+
+        let nacos = nacos.with_credentials("nacos", "secret");
+        *nacos.auth.token.lock().await = Some(Token {
+            access_token: "abc".into(),
+            refresh_at: Instant::now() + Duration::from_secs(60),
+            generation: 1,
+        });
+        assert_eq!(nacos.access_token().await.unwrap().as_deref(), Some("abc"));
+
+        nacos.invalidate_token("other").await;
+        assert_eq!(nacos.access_token().await.unwrap().as_deref(), Some("abc"));
+        // Logging in again fails without a server.
+        nacos.invalidate_token("abc").await;
+        assert!(nacos.access_token().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rotate_credentials() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_credentials("nacos", "old");
+        let clone = nacos.clone();
+        *nacos.auth.token.lock().await = Some(Token {
+            access_token: "abc".into(),
+            refresh_at: Instant::now() + Duration::from_secs(60),
+            generation: 1,
+        });
+        assert_eq!(clone.access_token().await.unwrap().as_deref(), Some("abc"));
+        // The token of the old password is not used anymore, by any clone.
+        nacos.set_credentials("nacos", "new");
+        assert!(clone.access_token().await.is_err());
+    }
+}
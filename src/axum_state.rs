@@ -0,0 +1,66 @@
+use std::{convert::Infallible, ops::Deref, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+
+use crate::ConfigHandle;
+
+/// An axum extractor of the latest value of a watched config, taken from a [`ConfigHandle`]
+/// in the router state:
+///
+/// ```ignore
+/// let handle = nacos.watch_live::<AppConfig>("app.yaml", None).await?;
+/// let app = Router::new().route("/", get(handler)).with_state(handle);
+///
+/// async fn handler(CurrentConfig(config): CurrentConfig<AppConfig>) { ... }
+/// ```
+pub struct CurrentConfig<T>(pub Arc<T>);
+
+impl<T> Deref for CurrentConfig<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for CurrentConfig<T>
+where
+    ConfigHandle<T>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Infallible> {
+        Ok(Self(ConfigHandle::from_ref(state).load()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+    use crate::live::LiveConfig;
+
+    #[tokio::test]
+    async fn extract() {
+        let live = LiveConfig::new(1);
+        let handle = ConfigHandle::new(live.clone(), vec![]);
+        let (mut parts, ()) = Request::new(()).into_parts();
+        let CurrentConfig(config) = CurrentConfig::<i32>::from_request_parts(&mut parts, &handle)
+            .await
+            .unwrap();
+        assert_eq!(*config, 1);
+
+        live.store(2);
+        let config = CurrentConfig::<i32>::from_request_parts(&mut parts, &handle)
+            .await
+            .unwrap();
+        assert_eq!(*config, 2);
+    }
+}
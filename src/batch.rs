@@ -0,0 +1,61 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bytes::Bytes;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use tokio::sync::Semaphore;
+
+use crate::{deadline, Nacos, Result};
+
+/// Most configs fetched at once by [`Nacos::get_configs`].
+const FETCH_PARALLELISM: usize = 8;
+
+impl Nacos {
+    /// Fetches configs concurrently, a few at a time, e.g. to load dozens of them at startup.
+    /// Every data id maps to the result of fetching it, as with [`Self::get_config`].
+    pub async fn get_configs(&self, data_ids: &[&str]) -> HashMap<String, Result<Bytes>> {
+        let permits = Arc::new(Semaphore::new(FETCH_PARALLELISM));
+        let deadline = deadline::current();
+        let mut fetches = FuturesUnordered::new();
+        for data_id in data_ids {
+            let nacos = self.clone();
+            let data_id = data_id.to_string();
+            let permits = permits.clone();
+            fetches.push(async move {
+                let _permit = permits.acquire_owned().await;
+                let fetch = nacos.get_config(&data_id);
+                let content = match deadline {
+                    Some(deadline) => deadline::with_deadline(deadline, fetch).await,
+                    None => fetch.await,
+                };
+                (data_id, content)
+            });
+        }
+        let mut configs = HashMap::with_capacity(data_ids.len());
+        while let Some((data_id, content)) = fetches.next().await {
+            configs.insert(data_id, content);
+        }
+        configs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn get_configs() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let data_ids: Vec<_> = (0..20).map(|i| format!("app-{i}.yaml")).collect();
+        let data_ids: Vec<_> = data_ids.iter().map(String::as_str).collect();
+        let configs = nacos.get_configs(&data_ids).await;
+        assert_eq!(configs.len(), 20);
+        assert!(configs["app-7.yaml"].is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Serves one shared Nacos client to local processes over HTTP, on TCP or a Unix socket.
+//!
+//! Configured through the environment:
+//! - The client, as in `nacos::ClientConfig::from_env`, e.g. `NACOS_SERVER_ADDR`.
+//! - `SIDECAR_LISTEN`: `ip:port` or `unix:/path`, default `127.0.0.1:8849`.
+//!
+//! Routes:
+//! - `GET /config?dataId=`: the config content.
+//! - `GET /config/listen?dataId=&md5=`: the content once its md5 differs from `md5`, or
+//!   `304 Not Modified` after `timeoutMs` (default 30000).
+//! - `GET /instances?serviceName=&healthyOnly=`: instances as JSON.
+
+use std::{collections::HashMap, convert::Infallible, time::Duration};
+
+use hyper::{
+    header::CONTENT_TYPE, server::conn::Http, service::service_fn, Body, Method, Request, Response,
+    StatusCode,
+};
+use md5::{Digest, Md5};
+use nacos::Nacos;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:8849";
+const DEFAULT_LISTEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("nacos-sidecar: {e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let nacos = Nacos::from_env()?;
+    let listen = std::env::var("SIDECAR_LISTEN").unwrap_or_else(|_| DEFAULT_LISTEN.into());
+
+    #[cfg(unix)]
+    if let Some(path) = listen.strip_prefix("unix:") {
+        // A socket left over from a previous run would make binding fail.
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path)?;
+        loop {
+            let (stream, _) = listener.accept().await?;
+            serve(nacos.clone(), stream);
+        }
+    }
+    let listener = tokio::net::TcpListener::bind(&listen).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        serve(nacos.clone(), stream);
+    }
+}
+
+fn serve<S>(nacos: Nacos, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let service = service_fn(move |request| {
+            let nacos = nacos.clone();
+            async move { Ok::<_, Infallible>(handle(&nacos, request).await) }
+        });
+        if let Err(e) = Http::new().serve_connection(stream, service).await {
+            eprintln!("nacos-sidecar: connection failed: {e}");
+        }
+    });
+}
+
+async fn handle(nacos: &Nacos, request: Request<Body>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return error(StatusCode::METHOD_NOT_ALLOWED, "only GET is supported");
+    }
+    let query: HashMap<String, String> =
+        match serde_urlencoded::from_str(request.uri().query().unwrap_or_default()) {
+            Ok(query) => query,
+            Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+    let param = |name: &str| query.get(name).map(String::as_str);
+    match (request.uri().path(), param("dataId"), param("serviceName")) {
+        ("/config", Some(data_id), _) => match nacos.get_config(data_id).await {
+            Ok(content) => Response::new(content.into()),
+            Err(e) => nacos_error(e),
+        },
+        ("/config/listen", Some(data_id), _) => {
+            let timeout = param("timeoutMs")
+                .and_then(|millis| millis.parse().ok())
+                .map_or(DEFAULT_LISTEN_TIMEOUT, Duration::from_millis);
+            let listen = listen(nacos, data_id, param("md5").unwrap_or_default());
+            match tokio::time::timeout(timeout, listen).await {
+                Ok(Ok(content)) => Response::new(content.into()),
+                Ok(Err(e)) => nacos_error(e),
+                Err(_) => empty(StatusCode::NOT_MODIFIED),
+            }
+        }
+        ("/instances", _, Some(service_name)) => {
+            let healthy_only = param("healthyOnly") == Some("true");
+            match nacos.list_instances(service_name, healthy_only).await {
+                Ok(instances) => {
+                    let mut response =
+                        Response::new(serde_json::to_vec(&instances).unwrap().into());
+                    response
+                        .headers_mut()
+                        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+                    response
+                }
+                Err(e) => nacos_error(e),
+            }
+        }
+        ("/config" | "/config/listen", None, _) => {
+            error(StatusCode::BAD_REQUEST, "dataId is required")
+        }
+        ("/instances", _, None) => error(StatusCode::BAD_REQUEST, "serviceName is required"),
+        _ => empty(StatusCode::NOT_FOUND),
+    }
+}
+
+/// The content once its md5 differs from `md5`, the caller's: right away if the caller is
+/// behind, or else at the next change. Callers share the sidecar's listener, which is
+/// compared against what each caller has seen rather than what the sidecar last fetched.
+async fn listen(nacos: &Nacos, data_id: &str, md5: &str) -> nacos::Result<bytes::Bytes> {
+    let current = match nacos.get_cached_config(data_id) {
+        Some(content) => content,
+        None => nacos.get_config(data_id).await?,
+    };
+    if hex::encode(Md5::digest(&current)) != md5 {
+        return Ok(current);
+    }
+    loop {
+        let content = nacos.wait_for_new_config(data_id).await?;
+        if hex::encode(Md5::digest(&content)) != md5 {
+            return Ok(content);
+        }
+    }
+}
+
+fn nacos_error(e: nacos::Error) -> Response<Body> {
+    let status = if e.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::BAD_GATEWAY
+    };
+    error(status, &e.to_string())
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(format!("{message}\n").into());
+    *response.status_mut() = status;
+    response
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
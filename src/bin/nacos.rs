@@ -0,0 +1,101 @@
+//! Command line client for operators and CI pipelines.
+//!
+//! The client is configured by the `NACOS_*` variables of [`ClientConfig::from_env`], e.g.
+//! `NACOS_SERVER_ADDR`, `NACOS_USERNAME` and `NACOS_PASSWORD`, which `--server`,
+//! `--namespace`, `--group` and `--https` override. Servers are given like the Java client's
+//! `serverAddr`, e.g. `nacos-1:8848,nacos-2:8848`.
+
+use std::{io::Read, process::ExitCode};
+
+use nacos::{ClientConfig, ConfigFormat, Nacos};
+
+const USAGE: &str = "\
+usage: nacos [--server host:port,...] [--namespace id] [--group group] [--https] <command>
+
+commands:
+  get <data-id>                      print a config
+  publish <data-id> [file] [--type t] publish a file, or stdin without one
+  delete <data-id>                   delete a config
+  listen <data-id>                   print a config and every new value of it
+  instances <service> [--healthy]    list instances of a service as JSON lines
+";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("nacos: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+type CliResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
+
+async fn run(args: Vec<String>) -> CliResult {
+    let mut config = ClientConfig::from_env()?;
+    let mut config_type = None;
+    let mut healthy = false;
+    let mut positional = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} needs a value"));
+        match arg.as_str() {
+            "--server" => config.server_addr = Some(value("--server")?),
+            "--namespace" => config.namespace = Some(value("--namespace")?),
+            "--group" => config.group = Some(value("--group")?),
+            "--type" => config_type = Some(value("--type")?),
+            "--https" => config.https = true,
+            "--healthy" => healthy = true,
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}").into()),
+            _ => positional.push(arg),
+        }
+    }
+
+    if config.server_addr.is_none() {
+        return Err("no server, pass --server or set NACOS_SERVER_ADDR".into());
+    }
+    let nacos = Nacos::from_config(&config)?;
+    match positional.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["get", data_id] => print!("{}", nacos.get_config_string(data_id).await?),
+        ["publish", data_id, ref file @ ..] if file.len() <= 1 => {
+            let content = match file.first() {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => {
+                    let mut content = String::new();
+                    std::io::stdin().read_to_string(&mut content)?;
+                    content
+                }
+            };
+            let format = match &config_type {
+                Some(config_type) => Some(
+                    ConfigFormat::from_config_type(config_type)
+                        .ok_or(format!("unknown config type {config_type}"))?,
+                ),
+                None => file
+                    .first()
+                    .and_then(|path| path.rsplit_once('.'))
+                    .and_then(|(_, extension)| ConfigFormat::from_config_type(extension)),
+            };
+            nacos.publish_config(data_id, &content, format).await?;
+        }
+        ["delete", data_id] => nacos.delete_config(data_id).await?,
+        ["listen", data_id] => loop {
+            let content = nacos.wait_for_new_config(data_id).await?;
+            println!("{}", String::from_utf8_lossy(&content));
+        },
+        ["instances", service] => {
+            for instance in nacos.list_instances(service, healthy).await? {
+                println!("{}", serde_json::to_string(&instance)?);
+            }
+        }
+        _ => return Err(format!("invalid arguments\n{USAGE}").into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,14 @@
+use serde::de::DeserializeOwned;
+
+use crate::ConfigFormat;
+
+/// A config type bound to a data id, loaded with [`crate::Nacos::load`].
+///
+/// Usually implemented with `#[derive(NacosConfig)]` (requires the `derive` feature).
+pub trait NacosConfig: DeserializeOwned {
+    const DATA_ID: &'static str;
+    /// Group to read from instead of the client's.
+    const GROUP: Option<&'static str> = None;
+    /// Format to use instead of the config type reported by the server.
+    const FORMAT: Option<ConfigFormat> = None;
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use tokio::runtime::Runtime;
+
+use crate::{
+    live::{AbortOnDrop, RETRY_DELAY},
+    ConfigFormat, Nacos, Result,
+};
+
+/// A synchronous client for programs that don't run an async runtime, wrapping [`Nacos`]
+/// with a runtime of its own. Clones share the runtime.
+///
+/// Its methods block the calling thread and panic when called from async code.
+#[derive(Clone)]
+pub struct BlockingNacos {
+    nacos: Nacos,
+    runtime: Arc<Runtime>,
+}
+
+/// Watches a config for [`BlockingNacos::watch`] until dropped.
+pub struct BlockingWatch {
+    _task: AbortOnDrop,
+}
+
+impl BlockingNacos {
+    /// Starts a runtime with a worker thread, which runs watches in the background.
+    pub fn new(nacos: Nacos) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("nacos-blocking")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            nacos,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The wrapped async client.
+    pub fn nacos(&self) -> &Nacos {
+        &self.nacos
+    }
+
+    pub fn get_config(&self, data_id: &str) -> Result<Bytes> {
+        self.runtime.block_on(self.nacos.get_config(data_id))
+    }
+
+    /// See [`Nacos::get_config_string`].
+    pub fn get_config_string(&self, data_id: &str) -> Result<String> {
+        self.runtime.block_on(self.nacos.get_config_string(data_id))
+    }
+
+    /// See [`Nacos::get_config_as`].
+    pub fn get_config_as<T: DeserializeOwned>(
+        &self,
+        data_id: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<T> {
+        self.runtime
+            .block_on(self.nacos.get_config_as(data_id, format))
+    }
+
+    /// See [`Nacos::publish_config`].
+    pub fn publish_config(
+        &self,
+        data_id: &str,
+        content: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        self.runtime
+            .block_on(self.nacos.publish_config(data_id, content, format))
+    }
+
+    pub fn delete_config(&self, data_id: &str) -> Result<()> {
+        self.runtime.block_on(self.nacos.delete_config(data_id))
+    }
+
+    /// Fetches a config and calls `on_change` with it, then again with every new value until
+    /// the returned watch is dropped. `on_change` runs on the worker thread; failures to watch
+    /// are logged and retried.
+    pub fn watch<F>(&self, data_id: &str, mut on_change: F) -> Result<BlockingWatch>
+    where
+        F: FnMut(Bytes) + Send + 'static,
+    {
+        let initial = self
+            .runtime
+            .block_on(self.nacos.wait_for_new_config(data_id))?;
+        on_change(initial);
+
+        let nacos = self.nacos.clone();
+        let data_id = data_id.to_string();
+        let (task, abort) = futures_util::future::abortable(async move {
+            loop {
+                match nacos.wait_for_new_config(&data_id).await {
+                    // Let other watches run while the callback blocks.
+                    Ok(content) => tokio::task::block_in_place(|| on_change(content)),
+                    Err(e) => {
+                        tracing::warn!(data_id, error = %e, "Failed to watch config");
+                        nacos.sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        });
+        self.runtime.spawn(task);
+        Ok(BlockingWatch {
+            _task: AbortOnDrop(vec![abort]),
+        })
+    }
+}
+
+impl Nacos {
+    /// Wraps this client in a [`BlockingNacos`].
+    pub fn into_blocking(self) -> Result<BlockingNacos> {
+        BlockingNacos::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn blocking() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .into_blocking()
+        .unwrap();
+        assert!(nacos.get_config("app.yaml").is_err());
+        assert!(nacos.publish_config("app.yaml", "a: 1", None).is_err());
+        assert!(nacos.watch("app.yaml", |_| {}).is_err());
+    }
+}
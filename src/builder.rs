@@ -0,0 +1,266 @@
+use std::{fmt, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    servers::{parse_server_list, ServerList, DEFAULT_PORT},
+    Nacos, Result,
+};
+
+/// Builds a [`Nacos`] client, see [`Nacos::builder`].
+#[derive(Clone)]
+pub struct NacosBuilder {
+    server: String,
+    use_https: bool,
+    namespace: Option<String>,
+    group: String,
+    #[cfg(feature = "auth")]
+    credentials: Option<(String, String)>,
+    #[cfg(feature = "access-key")]
+    access_key: Option<(String, String)>,
+    app_name: Option<String>,
+    tag: Option<String>,
+    timeout: Option<Duration>,
+    max_config_size: Option<usize>,
+    snapshot_dir: Option<PathBuf>,
+    access_log: bool,
+}
+
+impl Default for NacosBuilder {
+    fn default() -> Self {
+        Self {
+            server: format!("127.0.0.1:{}", DEFAULT_PORT),
+            use_https: false,
+            namespace: None,
+            group: "DEFAULT_GROUP".into(),
+            #[cfg(feature = "auth")]
+            credentials: None,
+            #[cfg(feature = "access-key")]
+            access_key: None,
+            app_name: None,
+            tag: None,
+            timeout: None,
+            max_config_size: None,
+            snapshot_dir: None,
+            access_log: false,
+        }
+    }
+}
+
+impl fmt::Debug for NacosBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("NacosBuilder");
+        debug
+            .field("server", &self.server)
+            .field("use_https", &self.use_https)
+            .field("namespace", &self.namespace)
+            .field("group", &self.group);
+        #[cfg(feature = "auth")]
+        debug.field(
+            "credentials",
+            &self
+                .credentials
+                .as_ref()
+                .map(|(username, _)| (username, "***")),
+        );
+        #[cfg(feature = "access-key")]
+        debug.field(
+            "access_key",
+            &self.access_key.as_ref().map(|(id, _)| (id, "***")),
+        );
+        debug
+            .field("app_name", &self.app_name)
+            .field("tag", &self.tag)
+            .field("timeout", &self.timeout)
+            .field("max_config_size", &self.max_config_size)
+            .field("snapshot_dir", &self.snapshot_dir)
+            .field("access_log", &self.access_log)
+            .finish()
+    }
+}
+
+impl Nacos {
+    /// A builder for a client of `127.0.0.1:8848`, in the public namespace and `DEFAULT_GROUP`.
+    pub fn builder() -> NacosBuilder {
+        NacosBuilder::default()
+    }
+}
+
+impl NacosBuilder {
+    /// Servers like the Java client's `serverAddr`: comma-separated `host[:port]` entries,
+    /// optionally prefixed with `http://` or `https://`. The port is 8848 by default. When a
+    /// server can't be reached, requests move on to the next one.
+    pub fn server(mut self, server: impl Into<String>) -> Self {
+        self.server = server.into();
+        self
+    }
+
+    /// Whether servers given without a scheme use HTTPS.
+    pub fn https(mut self, use_https: bool) -> Self {
+        self.use_https = use_https;
+        self
+    }
+
+    /// Id of the namespace, the public one if empty or `public`.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.group = group.into();
+        self
+    }
+
+    /// See [`Nacos::with_credentials`].
+    #[cfg(feature = "auth")]
+    pub fn auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// See [`Nacos::with_access_key`].
+    #[cfg(feature = "access-key")]
+    pub fn access_key(mut self, id: impl Into<String>, secret: impl Into<String>) -> Self {
+        self.access_key = Some((id.into(), secret.into()));
+        self
+    }
+
+    /// See [`Nacos::with_app_name`].
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// See [`Nacos::with_tag`].
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// See [`Nacos::with_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Nacos::with_max_config_size`].
+    pub fn max_config_size(mut self, bytes: usize) -> Self {
+        self.max_config_size = Some(bytes);
+        self
+    }
+
+    /// See [`Nacos::with_snapshot_dir`].
+    pub fn snapshot_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.snapshot_dir = Some(dir.into());
+        self
+    }
+
+    /// See [`Nacos::with_access_log`].
+    pub fn access_log(mut self, enabled: bool) -> Self {
+        self.access_log = enabled;
+        self
+    }
+
+    /// Like [`Self::build`], also checking that the server can be reached and accepts the
+    /// credentials, to fail at startup rather than on the first request.
+    pub async fn connect(self) -> Result<Nacos> {
+        let nacos = self.build()?;
+        nacos.check_connection().await?;
+        Ok(nacos)
+    }
+
+    /// Fails if the server address or group is invalid. The server is only contacted on first
+    /// use, see [`Self::connect`].
+    pub fn build(self) -> Result<Nacos> {
+        let servers = parse_server_list(&self.server, self.use_https)?;
+        crate::ids::validate("group", &self.group, crate::Group::MAX_LEN)?;
+        let placeholder = SocketAddr::from(([127, 0, 0, 1], DEFAULT_PORT));
+        let mut nacos = Nacos::new(false, placeholder, self.namespace, self.group);
+        nacos.servers = Arc::new(ServerList::new(servers));
+        #[cfg(feature = "auth")]
+        if let Some((username, password)) = self.credentials {
+            nacos = nacos.with_credentials(username, password);
+        }
+        #[cfg(feature = "access-key")]
+        if let Some((id, secret)) = self.access_key {
+            nacos = nacos.with_access_key(id, secret);
+        }
+        if let Some(app_name) = self.app_name {
+            nacos = nacos.with_app_name(app_name);
+        }
+        if let Some(tag) = self.tag {
+            nacos = nacos.with_tag(tag);
+        }
+        if let Some(timeout) = self.timeout {
+            nacos = nacos.with_timeout(timeout);
+        }
+        if let Some(bytes) = self.max_config_size {
+            nacos = nacos.with_max_config_size(bytes);
+        }
+        if let Some(dir) = self.snapshot_dir {
+            nacos = nacos.with_snapshot_dir(dir);
+        }
+        if self.access_log {
+            nacos = nacos.with_access_log();
+        }
+        Ok(nacos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn debug() {
+        let builder = Nacos::builder().auth("nacos", "hunter2");
+        let debug = format!("{:?}", builder);
+        assert!(
+            debug.contains(r#"credentials: Some(("nacos", "***"))"#),
+            "{}",
+            debug
+        );
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn builder() {
+        let builder = Nacos::builder()
+            .server("10.0.0.1")
+            .namespace("dev")
+            .group("APP");
+        #[cfg(feature = "auth")]
+        let builder = builder.auth("nacos", "secret");
+        let nacos = builder.build().unwrap();
+        assert_eq!(nacos.make_url("/"), "http://10.0.0.1:8848/");
+        assert_eq!(nacos.namespace.as_deref(), Some("dev"));
+        assert_eq!(nacos.group, "APP");
+
+        let default = Nacos::builder().build().unwrap();
+        assert_eq!(default.make_url("/"), "http://127.0.0.1:8848/");
+        assert_eq!(default.group, "DEFAULT_GROUP");
+
+        let cluster = Nacos::builder()
+            .server("nacos-1:8848,nacos-2:8848")
+            .https(true)
+            .build()
+            .unwrap();
+        assert_eq!(cluster.make_url("/"), "https://nacos-1:8848/");
+        assert!(matches!(
+            Nacos::builder().server("nacos:x").build(),
+            Err(crate::Error::InvalidServer(_))
+        ));
+        assert!(Nacos::builder().group("my group").build().is_err());
+    }
+
+    #[tokio::test]
+    async fn connect() {
+        let error = Nacos::builder()
+            .server("127.0.0.1:1")
+            .connect()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, crate::Error::Request { source, .. } if source.is_connect()));
+    }
+}
@@ -0,0 +1,99 @@
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_8};
+use reqwest::header::HeaderValue;
+
+use crate::{FetchedConfig, Nacos, Result};
+
+/// How to decode config content into UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// Decodes with the charset declared in the response's `Content-Type` (UTF-8 if absent),
+    /// falling back to `fallback` when the content is not valid in it.
+    Detect { fallback: &'static Encoding },
+    /// Always decodes with this encoding.
+    Fixed(&'static Encoding),
+}
+
+impl Charset {
+    /// Decodes `content`, replacing malformed sequences. `declared` is the charset the server
+    /// declared for it, if any.
+    pub fn decode<'a>(
+        self,
+        content: &'a [u8],
+        declared: Option<&'static Encoding>,
+    ) -> Cow<'a, str> {
+        match self {
+            Self::Detect { fallback } => declared
+                .unwrap_or(UTF_8)
+                .decode_without_bom_handling_and_without_replacement(content)
+                .unwrap_or_else(|| fallback.decode_without_bom_handling(content).0),
+            Self::Fixed(encoding) => encoding.decode_without_bom_handling(content).0,
+        }
+    }
+}
+
+/// Extracts the charset of a `Content-Type` header value.
+pub(crate) fn declared_encoding(content_type: &HeaderValue) -> Option<&'static Encoding> {
+    let content_type = content_type.to_str().ok()?;
+    let charset = content_type
+        .split(';')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("charset"))?
+        .1;
+    Encoding::for_label(charset.trim_matches('"').as_bytes())
+}
+
+impl Nacos {
+    /// Makes the typed APIs decode configs with `charset` before parsing them.
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    /// Fetches a config as a string decoded with `charset`, regardless of the client's.
+    pub async fn get_config_decoded(&self, data_id: &str, charset: Charset) -> Result<String> {
+        let config = self.fetch_config(&self.group, data_id).await?;
+        Ok(charset
+            .decode(&config.content, config.declared_encoding)
+            .into_owned())
+    }
+}
+
+impl FetchedConfig {
+    /// Content decoded with the client's charset, if it has one.
+    pub(crate) fn utf8_content(&self) -> Cow<'_, [u8]> {
+        match self.charset {
+            Some(charset) => match charset.decode(&self.content, self.declared_encoding) {
+                Cow::Borrowed(content) => Cow::Borrowed(content.as_bytes()),
+                Cow::Owned(content) => Cow::Owned(content.into_bytes()),
+            },
+            None => Cow::Borrowed(&self.content),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{GBK, WINDOWS_1252};
+
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let (gbk, _, _) = GBK.encode("名称=测试");
+        let detect = Charset::Detect { fallback: GBK };
+        assert_eq!(detect.decode(&gbk, None), "名称=测试");
+        assert_eq!(detect.decode("名称".as_bytes(), None), "名称");
+        assert_eq!(Charset::Fixed(GBK).decode(&gbk, Some(UTF_8)), "名称=测试");
+        assert_eq!(detect.decode(b"caf\xe9", Some(WINDOWS_1252)), "café");
+    }
+
+    #[test]
+    fn content_type_charset() {
+        let declared = |value| declared_encoding(&HeaderValue::from_static(value));
+        assert_eq!(declared("text/plain;charset=GBK"), Some(GBK));
+        assert_eq!(declared("text/plain; Charset=\"utf-8\""), Some(UTF_8));
+        assert_eq!(declared("text/plain"), None);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{ConfigFormat, Error, Nacos, NacosBuilder, Result};
+
+/// Settings of a client, as kept in a file next to the application's, see
+/// [`Nacos::from_config`]. Missing settings take the defaults of [`Nacos::builder`].
+///
+/// ```toml
+/// server_addr = "10.0.0.1:8848,10.0.0.2:8848"
+/// namespace = "dev"
+/// username = "nacos"
+/// password = "nacos"
+/// timeout_ms = 5000
+/// snapshot_dir = "/var/cache/nacos"
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Comma-separated, see [`NacosBuilder::server`].
+    pub server_addr: Option<String>,
+    pub https: bool,
+    pub namespace: Option<String>,
+    pub group: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Requires the `access-key` feature.
+    pub access_key: Option<String>,
+    pub secret_key: Option<String>,
+    pub app_name: Option<String>,
+    pub tag: Option<String>,
+    /// See [`Nacos::with_timeout`].
+    pub timeout_ms: Option<u64>,
+    pub max_config_size: Option<usize>,
+    pub snapshot_dir: Option<PathBuf>,
+    pub access_log: bool,
+}
+
+impl fmt::Debug for ClientConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientConfig")
+            .field("server_addr", &self.server_addr)
+            .field("https", &self.https)
+            .field("namespace", &self.namespace)
+            .field("group", &self.group)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("access_key", &self.access_key)
+            .field("secret_key", &self.secret_key.as_ref().map(|_| "***"))
+            .field("app_name", &self.app_name)
+            .field("tag", &self.tag)
+            .field("timeout_ms", &self.timeout_ms)
+            .field("max_config_size", &self.max_config_size)
+            .field("snapshot_dir", &self.snapshot_dir)
+            .field("access_log", &self.access_log)
+            .finish()
+    }
+}
+
+impl ClientConfig {
+    /// Reads a JSON, YAML or TOML file, picking the format from its extension.
+    pub fn from_file(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let format = ConfigFormat::from_config_type(extension)
+            .ok_or_else(|| Error::UnknownConfigType(Some(extension.to_string())))?;
+        format.deserialize(&std::fs::read(&path)?)
+    }
+
+    /// Reads `NACOS_SERVER_ADDR`, `NACOS_HTTPS`, `NACOS_NAMESPACE`, `NACOS_GROUP`,
+    /// `NACOS_USERNAME`, `NACOS_PASSWORD`, `NACOS_ACCESS_KEY`, `NACOS_SECRET_KEY`,
+    /// `NACOS_APP_NAME`, `NACOS_TAG`, `NACOS_TIMEOUT_MS`, `NACOS_MAX_CONFIG_SIZE` and
+    /// `NACOS_SNAPSHOT_DIR`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        fn parse<T: std::str::FromStr>(
+            name: &str,
+            value: Option<String>,
+            expected: &'static str,
+        ) -> Result<Option<T>> {
+            value
+                .map(|value| {
+                    value.parse().map_err(|_| Error::InvalidValue {
+                        key: name.to_string(),
+                        value,
+                        expected,
+                    })
+                })
+                .transpose()
+        }
+        Ok(Self {
+            server_addr: var("NACOS_SERVER_ADDR"),
+            https: parse("NACOS_HTTPS", var("NACOS_HTTPS"), "a boolean")?.unwrap_or(false),
+            namespace: var("NACOS_NAMESPACE"),
+            group: var("NACOS_GROUP"),
+            username: var("NACOS_USERNAME"),
+            password: var("NACOS_PASSWORD"),
+            access_key: var("NACOS_ACCESS_KEY"),
+            secret_key: var("NACOS_SECRET_KEY"),
+            app_name: var("NACOS_APP_NAME"),
+            tag: var("NACOS_TAG"),
+            timeout_ms: parse("NACOS_TIMEOUT_MS", var("NACOS_TIMEOUT_MS"), "milliseconds")?,
+            max_config_size: parse(
+                "NACOS_MAX_CONFIG_SIZE",
+                var("NACOS_MAX_CONFIG_SIZE"),
+                "a number of bytes",
+            )?,
+            snapshot_dir: var("NACOS_SNAPSHOT_DIR").map(PathBuf::from),
+            access_log: false,
+        })
+    }
+
+    pub fn builder(&self) -> Result<NacosBuilder> {
+        let mut builder = Nacos::builder().https(self.https);
+        if let Some(server_addr) = &self.server_addr {
+            builder = builder.server(server_addr);
+        }
+        if let Some(namespace) = &self.namespace {
+            builder = builder.namespace(namespace);
+        }
+        if let Some(group) = &self.group {
+            builder = builder.group(group);
+        }
+        match (&self.username, &self.password) {
+            #[cfg(feature = "auth")]
+            (Some(username), Some(password)) => builder = builder.auth(username, password),
+            #[cfg(not(feature = "auth"))]
+            (Some(_), Some(_)) => return Err(Error::FeatureRequired("username", "auth")),
+            (None, None) => {}
+            _ => return Err(incomplete("username", "password")),
+        }
+        match (&self.access_key, &self.secret_key) {
+            #[cfg(feature = "access-key")]
+            (Some(id), Some(secret)) => builder = builder.access_key(id, secret),
+            #[cfg(not(feature = "access-key"))]
+            (Some(_), Some(_)) => return Err(Error::FeatureRequired("access_key", "access-key")),
+            (None, None) => {}
+            _ => return Err(incomplete("access_key", "secret_key")),
+        }
+        if let Some(app_name) = &self.app_name {
+            builder = builder.app_name(app_name);
+        }
+        if let Some(tag) = &self.tag {
+            builder = builder.tag(tag);
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout_ms));
+        }
+        if let Some(bytes) = self.max_config_size {
+            builder = builder.max_config_size(bytes);
+        }
+        if let Some(dir) = &self.snapshot_dir {
+            builder = builder.snapshot_dir(dir);
+        }
+        Ok(builder.access_log(self.access_log))
+    }
+}
+
+/// Settings that must be given together.
+fn incomplete(first: &'static str, second: &'static str) -> Error {
+    Error::InvalidValue {
+        key: format!("{} and {}", first, second),
+        value: String::new(),
+        expected: "both or neither",
+    }
+}
+
+impl Nacos {
+    /// Builds a client from settings kept in a file, see [`ClientConfig::from_file`].
+    pub fn from_config(config: &ClientConfig) -> Result<Self> {
+        config.builder()?.build()
+    }
+
+    /// Builds a client configured by environment variables, see [`ClientConfig::from_env`].
+    pub fn from_env() -> Result<Self> {
+        Self::from_config(&ClientConfig::from_env()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn client_config() {
+        let config: ClientConfig = ConfigFormat::Json
+            .deserialize(
+                br#"{"server_addr": "10.0.0.1:80", "namespace": "dev", "username": "nacos",
+                     "password": "nacos", "timeout_ms": 5000}"#,
+            )
+            .unwrap();
+        assert_eq!(config.timeout_ms, Some(5000));
+        #[cfg(feature = "auth")]
+        {
+            let nacos = Nacos::from_config(&config).unwrap();
+            assert_eq!(nacos.make_url("/"), "http://10.0.0.1:80/");
+            assert_eq!(nacos.timeout, Some(Duration::from_secs(5)));
+        }
+        #[cfg(not(feature = "auth"))]
+        assert!(matches!(
+            Nacos::from_config(&config),
+            Err(Error::FeatureRequired("username", "auth"))
+        ));
+        let debug = format!("{:?}", config);
+        assert!(debug.contains(r#"password: Some("***")"#), "{}", debug);
+
+        let config = ClientConfig {
+            username: Some("nacos".into()),
+            ..Default::default()
+        };
+        assert!(Nacos::from_config(&config).is_err());
+        assert!(ConfigFormat::Json
+            .deserialize::<ClientConfig>(br#"{"server": "10.0.0.1"}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn from_env() {
+        let vars = HashMap::from([
+            ("NACOS_SERVER_ADDR", "10.0.0.1:8848"),
+            ("NACOS_HTTPS", "true"),
+            ("NACOS_USERNAME", "nacos"),
+            ("NACOS_TIMEOUT_MS", "100"),
+        ]);
+        let config = ClientConfig::from_vars(|name| vars.get(name).map(|v| v.to_string()));
+        let config = config.unwrap();
+        assert_eq!(config.server_addr.as_deref(), Some("10.0.0.1:8848"));
+        assert!(config.https);
+        assert_eq!(config.username.as_deref(), Some("nacos"));
+        assert_eq!(config.timeout_ms, Some(100));
+
+        let invalid = ClientConfig::from_vars(|name| {
+            (name == "NACOS_TIMEOUT_MS").then(|| "soon".to_string())
+        });
+        assert!(matches!(invalid, Err(Error::InvalidValue { .. })));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Error, Nacos, Result};
+
+/// A node of the Nacos cluster, see [`Nacos::cluster_nodes`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterNode {
+    pub ip: String,
+    pub port: u16,
+    /// `UP`, `DOWN`, `SUSPICIOUS`, `STARTING` or `ISOLATION`.
+    pub state: String,
+    #[serde(default)]
+    pub address: String,
+    /// Failed health checks by the node answering the query.
+    #[serde(default)]
+    pub fail_access_cnt: u32,
+    /// Version, raft metadata and other details that vary by server version.
+    #[serde(default)]
+    pub extend_info: Value,
+}
+
+impl ClusterNode {
+    pub fn is_up(&self) -> bool {
+        self.state == "UP"
+    }
+}
+
+/// State of the server answering, see [`Nacos::server_state`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ServerState {
+    pub version: Option<String>,
+    /// `standalone` or `cluster`.
+    pub standalone_mode: Option<String>,
+    /// `config`, `naming`, or none if the server runs both.
+    pub function_mode: Option<String>,
+    /// Whether the readiness endpoint reported the server ready.
+    #[serde(skip)]
+    pub ready: bool,
+    /// Other state keys, which vary by server version.
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ServerState {
+    pub fn is_standalone(&self) -> bool {
+        self.standalone_mode.as_deref() == Some("standalone")
+    }
+}
+
+/// Naming load of the server answering, see [`Nacos::naming_metrics`]. Counts missing from
+/// older servers are zero.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NamingMetrics {
+    /// `UP` or `DOWN`.
+    pub status: String,
+    pub service_count: u64,
+    pub instance_count: u64,
+    /// Services and instances this node is responsible for.
+    pub responsible_service_count: u64,
+    pub responsible_instance_count: u64,
+    pub client_count: u64,
+    pub responsible_client_count: u64,
+    /// System load average.
+    pub load: f64,
+    /// CPU usage, from 0 to 1.
+    pub cpu: f64,
+    /// Heap usage, from 0 to 1.
+    pub mem: f64,
+}
+
+/// Naming switches of the cluster, see [`Nacos::switches`]. Switches without a field here are
+/// in `extra`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Switches {
+    pub health_check_enabled: Option<bool>,
+    pub auto_change_health_check_enabled: Option<bool>,
+    pub push_enabled: Option<bool>,
+    pub distro_enabled: Option<bool>,
+    pub light_beat_enabled: Option<bool>,
+    pub default_instance_ephemeral: Option<bool>,
+    pub client_beat_interval: Option<u64>,
+    pub default_push_cache_millis: Option<u64>,
+    pub default_cache_millis: Option<u64>,
+    pub check_times: Option<u32>,
+    pub distro_threshold: Option<f64>,
+    /// Set while an operator forces the server status, like `DOWN`.
+    pub overridden_server_status: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ServerList {
+    servers: Vec<ClusterNode>,
+}
+
+#[derive(Deserialize)]
+struct NodeList {
+    data: Vec<ClusterNode>,
+}
+
+impl Nacos {
+    /// Lists the cluster nodes as seen by the naming module.
+    pub async fn list_servers(&self) -> Result<Vec<ClusterNode>> {
+        let url = self.make_url("/nacos/v1/ns/operator/servers");
+        let request = self.client.get(url);
+        let response = self.send("nacos.cluster.servers", request, &[]).await?;
+        Ok(response.json::<ServerList>().await?.servers)
+    }
+
+    /// Lists the cluster nodes as seen by the core cluster module.
+    pub async fn cluster_nodes(&self) -> Result<Vec<ClusterNode>> {
+        let url = self.make_url("/nacos/v1/core/cluster/nodes");
+        let request = self.client.get(url);
+        let response = self.send("nacos.cluster.nodes", request, &[]).await?;
+        Ok(response.json::<NodeList>().await?.data)
+    }
+}
+
+impl Nacos {
+    /// Queries the server's version, mode and readiness, to wait for a server that is up but
+    /// still loading data. Fails only if the server can't be reached or its state is invalid.
+    pub async fn server_state(&self) -> Result<ServerState> {
+        let url = self.make_url("/nacos/v1/console/server/state");
+        let request = self.client.get(url);
+        let response = self.send("nacos.server.state", request, &[]).await?;
+        let mut state: ServerState = response.json().await?;
+
+        let url = self.make_url("/nacos/v1/console/health/readiness");
+        let request = self.client.get(url);
+        state.ready = match self.send("nacos.health", request, &[]).await {
+            Ok(_) => true,
+            Err(e) if e.status().is_some() => false,
+            Err(e) => return Err(e),
+        };
+        Ok(state)
+    }
+
+    pub async fn switches(&self) -> Result<Switches> {
+        let url = self.make_url("/nacos/v1/ns/operator/switches");
+        let request = self.client.get(url);
+        let response = self.send("nacos.naming.switches", request, &[]).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Changes a switch of the whole cluster, like `healthCheckEnabled`. With `debug`, only the
+    /// server answering changes it, which is useful to try a setting.
+    pub async fn update_switch(&self, entry: &str, value: &str, debug: bool) -> Result<()> {
+        let url = self.make_url("/nacos/v1/ns/operator/switches");
+        let request = self.client.put(url).query(&[
+            ("entry", entry),
+            ("value", value),
+            ("debug", if debug { "true" } else { "false" }),
+        ]);
+        let response = self
+            .send(
+                "nacos.naming.switches.update",
+                request,
+                &[("nacos.switch", entry)],
+            )
+            .await?;
+        match response.text().await?.trim() {
+            "ok" => Ok(()),
+            body => Err(Error::Refused(body.to_string())),
+        }
+    }
+
+    pub async fn naming_metrics(&self) -> Result<NamingMetrics> {
+        let url = self.make_url("/nacos/v1/ns/operator/metrics");
+        let request = self.client.get(url);
+        let response = self.send("nacos.naming.metrics", request, &[]).await?;
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes() {
+        let servers: ServerList = serde_json::from_str(
+            r#"{"servers": [{
+                "ip": "10.0.0.1", "port": 8848, "state": "UP", "address": "10.0.0.1:8848",
+                "failAccessCnt": 0, "extendInfo": {"version": "2.2.3"}, "abilities": {}
+            }]}"#,
+        )
+        .unwrap();
+        assert!(servers.servers[0].is_up());
+        assert_eq!(servers.servers[0].extend_info["version"], "2.2.3");
+
+        let nodes: NodeList = serde_json::from_str(
+            r#"{"code": 200, "message": null, "data": [
+                {"ip": "10.0.0.2", "port": 8848, "state": "SUSPICIOUS", "failAccessCnt": 3}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(!nodes.data[0].is_up());
+        assert_eq!(nodes.data[0].fail_access_cnt, 3);
+    }
+
+    #[test]
+    fn server_state() {
+        let state: ServerState = serde_json::from_str(
+            r#"{"version": "2.2.3", "standalone_mode": "standalone", "function_mode": null,
+                "auth_enabled": "false", "login_page_enabled": "true"}"#,
+        )
+        .unwrap();
+        assert_eq!(state.version.as_deref(), Some("2.2.3"));
+        assert!(state.is_standalone());
+        assert_eq!(state.function_mode, None);
+        assert_eq!(state.extra["auth_enabled"], "false");
+    }
+
+    #[test]
+    fn naming_metrics() {
+        let metrics: NamingMetrics = serde_json::from_str(
+            r#"{"status": "UP", "serviceCount": 12, "load": 0.5, "mem": 0.25, "cpu": 0.1,
+                "responsibleServiceCount": 6, "instanceCount": 30, "responsibleInstanceCount": 15,
+                "clientCount": 20, "connectionBasedClientCount": 18}"#,
+        )
+        .unwrap();
+        assert_eq!(metrics.service_count, 12);
+        assert_eq!(metrics.responsible_instance_count, 15);
+        assert_eq!(metrics.responsible_client_count, 0);
+        assert_eq!(metrics.load, 0.5);
+    }
+
+    #[test]
+    fn switches() {
+        let switches: Switches = serde_json::from_str(
+            r#"{"name": "00-00---000-NACOS_SWITCH_DOMAIN-000---00-00", "masters": null,
+                "healthCheckEnabled": true, "pushEnabled": false, "clientBeatInterval": 5000,
+                "distroThreshold": 0.7, "overriddenServerStatus": null, "checksum": null}"#,
+        )
+        .unwrap();
+        assert_eq!(switches.health_check_enabled, Some(true));
+        assert_eq!(switches.push_enabled, Some(false));
+        assert_eq!(switches.client_beat_interval, Some(5000));
+        assert_eq!(switches.overridden_server_status, None);
+        assert!(switches.extra.contains_key("name"));
+    }
+}
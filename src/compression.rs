@@ -0,0 +1,51 @@
+//! Snapshot compressions provided by features, see [`crate::Nacos::with_snapshot_compression`].
+
+#[cfg(feature = "snapshot-gzip")]
+use std::io::{Read, Write};
+
+use crate::SnapshotCompression;
+
+/// Compresses snapshots with gzip, with the `snapshot-gzip` feature.
+#[cfg(feature = "snapshot-gzip")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gzip;
+
+#[cfg(feature = "snapshot-gzip")]
+impl SnapshotCompression for Gzip {
+    fn name(&self) -> &str {
+        "gzip"
+    }
+
+    fn compress(&self, content: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content)?;
+        encoder.finish()
+    }
+
+    fn decompress(&self, compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        // A stream may be several members, as written by `cat a.gz b.gz`.
+        flate2::read::MultiGzDecoder::new(compressed).read_to_end(&mut content)?;
+        Ok(content)
+    }
+}
+
+/// Compresses snapshots with zstd at its default level, with the `snapshot-zstd` feature.
+#[cfg(feature = "snapshot-zstd")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zstd;
+
+#[cfg(feature = "snapshot-zstd")]
+impl SnapshotCompression for Zstd {
+    fn name(&self) -> &str {
+        "zstd"
+    }
+
+    fn compress(&self, content: &[u8]) -> std::io::Result<Vec<u8>> {
+        zstd::encode_all(content, zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    fn decompress(&self, compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+        zstd::decode_all(compressed)
+    }
+}
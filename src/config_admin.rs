@@ -0,0 +1,539 @@
+use std::{collections::HashMap, fmt};
+
+use bytes::Bytes;
+use serde::{Deserialize, Deserializer};
+
+use crate::{protocol, redact::Content, Error, Nacos, Page, Result};
+
+/// Clients listening to a config, see [`Nacos::config_listeners`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+pub struct ConfigListeners {
+    /// Client IP to the md5 of the value it last fetched.
+    #[serde(rename = "lisentersGroupkeyStatus", default)]
+    pub md5_by_ip: HashMap<String, String>,
+}
+
+impl ConfigListeners {
+    /// Whether every listener has fetched the value with `md5`, e.g. before deleting or moving
+    /// a config.
+    pub fn all_on(&self, md5: &str) -> bool {
+        self.md5_by_ip
+            .values()
+            .all(|listener_md5| listener_md5 == md5)
+    }
+}
+
+/// A config found by [`Nacos::search_configs`]. Its `Debug` output summarizes the content, see
+/// [`crate::set_log_content`].
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigInfo {
+    /// Server-side id, used to select configs for [`Nacos::export_configs`].
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub id: String,
+    pub data_id: String,
+    pub group: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub md5: Option<String>,
+    /// Namespace id, empty for the public namespace.
+    #[serde(default)]
+    pub tenant: String,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    /// Config type, like `yaml`.
+    #[serde(rename = "type", default)]
+    pub config_type: Option<String>,
+}
+
+/// A past value of a config, see [`Nacos::get_history_detail`]. Its `Debug` output summarizes
+/// the content, see [`crate::set_log_content`].
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// The `nid` of the entry.
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    pub data_id: String,
+    pub group: String,
+    #[serde(default)]
+    pub tenant: String,
+    #[serde(default)]
+    pub md5: Option<String>,
+    /// Empty in listings, only [`Nacos::get_history_detail`] includes it.
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub src_ip: Option<String>,
+    #[serde(default)]
+    pub src_user: Option<String>,
+    /// `I` for inserts, `U` for updates and `D` for deletes.
+    #[serde(default)]
+    pub op_type: Option<String>,
+    /// As formatted by the server.
+    #[serde(default)]
+    pub created_time: Option<String>,
+    #[serde(default)]
+    pub last_modified_time: Option<String>,
+}
+
+impl fmt::Debug for ConfigInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigInfo")
+            .field("id", &self.id)
+            .field("data_id", &self.data_id)
+            .field("group", &self.group)
+            .field("content", &Content(self.content.as_bytes()))
+            .field("md5", &self.md5)
+            .field("tenant", &self.tenant)
+            .field("app_name", &self.app_name)
+            .field("config_type", &self.config_type)
+            .finish()
+    }
+}
+
+impl fmt::Debug for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistoryEntry")
+            .field("id", &self.id)
+            .field("data_id", &self.data_id)
+            .field("group", &self.group)
+            .field("tenant", &self.tenant)
+            .field("md5", &self.md5)
+            .field("content", &Content(self.content.as_bytes()))
+            .field("src_ip", &self.src_ip)
+            .field("src_user", &self.src_user)
+            .field("op_type", &self.op_type)
+            .field("created_time", &self.created_time)
+            .field("last_modified_time", &self.last_modified_time)
+            .finish()
+    }
+}
+
+/// Config quota and usage of a namespace or group, see [`Nacos::namespace_capacity`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Capacity {
+    /// Maximum number of configs, 0 for the server default.
+    pub quota: u64,
+    /// Number of configs.
+    pub usage: u64,
+    /// Maximum size of a config in bytes, 0 for the server default.
+    pub max_size: u64,
+    pub max_aggr_count: u64,
+    pub max_aggr_size: u64,
+}
+
+impl Capacity {
+    /// Share of the quota in use, `None` without an explicit quota.
+    pub fn usage_ratio(&self) -> Option<f64> {
+        (self.quota > 0).then(|| self.usage as f64 / self.quota as f64)
+    }
+}
+
+#[derive(Deserialize)]
+struct CapacityResponse {
+    #[serde(default)]
+    data: Option<Capacity>,
+}
+
+/// What to do with imported configs that already exist, see [`Nacos::import_configs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Stop at the first existing config.
+    Abort,
+    /// Keep existing configs.
+    Skip,
+    /// Replace existing configs.
+    Overwrite,
+}
+
+impl ConflictPolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Abort => "ABORT",
+            Self::Skip => "SKIP",
+            Self::Overwrite => "OVERWRITE",
+        }
+    }
+}
+
+/// Outcome of [`Nacos::import_configs`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportResult {
+    #[serde(rename = "succCount")]
+    pub success_count: u64,
+    pub skip_count: u64,
+    #[serde(rename = "failData")]
+    pub failed: Vec<ConfigId>,
+    #[serde(rename = "skipData")]
+    pub skipped: Vec<ConfigId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigId {
+    pub data_id: String,
+    pub group: String,
+}
+
+#[derive(Deserialize)]
+struct ImportResponse {
+    code: u32,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    data: Option<ImportResult>,
+}
+
+impl ImportResponse {
+    /// Aborted imports answer an error code with what was imported before the conflict.
+    fn into_result(self) -> Result<ImportResult> {
+        match self {
+            Self {
+                code: 200, data, ..
+            } => Ok(data.unwrap_or_default()),
+            Self { message, .. } => Err(Error::Refused(message.unwrap_or_default())),
+        }
+    }
+}
+
+fn clone_body(configs: &[ConfigInfo]) -> Vec<serde_json::Value> {
+    configs
+        .iter()
+        .map(|config| {
+            let id: serde_json::Value = config
+                .id
+                .parse::<u64>()
+                .map_or_else(|_| config.id.clone().into(), Into::into);
+            serde_json::json!({"cfgId": id, "dataId": config.data_id, "group": config.group})
+        })
+        .collect()
+}
+
+/// Servers send ids as numbers or, to keep them exact in JavaScript, as strings.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(u64),
+    }
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(id) => id,
+        Id::Number(id) => id.to_string(),
+    })
+}
+
+impl Nacos {
+    /// Queries which clients listen to a config of the client's group, as seen by the server
+    /// answering.
+    pub async fn config_listeners(&self, data_id: &str) -> Result<ConfigListeners> {
+        let url = self.make_url("/nacos/v1/cs/configs/listener");
+        let key = self.config_key(&self.group, data_id);
+        let request = self.client.get(url).query(&protocol::config_params(&key));
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.data_id", data_id),
+        ];
+        let response = self
+            .send("nacos.config.listeners", request, &attributes)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Lists configs of the client's namespace, `page` starting from 1. Patterns containing `*`
+    /// use the fuzzy search, like `app-*.yaml`, others match exactly. An empty pattern matches
+    /// everything.
+    pub async fn search_configs(
+        &self,
+        data_id_pattern: &str,
+        group_pattern: &str,
+        page: u32,
+        page_size: u32,
+    ) -> Result<Page<ConfigInfo>> {
+        let blur = data_id_pattern.contains('*') || group_pattern.contains('*');
+        let url = self.make_url("/nacos/v1/cs/configs");
+        // The patterns take the place of the group and data id of a key.
+        let key = self.config_key(group_pattern, data_id_pattern);
+        let request = self
+            .client
+            .get(url)
+            .query(&protocol::config_params(&key))
+            .query(&[
+                ("search", if blur { "blur" } else { "accurate" }),
+                ("pageNo", &page.to_string()),
+                ("pageSize", &page_size.to_string()),
+            ]);
+        let attributes = [
+            ("nacos.group", group_pattern),
+            ("nacos.data_id", data_id_pattern),
+        ];
+        let response = self
+            .send("nacos.config.search", request, &attributes)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Exports configs of the client's namespace as a zip archive, as the console does. Without
+    /// `group` and `ids`, the whole namespace is exported.
+    pub async fn export_configs(&self, group: Option<&str>, ids: &[&str]) -> Result<Bytes> {
+        let url = self.make_url("/nacos/v1/cs/configs");
+        let tenant = self.namespace.as_deref().unwrap_or_default();
+        let mut request = self
+            .client
+            .get(url)
+            .query(&[("export", "true")])
+            .query(&protocol::tenant_params(tenant));
+        if let Some(group) = group {
+            request = request.query(&[("group", group)]);
+        }
+        if !ids.is_empty() {
+            request = request.query(&[("ids", ids.join(","))]);
+        }
+        let attributes = [("nacos.group", group.unwrap_or_default())];
+        let response = self
+            .send("nacos.config.export", request, &attributes)
+            .await?;
+        Ok(response.bytes().await?)
+    }
+
+    /// Imports a zip archive made by [`Self::export_configs`] or the console into the client's
+    /// namespace.
+    pub async fn import_configs(
+        &self,
+        archive: Bytes,
+        policy: ConflictPolicy,
+    ) -> Result<ImportResult> {
+        let url = self.make_url("/nacos/v1/cs/configs");
+        let file = reqwest::multipart::Part::stream(archive)
+            .file_name("nacos_config.zip")
+            .mime_str("application/zip")?;
+        let request = self
+            .client
+            .post(url)
+            .query(&[
+                ("import", "true"),
+                ("namespace", self.namespace.as_deref().unwrap_or_default()),
+                ("policy", policy.as_str()),
+            ])
+            .multipart(reqwest::multipart::Form::new().part("file", file));
+        let response = self.send("nacos.config.import", request, &[]).await?;
+        response.json::<ImportResponse>().await?.into_result()
+    }
+
+    /// Copies configs, e.g. found with [`Self::search_configs`], into `target_namespace`
+    /// under the same data ids and groups.
+    pub async fn clone_configs(
+        &self,
+        configs: &[ConfigInfo],
+        target_namespace: &str,
+        policy: ConflictPolicy,
+    ) -> Result<ImportResult> {
+        let tenant = if crate::namespace::is_public(target_namespace) {
+            ""
+        } else {
+            target_namespace
+        };
+        let url = self.make_url("/nacos/v1/cs/configs");
+        let request = self
+            .client
+            .post(url)
+            .query(&[
+                ("clone", "true"),
+                ("tenant", tenant),
+                ("policy", policy.as_str()),
+            ])
+            .json(&clone_body(configs));
+        let attributes = [("nacos.namespace", target_namespace)];
+        let response = self
+            .send("nacos.config.clone", request, &attributes)
+            .await?;
+        response.json::<ImportResponse>().await?.into_result()
+    }
+
+    /// Lists past values of a config of the client's group, newest first.
+    pub async fn list_history(
+        &self,
+        data_id: &str,
+        page: u32,
+        page_size: u32,
+    ) -> Result<Page<HistoryEntry>> {
+        let url = self.make_url("/nacos/v1/cs/history");
+        let request = self.history_request(url, data_id).query(&[
+            ("search", "accurate"),
+            ("pageNo", &page.to_string()),
+            ("pageSize", &page_size.to_string()),
+        ]);
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.data_id", data_id),
+        ];
+        let response = self
+            .send("nacos.history.list", request, &attributes)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Fetches a past value of a config with its content, `nid` being a [`HistoryEntry::id`].
+    /// Newer servers check that the entry belongs to `data_id` in the client's group.
+    pub async fn get_history_detail(&self, nid: &str, data_id: &str) -> Result<HistoryEntry> {
+        let url = self.make_url("/nacos/v1/cs/history");
+        let request = self.history_request(url, data_id).query(&[("nid", nid)]);
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.data_id", data_id),
+        ];
+        let response = self
+            .send("nacos.history.detail", request, &attributes)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Capacity of the client's namespace, which must not be the public one.
+    pub async fn namespace_capacity(&self) -> Result<Capacity> {
+        let namespace = self.namespace.as_deref().unwrap_or_default();
+        self.capacity(&[("namespaceId", namespace)]).await
+    }
+
+    pub async fn group_capacity(&self, group: &str) -> Result<Capacity> {
+        self.capacity(&[("group", group)]).await
+    }
+
+    /// A namespace or group without its own capacity record reports the defaults.
+    async fn capacity(&self, query: &[(&str, &str)]) -> Result<Capacity> {
+        let url = self.make_url("/nacos/v1/cs/capacity");
+        let request = self.client.get(url).query(query);
+        let response = self.send("nacos.config.capacity", request, &[]).await?;
+        Ok(response
+            .json::<CapacityResponse>()
+            .await?
+            .data
+            .unwrap_or_default())
+    }
+
+    fn history_request(&self, url: String, data_id: &str) -> reqwest::RequestBuilder {
+        let key = self.config_key(&self.group, data_id);
+        self.client.get(url).query(&protocol::config_params(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listeners() {
+        let listeners: ConfigListeners = serde_json::from_str(
+            r#"{"collectStatus": 200, "lisentersGroupkeyStatus": {
+                "10.0.0.1": "5d41402abc4b2a76b9719d911017c592",
+                "10.0.0.2": "7d793037a0760186574b0282f2f435e7"
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(listeners.md5_by_ip.len(), 2);
+        assert!(!listeners.all_on("5d41402abc4b2a76b9719d911017c592"));
+        assert!(ConfigListeners::default().all_on("anything"));
+    }
+
+    #[test]
+    fn search() {
+        let page: Page<ConfigInfo> = serde_json::from_str(
+            r#"{"totalCount": 1, "pageNumber": 1, "pagesAvailable": 1, "pageItems": [{
+                "id": 1, "dataId": "app-dev.yaml", "group": "DEFAULT_GROUP",
+                "content": "a: 1", "md5": null, "tenant": "", "appName": "", "type": "yaml"
+            }]}"#,
+        )
+        .unwrap();
+        let config = &page.page_items[0];
+        assert_eq!(config.id, "1");
+        assert_eq!(config.data_id, "app-dev.yaml");
+        assert_eq!(config.content, "a: 1");
+        assert_eq!(config.config_type.as_deref(), Some("yaml"));
+        assert!(format!("{:?}", config).contains(r#"content: <4 bytes, md5 "#));
+    }
+
+    #[test]
+    fn import() {
+        let response: ImportResponse = serde_json::from_str(
+            r#"{"code": 200, "message": "导入成功", "data": {
+                "succCount": 2, "skipCount": 1,
+                "skipData": [{"dataId": "app.yaml", "group": "DEFAULT_GROUP"}]
+            }}"#,
+        )
+        .unwrap();
+        let result = response.into_result().unwrap();
+        assert_eq!(result.success_count, 2);
+        assert_eq!(result.skipped[0].data_id, "app.yaml");
+
+        let response: ImportResponse =
+            serde_json::from_str(r#"{"code": 100002, "message": "导入失败", "data": null}"#)
+                .unwrap();
+        assert!(matches!(response.into_result(), Err(Error::Refused(_))));
+    }
+
+    #[test]
+    fn clone() {
+        let config = ConfigInfo {
+            id: "42".into(),
+            data_id: "app.yaml".into(),
+            group: "DEFAULT_GROUP".into(),
+            content: String::new(),
+            md5: None,
+            tenant: "staging".into(),
+            app_name: None,
+            config_type: None,
+        };
+        assert_eq!(
+            clone_body(&[config]),
+            [serde_json::json!({"cfgId": 42, "dataId": "app.yaml", "group": "DEFAULT_GROUP"})]
+        );
+    }
+
+    #[test]
+    fn history() {
+        let entry: HistoryEntry = serde_json::from_str(
+            r#"{"id": "203", "lastId": -1, "dataId": "app.yaml", "group": "DEFAULT_GROUP",
+                "tenant": "", "appName": "", "md5": "5d41402abc4b2a76b9719d911017c592",
+                "content": "a: 1", "srcIp": "10.0.0.1", "srcUser": "nacos", "opType": "U",
+                "createdTime": "2010-05-04T16:00:00.000+0000",
+                "lastModifiedTime": "2023-01-01T08:00:00.000+0000"}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.id, "203");
+        assert_eq!(entry.content, "a: 1");
+        assert_eq!(entry.op_type.as_deref(), Some("U"));
+    }
+
+    #[test]
+    fn capacity() {
+        let response: CapacityResponse = serde_json::from_str(
+            r#"{"code": 200, "message": null, "data": {
+                "id": 1, "quota": 200, "usage": 150, "maxSize": 102400, "maxAggrCount": 10000,
+                "maxAggrSize": 1024, "group": "DEFAULT_GROUP"
+            }}"#,
+        )
+        .unwrap();
+        let capacity = response.data.unwrap();
+        assert_eq!(capacity.usage_ratio(), Some(0.75));
+        assert_eq!(Capacity::default().usage_ratio(), None);
+    }
+
+    #[test]
+    fn history_params() {
+        let nacos = Nacos::new(
+            false,
+            "127.0.0.1:1".parse().unwrap(),
+            Some("dev".into()),
+            "MY GROUP".into(),
+        );
+        let url = nacos.make_url("/nacos/v1/cs/history");
+        let request = nacos.history_request(url, "app#1.yaml").build().unwrap();
+        assert_eq!(
+            request.url().query(),
+            Some("tenant=dev&group=MY+GROUP&dataId=app%231.yaml")
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+use config::{AsyncSource, ConfigError, Map, Source, Value as ConfigValue, ValueKind};
+use serde_json::Value;
+
+use crate::{ConfigFormat, Nacos, Result};
+
+/// A `config` crate [`AsyncSource`] fetching layered configs on every build, see
+/// [`Nacos::get_layered_config_as`].
+#[derive(Clone)]
+pub struct NacosSource {
+    nacos: Nacos,
+    data_ids: Vec<String>,
+    format: Option<ConfigFormat>,
+}
+
+impl NacosSource {
+    pub fn new<S: Into<String>>(
+        nacos: Nacos,
+        data_ids: impl IntoIterator<Item = S>,
+        format: Option<ConfigFormat>,
+    ) -> Self {
+        Self {
+            nacos,
+            data_ids: data_ids.into_iter().map(Into::into).collect(),
+            format,
+        }
+    }
+
+    /// Fetches the configs once, for synchronous builders.
+    pub async fn fetch(&self) -> Result<NacosSnapshot> {
+        Ok(NacosSnapshot {
+            origin: self.origin(),
+            tree: Arc::new(self.fetch_tree().await?),
+        })
+    }
+
+    async fn fetch_tree(&self) -> Result<Value> {
+        let data_ids: Vec<_> = self.data_ids.iter().map(String::as_str).collect();
+        self.nacos
+            .get_layered_config_as(&data_ids, self.format)
+            .await
+    }
+
+    fn origin(&self) -> String {
+        format!("nacos:{}", self.data_ids.join(","))
+    }
+}
+
+impl fmt::Debug for NacosSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NacosSource")
+            .field("data_ids", &self.data_ids)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl AsyncSource for NacosSource {
+    async fn collect(&self) -> Result<Map<String, ConfigValue>, ConfigError> {
+        let tree = self
+            .fetch_tree()
+            .await
+            .map_err(|e| ConfigError::Foreign(Box::new(e)))?;
+        Ok(to_table(&self.origin(), &tree))
+    }
+}
+
+/// Configs fetched by [`NacosSource::fetch`], usable as a synchronous [`Source`].
+#[derive(Debug, Clone)]
+pub struct NacosSnapshot {
+    origin: String,
+    tree: Arc<Value>,
+}
+
+impl Source for NacosSnapshot {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, ConfigValue>, ConfigError> {
+        Ok(to_table(&self.origin, &self.tree))
+    }
+}
+
+fn to_table(origin: &String, tree: &Value) -> Map<String, ConfigValue> {
+    match to_config_value(origin, tree).kind {
+        ValueKind::Table(table) => table,
+        _ => Map::new(),
+    }
+}
+
+fn to_config_value(origin: &String, value: &Value) -> ConfigValue {
+    let kind = match value {
+        Value::Null => ValueKind::Nil,
+        Value::Bool(b) => ValueKind::Boolean(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => ValueKind::I64(i),
+            (None, Some(u)) => ValueKind::U64(u),
+            _ => ValueKind::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => ValueKind::String(s.clone()),
+        Value::Array(items) => ValueKind::Array(
+            items
+                .iter()
+                .map(|item| to_config_value(origin, item))
+                .collect(),
+        ),
+        Value::Object(map) => ValueKind::Table(
+            map.iter()
+                .map(|(k, v)| (k.clone(), to_config_value(origin, v)))
+                .collect(),
+        ),
+    };
+    ConfigValue::new(Some(origin), kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use config::Config;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn snapshot_source() {
+        let snapshot = NacosSnapshot {
+            origin: "nacos:app.yaml".into(),
+            tree: Arc::new(json!({"server": {"port": "8080", "hosts": ["a", "b"]}})),
+        };
+        let config = Config::builder()
+            .set_default("server.debug", true)
+            .unwrap()
+            .add_source(snapshot)
+            .build()
+            .unwrap();
+        assert_eq!(config.get::<u16>("server.port").unwrap(), 8080);
+        assert_eq!(
+            config.get::<Vec<String>>("server.hosts").unwrap(),
+            ["a", "b"]
+        );
+        assert!(config.get_bool("server.debug").unwrap());
+    }
+}
@@ -0,0 +1,91 @@
+use std::future::Future;
+
+use crate::{runtime::Instant, Nacos};
+#[cfg(feature = "config")]
+use crate::{Error, Result};
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Fails the requests made by `future` that don't complete by `deadline`, overriding the
+/// client's timeout, e.g. to keep startup fetches strict while background refreshes are
+/// lenient. Nested deadlines can only shorten an outer one.
+pub fn with_deadline<F: Future>(deadline: Instant, future: F) -> impl Future<Output = F::Output> {
+    let deadline = DEADLINE
+        .try_with(|outer| deadline.min(*outer))
+        .unwrap_or(deadline);
+    DEADLINE.scope(deadline, future)
+}
+
+impl Nacos {
+    /// Fails requests that take longer than `timeout`. Long polls wait for their own timeout,
+    /// 10 to 60 seconds, plus `timeout`. Has no effect on wasm32, where fetch has no timeout.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self.client = self.dns.client(Some(timeout));
+        self
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// The end of the current [`with_deadline`] scope, to carry it over to spawned tasks.
+pub(crate) fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Times out a request when the current [`with_deadline`] scope ends, if any. Requests on
+/// wasm32 have no timeout.
+pub(crate) fn apply(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match DEADLINE.try_with(|deadline| *deadline) {
+        #[cfg(not(target_arch = "wasm32"))]
+        Ok(deadline) => request.timeout(deadline.saturating_duration_since(Instant::now())),
+        _ => request,
+    }
+}
+
+#[cfg(feature = "config")]
+/// Runs `future` until the current [`with_deadline`] scope ends, if any.
+pub(crate) async fn wait<F: Future>(nacos: &Nacos, future: F) -> Result<F::Output> {
+    match DEADLINE.try_with(|deadline| *deadline) {
+        Ok(deadline) => tokio::select! {
+            output = future => Ok(output),
+            _ = nacos.sleep(deadline.saturating_duration_since(Instant::now())) => {
+                Err(Error::Io(std::io::ErrorKind::TimedOut.into()))
+            }
+        },
+        Err(_) => Ok(future.await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn deadline() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let timeout =
+            |request: reqwest::RequestBuilder| request.build().unwrap().timeout().copied();
+        assert_eq!(timeout(apply(nacos.client.get("http://a/"))), None);
+
+        let soon = Instant::now() + Duration::from_secs(5);
+        let later = soon + Duration::from_secs(5);
+        let timeouts = with_deadline(soon, async {
+            let inner = with_deadline(later, async {
+                timeout(apply(nacos.client.get("http://a/")))
+            });
+            (timeout(apply(nacos.client.get("http://a/"))), inner.await)
+        })
+        .await;
+        assert!(timeouts.0.unwrap() <= Duration::from_secs(5));
+        assert!(timeouts.1.unwrap() <= Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,311 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde_json::Value;
+
+use crate::{redact::Content, value::parse_tree, ConfigEvent, FetchedConfig, Nacos};
+
+/// Largest number of line pairs compared to find the lines in common, beyond which the
+/// differing lines are all reported as removed and added.
+const MAX_LINE_PAIRS: usize = 1 << 20;
+
+/// How a config changed, see [`Nacos::with_config_diffs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigDiff {
+    /// Of configs in a known format that parse before and after the change.
+    Keys(Vec<KeyChange>),
+    /// Of other configs.
+    Lines(Vec<LineChange>),
+}
+
+/// A value that changed, with the path to it like `spring.datasource.url`. Arrays are
+/// compared as a whole. `Debug` summarizes values like config content, see
+/// [`crate::set_log_content`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyChange {
+    pub key: String,
+    /// `None` if the key was added.
+    pub old: Option<Value>,
+    /// `None` if the key was removed.
+    pub new: Option<Value>,
+}
+
+/// A line that changed, numbered from 1 in the old or the new content. `Debug` summarizes
+/// the text like config content.
+#[derive(Clone, PartialEq, Eq)]
+pub enum LineChange {
+    Removed { line: usize, text: String },
+    Added { line: usize, text: String },
+}
+
+/// A value as it may appear in `Debug` output.
+struct Redacted<'a>(&'a Value);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Content(self.0.to_string().as_bytes()), f)
+    }
+}
+
+impl fmt::Debug for KeyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyChange")
+            .field("key", &self.key)
+            .field("old", &self.old.as_ref().map(Redacted))
+            .field("new", &self.new.as_ref().map(Redacted))
+            .finish()
+    }
+}
+
+impl fmt::Debug for LineChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, line, text) = match self {
+            Self::Removed { line, text } => ("Removed", line, text),
+            Self::Added { line, text } => ("Added", line, text),
+        };
+        f.debug_struct(name)
+            .field("line", line)
+            .field("text", &Content(text.as_bytes()))
+            .finish()
+    }
+}
+
+impl Nacos {
+    /// Reports the changes of tracked configs as [`ConfigEvent::Changed`], with a diff against
+    /// the previous value, so that services can react only to the parts they use.
+    pub fn with_config_diffs(mut self) -> Self {
+        self.config_diffs = true;
+        self
+    }
+
+    /// Emits the diff of a config that changed from `previous`.
+    pub(crate) fn emit_diff(
+        &self,
+        group: &str,
+        data_id: &str,
+        previous: &FetchedConfig,
+        config: &FetchedConfig,
+    ) {
+        if !self.config_diffs || previous.content == config.content {
+            return;
+        }
+        self.emit(ConfigEvent::Changed {
+            group: group.to_string(),
+            data_id: data_id.to_string(),
+            diff: diff(previous, config),
+        });
+    }
+}
+
+fn diff(previous: &FetchedConfig, config: &FetchedConfig) -> ConfigDiff {
+    let old = previous.utf8_content();
+    let new = config.utf8_content();
+    if let Ok(format) = config.format(None) {
+        if let (Ok(old), Ok(new)) = (parse_tree(&old, format), parse_tree(&new, format)) {
+            return ConfigDiff::Keys(diff_keys(&old, &new));
+        }
+    }
+    ConfigDiff::Lines(diff_lines(
+        &String::from_utf8_lossy(&old),
+        &String::from_utf8_lossy(&new),
+    ))
+}
+
+fn diff_keys(old: &Value, new: &Value) -> Vec<KeyChange> {
+    let mut old_leaves = BTreeMap::new();
+    leaves(old, String::new(), &mut old_leaves);
+    let mut new_leaves = BTreeMap::new();
+    leaves(new, String::new(), &mut new_leaves);
+    let mut changes = Vec::new();
+    for (key, old) in &old_leaves {
+        match new_leaves.get(key) {
+            Some(new) if new == old => {}
+            new => changes.push(KeyChange {
+                key: key.clone(),
+                old: Some((*old).clone()),
+                new: new.map(|new| (*new).clone()),
+            }),
+        }
+    }
+    for (key, new) in new_leaves {
+        if !old_leaves.contains_key(&key) {
+            changes.push(KeyChange {
+                key,
+                old: None,
+                new: Some(new.clone()),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    changes
+}
+
+fn leaves<'a>(node: &'a Value, path: String, leaves: &mut BTreeMap<String, &'a Value>) {
+    match node {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                self::leaves(value, path, leaves);
+            }
+        }
+        leaf => {
+            leaves.insert(path, leaf);
+        }
+    }
+}
+
+/// The lines to remove from `old` and add to make `new`, keeping the longest common
+/// subsequence of lines.
+fn diff_lines(old: &str, new: &str) -> Vec<LineChange> {
+    let old: Vec<_> = old.lines().collect();
+    let new: Vec<_> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+    let remove = |i: usize| LineChange::Removed {
+        line: prefix + i + 1,
+        text: removed[i].to_string(),
+    };
+    let add = |j: usize| LineChange::Added {
+        line: prefix + j + 1,
+        text: added[j].to_string(),
+    };
+    let mut changes = Vec::new();
+    if removed.len().saturating_mul(added.len()) > MAX_LINE_PAIRS {
+        changes.extend((0..removed.len()).map(remove));
+        changes.extend((0..added.len()).map(add));
+        return changes;
+    }
+
+    // common[i][j] is the length of the longest common subsequence of removed[i..] and
+    // added[j..].
+    let mut common = vec![vec![0; added.len() + 1]; removed.len() + 1];
+    for i in (0..removed.len()).rev() {
+        for j in (0..added.len()).rev() {
+            common[i][j] = if removed[i] == added[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < removed.len() && j < added.len() {
+        if removed[i] == added[j] && common[i][j] == common[i + 1][j + 1] + 1 {
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            changes.push(remove(i));
+            i += 1;
+        } else {
+            changes.push(add(j));
+            j += 1;
+        }
+    }
+    changes.extend((i..removed.len()).map(remove));
+    changes.extend((j..added.len()).map(add));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let added = |line, text: &str| LineChange::Added {
+            line,
+            text: text.into(),
+        };
+        let removed = |line, text: &str| LineChange::Removed {
+            line,
+            text: text.into(),
+        };
+        assert_eq!(diff_lines("a\nb\nc\n", "a\nb\nc\n"), []);
+        assert_eq!(
+            diff_lines("a\nb\nc\nd", "a\nx\nc\nd\ne"),
+            [removed(2, "b"), added(2, "x"), added(5, "e")]
+        );
+        assert_eq!(diff_lines("a\nb", ""), [removed(1, "a"), removed(2, "b")]);
+
+        // Past the bound, the differing lines are not matched.
+        let old: String = (0..2000).map(|i| format!("{i}\n")).collect();
+        let new = old.replace('\n', "b\n");
+        let changes = diff_lines(&old, &new);
+        assert_eq!(changes.len(), 4000);
+        assert_eq!(changes[0], removed(1, "0"));
+        assert_eq!(changes[2000], added(1, "0b"));
+    }
+
+    #[test]
+    // Fields differ between features.
+    #[allow(clippy::needless_update)]
+    fn keys() {
+        let config = |content: &'static str| FetchedConfig {
+            content: Bytes::from_static(content.as_bytes()),
+            config_type: Some("json".into()),
+            ..Default::default()
+        };
+        let keys = diff(
+            &config(r#"{"server": {"port": 80, "host": "a"}, "level": "info"}"#),
+            &config(r#"{"server": {"port": 8080, "host": "a"}, "tls": true}"#),
+        );
+        assert_eq!(
+            keys,
+            ConfigDiff::Keys(vec![
+                KeyChange {
+                    key: "level".into(),
+                    old: Some(json!("info")),
+                    new: None,
+                },
+                KeyChange {
+                    key: "server.port".into(),
+                    old: Some(json!(80)),
+                    new: Some(json!(8080)),
+                },
+                KeyChange {
+                    key: "tls".into(),
+                    old: None,
+                    new: Some(json!(true)),
+                },
+            ])
+        );
+
+        // Unknown formats are compared line by line.
+        let mut text = config("a");
+        text.config_type = Some("text".into());
+        let mut changed = config("b");
+        changed.config_type = Some("text".into());
+        assert!(matches!(diff(&text, &changed), ConfigDiff::Lines(lines) if lines.len() == 2));
+    }
+
+    #[test]
+    fn debug() {
+        let change = KeyChange {
+            key: "db.password".into(),
+            old: Some(json!("hunter2")),
+            new: None,
+        };
+        let line = LineChange::Added {
+            line: 1,
+            text: "password=hunter2".into(),
+        };
+        for debug in [format!("{:?}", change), format!("{:?}", line)] {
+            assert!(!debug.contains("hunter2"), "{}", debug);
+        }
+    }
+}
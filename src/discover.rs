@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio_stream::wrappers::WatchStream;
+use tower::discover::Change;
+
+use crate::{naming::ServiceState, Instance, ServiceSubscription};
+
+/// A tower [`Discover`](tower::discover::Discover) of the available instances of a service,
+/// see [`ServiceSubscription::discover`].
+pub struct ServiceDiscover<S, F> {
+    updates: WatchStream<ServiceState>,
+    make_service: F,
+    known: HashMap<String, Instance>,
+    pending: VecDeque<Change<String, S>>,
+    _subscription: ServiceSubscription,
+}
+
+// Nothing is structurally pinned.
+impl<S, F> Unpin for ServiceDiscover<S, F> {}
+
+impl ServiceSubscription {
+    /// Turns the subscription into a tower `Discover` keyed by [`Instance::addr`]. A service is
+    /// made with `make_service` for every available instance, and made again when the instance
+    /// changes.
+    pub fn discover<S, F>(self, make_service: F) -> ServiceDiscover<S, F>
+    where
+        F: FnMut(&Instance) -> S,
+    {
+        ServiceDiscover {
+            updates: WatchStream::new(self.receiver()),
+            make_service,
+            known: HashMap::new(),
+            pending: VecDeque::new(),
+            _subscription: self,
+        }
+    }
+}
+
+impl<S, F> ServiceDiscover<S, F>
+where
+    F: FnMut(&Instance) -> S,
+{
+    fn reconcile(&mut self, instances: &[Instance]) {
+        let available: HashMap<_, _> = instances
+            .iter()
+            .filter(|instance| instance.is_available())
+            .map(|instance| (instance.addr(), instance))
+            .collect();
+        self.known.retain(|addr, _| {
+            let keep = available.contains_key(addr);
+            if !keep {
+                self.pending.push_back(Change::Remove(addr.clone()));
+            }
+            keep
+        });
+        for (addr, instance) in available {
+            if self.known.get(&addr) != Some(instance) {
+                let service = (self.make_service)(instance);
+                self.pending
+                    .push_back(Change::Insert(addr.clone(), service));
+                self.known.insert(addr, instance.clone());
+            }
+        }
+    }
+}
+
+impl<S, F> Stream for ServiceDiscover<S, F>
+where
+    F: FnMut(&Instance) -> S,
+{
+    type Item = Result<Change<String, S>, Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(change) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(change)));
+            }
+            match Pin::new(&mut this.updates).poll_next(cx) {
+                Poll::Ready(Some(state)) => this.reconcile(&state.instances),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::watch;
+    use tokio_stream::StreamExt;
+
+    use super::*;
+    use crate::live::AbortOnDrop;
+
+    fn instance(ip: &str, healthy: bool) -> Instance {
+        Instance {
+            instance_id: None,
+            ip: ip.into(),
+            port: 80,
+            weight: 1.0,
+            healthy,
+            enabled: true,
+            ephemeral: true,
+            cluster_name: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    async fn next<F: FnMut(&Instance) -> String>(
+        discover: &mut ServiceDiscover<String, F>,
+    ) -> String {
+        match discover.next().await.unwrap().unwrap() {
+            Change::Insert(addr, service) => format!("+{addr}={service}"),
+            Change::Remove(addr) => format!("-{addr}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn changes() {
+        let (sender, receiver) = watch::channel(ServiceState::new(vec![
+            instance("10.0.0.1", true),
+            instance("10.0.0.2", false),
+        ]));
+        let subscription = ServiceSubscription::new(receiver, AbortOnDrop(vec![]));
+        let mut discover = subscription.discover(|instance| instance.ip.clone());
+        fn assert_discover<D: tower::discover::Discover>(_: &D) {}
+        assert_discover(&discover);
+        assert_eq!(next(&mut discover).await, "+10.0.0.1:80=10.0.0.1");
+
+        sender.send_replace(ServiceState::new(vec![
+            instance("10.0.0.1", false),
+            instance("10.0.0.2", true),
+        ]));
+        assert_eq!(next(&mut discover).await, "-10.0.0.1:80");
+        assert_eq!(next(&mut discover).await, "+10.0.0.2:80=10.0.0.2");
+    }
+}
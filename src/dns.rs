@@ -0,0 +1,140 @@
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use hyper::client::connect::dns::Name;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::runtime;
+
+#[cfg(not(target_arch = "wasm32"))]
+/// How long the addresses of a server name are used before resolving it again.
+const DNS_TTL: Duration = Duration::from_secs(60);
+
+/// Resolves server names for the client's connections, caching the addresses so that new
+/// connections don't wait for DNS. Shared by clones and kept when the HTTP client is rebuilt.
+/// Empty on wasm32, where the browser resolves names.
+#[derive(Clone, Default)]
+pub(crate) struct DnsCache {
+    #[cfg(not(target_arch = "wasm32"))]
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Entry {
+    addrs: Vec<SocketAddr>,
+    resolved: Instant,
+}
+
+impl DnsCache {
+    /// An HTTP client resolving names with the cache. Redirects are returned, to be followed
+    /// only within the cluster.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn client(&self, timeout: Option<Duration>) -> reqwest::Client {
+        let builder = reqwest::Client::builder()
+            .dns_resolver(Arc::new(self.clone()))
+            .redirect(reqwest::redirect::Policy::none());
+        let builder = match timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        };
+        builder.build().expect("the TLS backend initializes")
+    }
+
+    /// The browser's fetch, which follows redirects itself and has no timeout.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn client(&self, timeout: Option<Duration>) -> reqwest::Client {
+        let _ = timeout;
+        reqwest::Client::new()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DnsCache {
+    fn cached(&self, name: &str) -> Option<Vec<SocketAddr>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(name)?;
+        (entry.resolved.elapsed() < DNS_TTL).then(|| entry.addrs.clone())
+    }
+
+    fn store(&self, name: &str, addrs: Vec<SocketAddr>) {
+        let entry = Entry {
+            addrs,
+            resolved: Instant::now(),
+        };
+        self.entries.lock().unwrap().insert(name.to_string(), entry);
+    }
+
+    /// Resolves `name` again on its next connection, as its server couldn't be reached.
+    pub(crate) fn invalidate(&self, name: &str) {
+        if let Some(entry) = self.entries.lock().unwrap().get_mut(name) {
+            entry.resolved = Instant::now() - DNS_TTL;
+        }
+    }
+
+    async fn lookup(&self, name: &str) -> std::io::Result<Vec<SocketAddr>> {
+        if let Some(addrs) = self.cached(name) {
+            return Ok(addrs);
+        }
+        match runtime::lookup_host(name).await {
+            Ok(addrs) => {
+                self.store(name, addrs.clone());
+                Ok(addrs)
+            }
+            Err(e) => {
+                // Stale addresses beat none while DNS is down.
+                let stale = self
+                    .entries
+                    .lock()
+                    .unwrap()
+                    .get(name)
+                    .map(|entry| entry.addrs.clone());
+                match stale {
+                    Some(addrs) => {
+                        tracing::warn!(host = %name, error = %e, "Using stale addresses");
+                        Ok(addrs)
+                    }
+                    None => Err(e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Resolve for DnsCache {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.clone();
+        Box::pin(async move {
+            let addrs = cache.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn dns_cache() {
+        let cache = DnsCache::default();
+        let addrs = vec![SocketAddr::from(([10, 0, 0, 1], 0))];
+        cache.store("nacos.invalid", addrs.clone());
+        assert_eq!(cache.lookup("nacos.invalid").await.unwrap(), addrs);
+
+        // Resolving again fails, the stale addresses are kept.
+        cache.invalidate("nacos.invalid");
+        assert_eq!(cache.cached("nacos.invalid"), None);
+        assert_eq!(cache.lookup("nacos.invalid").await.unwrap(), addrs);
+        assert!(cache.lookup("other.invalid").await.is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::{io::Write, path::Path};
+
+use bytes::Bytes;
+use md5::{Digest, Md5};
+
+use crate::{html, ids, protocol, runtime, Error, Nacos, Result};
+
+/// Chunks received ahead of the thread writing them.
+const CHUNKS_IN_FLIGHT: usize = 16;
+
+impl Nacos {
+    /// Writes a config to `path` as it is received, without holding it in memory, e.g. for
+    /// certificate bundles or dictionaries. Returns the number of bytes written.
+    ///
+    /// The content is written as served: neither filters, decryption nor
+    /// [`Self::with_max_config_size`] apply. Like snapshots, the file is replaced only once
+    /// the whole config was received and matches its md5.
+    pub async fn download_config_to(&self, data_id: &str, path: impl AsRef<Path>) -> Result<u64> {
+        let group = self.group.as_str();
+        ids::validate_key(group, data_id)?;
+        let request = self.fetch_request(&self.config_key(group, data_id));
+        let attributes = [("nacos.group", group), ("nacos.data_id", data_id)];
+        let response = self
+            .send("nacos.config.download", request, &attributes)
+            .await?;
+        write_response(data_id, response, path.as_ref()).await
+    }
+}
+
+/// Writes the content of a config response to `path`, through a temporary file.
+async fn write_response(
+    data_id: &str,
+    mut response: reqwest::Response,
+    path: &Path,
+) -> Result<u64> {
+    let headers = response.headers().clone();
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".nacos-tmp");
+    let (chunks, mut received) = tokio::sync::mpsc::channel::<Bytes>(CHUNKS_IN_FLIGHT);
+    let file = tmp.clone();
+    let writer = runtime::unblock(move || {
+        let mut file = std::fs::File::create(file)?;
+        while let Some(chunk) = received.blocking_recv() {
+            file.write_all(&chunk)?;
+        }
+        file.sync_all()
+    });
+    let received = async {
+        let chunks = chunks;
+        let mut written = 0;
+        let mut md5 = Md5::new();
+        // The start of the content, until it's long enough to tell a page.
+        let mut head = Some(Vec::new());
+        while let Some(chunk) = response.chunk().await? {
+            if let Some(start) = &mut head {
+                start.extend_from_slice(&chunk[..chunk.len().min(html::MAX_TITLE_SEARCH)]);
+                if start.len() >= html::MAX_TITLE_SEARCH {
+                    html::check_not_html(data_id, &headers, start)?;
+                    head = None;
+                }
+            }
+            md5.update(&chunk);
+            written += chunk.len() as u64;
+            // The writer stopped on an error, which it returns below.
+            if chunks.send(chunk).await.is_err() {
+                break;
+            }
+        }
+        if let Some(start) = head {
+            html::check_not_html(data_id, &headers, &start)?;
+        }
+        let md5: protocol::ContentMd5 = md5.finalize().into();
+        if super::content_md5(&headers).is_some_and(|expected| expected != md5) {
+            return Err(Error::Corrupted {
+                data_id: data_id.to_string(),
+            });
+        }
+        Ok(written)
+    }
+    .await;
+    let written = match writer.await {
+        Err(e) => Err(e.into()),
+        Ok(()) => received,
+    };
+    let path = path.to_path_buf();
+    match written {
+        Ok(written) => {
+            runtime::unblock(move || std::fs::rename(&tmp, path)).await?;
+            Ok(written)
+        }
+        Err(e) => {
+            let _ = runtime::unblock(move || std::fs::remove_file(&tmp)).await;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn download() {
+        let path = std::env::temp_dir().join(format!("nacos-download-{}", std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        assert!(nacos.download_config_to("certs.pem", &path).await.is_err());
+        assert!(nacos.download_config_to("no spaces", &path).await.is_err());
+        assert!(!path.exists());
+
+        let response = |content: &'static str, md5: &[u8]| {
+            reqwest::Response::from(
+                hyper::Response::builder()
+                    .header("Content-MD5", protocol::md5_hex(md5))
+                    .body(content)
+                    .unwrap(),
+            )
+        };
+        let pem = "-----BEGIN CERTIFICATE-----";
+        assert_eq!(
+            write_response("certs.pem", response(pem, pem.as_bytes()), &path)
+                .await
+                .unwrap(),
+            pem.len() as u64
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), pem);
+
+        // A truncated response leaves the file as it was.
+        let truncated = response("-----BEGIN", pem.as_bytes());
+        let error = write_response("certs.pem", truncated, &path).await;
+        assert!(matches!(error, Err(Error::Corrupted { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), pem);
+
+        let page = reqwest::Response::from(hyper::Response::new(
+            " \n\n<!DOCTYPE html><title>Sign in</title>",
+        ));
+        let error = write_response("certs.pem", page, &path).await;
+        assert!(matches!(error, Err(Error::HtmlPage { .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::{kms::CIPHER_PREFIX, Error, FetchedConfig, Nacos, Result};
+
+pub type EncryptionFuture<T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send>>;
+
+/// Content of a config encrypted by an [`EncryptionPlugin`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Encrypted {
+    pub content: String,
+    /// The data key, itself encrypted, for plugins that encrypt each config with its own key.
+    /// Nacos stores it next to the config.
+    pub data_key: Option<String>,
+}
+
+/// Encrypts and decrypts configs whose data id starts with `cipher-<algorithm name>-`, like
+/// the encryption plugins of the Java client.
+pub trait EncryptionPlugin: Send + Sync + 'static {
+    fn algorithm_name(&self) -> &str;
+
+    fn encrypt(&self, data_id: &str, plaintext: String) -> EncryptionFuture<Encrypted>;
+
+    fn decrypt(&self, data_id: &str, encrypted: Encrypted) -> EncryptionFuture<String>;
+}
+
+impl Nacos {
+    /// Adds a plugin for configs prefixed with `cipher-<algorithm name>-`. Fetched configs are
+    /// decrypted, so all fetch and watch APIs return plaintext.
+    pub fn with_encryption_plugin(mut self, plugin: impl EncryptionPlugin) -> Self {
+        self.encryption_plugins.push(Arc::new(plugin));
+        self
+    }
+
+    /// Encrypts `cipher-<algorithm name>-` configs with their plugin before publishing them, so
+    /// the server only stores ciphertext. Publishing other `cipher-` configs then fails rather
+    /// than sending plaintext.
+    pub fn with_publish_encryption(mut self) -> Self {
+        self.encrypt_on_publish = true;
+        self
+    }
+
+    pub(crate) fn encryption_plugin(&self, data_id: &str) -> Option<&Arc<dyn EncryptionPlugin>> {
+        let algorithm = data_id.strip_prefix(CIPHER_PREFIX)?;
+        self.encryption_plugins.iter().find(|plugin| {
+            algorithm
+                .strip_prefix(plugin.algorithm_name())
+                .is_some_and(|rest| rest.starts_with('-'))
+        })
+    }
+
+    /// Decrypts the content of `cipher-` configs with a matching plugin, or else the KMS.
+    pub(crate) async fn decrypt(&self, data_id: &str, config: &mut FetchedConfig) -> Result<()> {
+        if !data_id.starts_with(CIPHER_PREFIX) {
+            return Ok(());
+        }
+        let plaintext = if let Some(plugin) = self.encryption_plugin(data_id) {
+            let encrypted = Encrypted {
+                content: ciphertext(config)?,
+                data_key: config.encrypted_data_key.clone(),
+            };
+            plugin.decrypt(data_id, encrypted).await
+        } else if let Some(kms) = &self.kms {
+            kms.decrypt(data_id, ciphertext(config)?).await
+        } else {
+            return Ok(());
+        };
+        config.content = plaintext.map_err(Error::Decrypt)?.into();
+        Ok(())
+    }
+
+    /// Encrypts content about to be published, if the client does so.
+    pub(crate) async fn encrypt(&self, data_id: &str, content: String) -> Result<Encrypted> {
+        if !self.encrypt_on_publish || !data_id.starts_with(CIPHER_PREFIX) {
+            return Ok(Encrypted {
+                content,
+                data_key: None,
+            });
+        }
+        let Some(plugin) = self.encryption_plugin(data_id) else {
+            return Err(Error::Encrypt(format!(
+                "no encryption plugin for {}",
+                data_id
+            )));
+        };
+        plugin
+            .encrypt(data_id, content)
+            .await
+            .map_err(Error::Encrypt)
+    }
+}
+
+fn ciphertext(config: &FetchedConfig) -> Result<String> {
+    let ciphertext = std::str::from_utf8(&config.content).map_err(Error::Decode)?;
+    Ok(ciphertext.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// Reverses the content, with the data key as a suffix.
+    struct Reverse;
+
+    impl EncryptionPlugin for Reverse {
+        fn algorithm_name(&self) -> &str {
+            "reverse"
+        }
+
+        fn encrypt(&self, _: &str, plaintext: String) -> EncryptionFuture<Encrypted> {
+            Box::pin(async move {
+                Ok(Encrypted {
+                    content: plaintext.chars().rev().collect(),
+                    data_key: Some("key".into()),
+                })
+            })
+        }
+
+        fn decrypt(&self, _: &str, encrypted: Encrypted) -> EncryptionFuture<String> {
+            Box::pin(async move {
+                let content: String = encrypted.content.chars().rev().collect();
+                Ok(content + &encrypted.data_key.unwrap_or_default())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn decrypt() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_encryption_plugin(Reverse);
+        assert!(nacos.encryption_plugin("cipher-reverse-db.yaml").is_some());
+        assert!(nacos.encryption_plugin("cipher-reversed-db.yaml").is_none());
+        assert!(nacos.encryption_plugin("reverse-db.yaml").is_none());
+
+        let mut config = FetchedConfig {
+            content: "terces".into(),
+            encrypted_data_key: Some("!".into()),
+            ..Default::default()
+        };
+        nacos
+            .decrypt("cipher-reverse-db.yaml", &mut config)
+            .await
+            .unwrap();
+        assert_eq!(config.content, "secret!");
+        // Without a KMS, other cipher- configs are returned as served.
+        nacos.decrypt("cipher-db.yaml", &mut config).await.unwrap();
+        assert_eq!(config.content, "secret!");
+    }
+
+    #[tokio::test]
+    async fn encrypt() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_encryption_plugin(Reverse);
+        // Off by default.
+        let encrypted = nacos
+            .encrypt("cipher-reverse-db.yaml", "secret".into())
+            .await;
+        assert_eq!(encrypted.unwrap().content, "secret");
+
+        let nacos = nacos.with_publish_encryption();
+        let encrypted = nacos
+            .encrypt("cipher-reverse-db.yaml", "secret".into())
+            .await
+            .unwrap();
+        assert_eq!(encrypted.content, "terces");
+        assert_eq!(encrypted.data_key.as_deref(), Some("key"));
+        let plain = nacos.encrypt("db.yaml", "secret".into()).await.unwrap();
+        assert_eq!(plain.content, "secret");
+        assert!(matches!(
+            nacos.encrypt("cipher-aes-db.yaml", "secret".into()).await,
+            Err(Error::Encrypt(_))
+        ));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{live::RETRY_DELAY, protocol, ChangeMetadata, ConfigFormat, Error, Nacos, Result};
+
+/// Writes sent before giving up on a write that fails on the way.
+const MAX_WRITE_ATTEMPTS: u32 = 3;
+
+/// How a config reached the state requested by [`Nacos::ensure_config_published`] or
+/// [`Nacos::ensure_config_deleted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The server answered that it applied the write.
+    Applied,
+    /// The server was found in the requested state after a failed attempt, which was applied
+    /// without its answer reaching the client or had been done before.
+    AlreadyApplied,
+}
+
+impl Nacos {
+    /// Like [`Self::publish_config_with`], retrying writes that fail on the way. Between
+    /// attempts the config is fetched, and the write succeeds if it already has the content.
+    ///
+    /// On [`Error::WriteFailed`], the server was last seen without the content or couldn't be
+    /// checked. Other errors are answers of the server, which did not apply the write.
+    pub async fn ensure_config_published(
+        &self,
+        data_id: &str,
+        content: &str,
+        format: Option<ConfigFormat>,
+        metadata: &ChangeMetadata,
+    ) -> Result<WriteOutcome> {
+        // Retries send the same content, even with a filter or encryption that varies.
+        let encrypted = self.prepare_published(data_id, content).await?;
+        let md5 = protocol::md5(encrypted.content.as_bytes());
+        let mut attempt = 1;
+        loop {
+            let error = match self
+                .send_published(data_id, &encrypted, format, metadata)
+                .await
+            {
+                Ok(()) => return Ok(WriteOutcome::Applied),
+                Err(e) if is_transient(&e) => e,
+                Err(e) => return Err(e),
+            };
+            let state = self.fetch_content(&self.group, data_id).await;
+            if state.as_ref().is_ok_and(|config| config.md5 == md5) {
+                return Ok(WriteOutcome::AlreadyApplied);
+            }
+            let state_known = match &state {
+                Ok(_) => true,
+                Err(e) => e.is_not_found(),
+            };
+            self.retry_write(data_id, attempt, error, state_known)
+                .await?;
+            attempt += 1;
+        }
+    }
+
+    /// Like [`Self::delete_config_with`], retrying deletes that fail on the way. Between
+    /// attempts the config is fetched, and the delete succeeds if it is gone.
+    ///
+    /// Errors are reported like [`Self::ensure_config_published`]'s.
+    pub async fn ensure_config_deleted(
+        &self,
+        data_id: &str,
+        metadata: &ChangeMetadata,
+    ) -> Result<WriteOutcome> {
+        crate::ids::validate_key(&self.group, data_id)?;
+        let mut attempt = 1;
+        loop {
+            let error = match self.send_deleted(data_id, metadata).await {
+                Ok(()) => return Ok(WriteOutcome::Applied),
+                Err(e) if is_transient(&e) => e,
+                Err(e) => return Err(e),
+            };
+            let state = self.fetch_content(&self.group, data_id).await;
+            if state.as_ref().is_err_and(Error::is_not_found) {
+                return Ok(WriteOutcome::AlreadyApplied);
+            }
+            self.retry_write(data_id, attempt, error, state.is_ok())
+                .await?;
+            attempt += 1;
+        }
+    }
+
+    /// Waits before the next attempt of a write, or fails with `error` after the last one.
+    async fn retry_write(
+        &self,
+        data_id: &str,
+        attempt: u32,
+        error: Error,
+        state_known: bool,
+    ) -> Result<()> {
+        if attempt == MAX_WRITE_ATTEMPTS {
+            return Err(Error::WriteFailed {
+                data_id: data_id.to_string(),
+                state_known,
+                source: Box::new(error),
+            });
+        }
+        tracing::warn!(data_id, error = %error, "Retrying the write of config");
+        self.sleep(RETRY_DELAY * attempt).await;
+        Ok(())
+    }
+}
+
+/// Whether a write failed on the way, and may succeed or have succeeded.
+fn is_transient(error: &Error) -> bool {
+    let (Error::Http(e) | Error::Request { source: e, .. }) = error else {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Error::Transport { .. } = error {
+            return true;
+        }
+        return false;
+    };
+    match e.status() {
+        Some(status) => {
+            status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        }
+        None => !e.is_builder() && !e.is_redirect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, str::FromStr};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn retried() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let metadata = ChangeMetadata::default();
+        let error = nacos.send_deleted("app.yaml", &metadata).await.unwrap_err();
+        assert!(is_transient(&error));
+        let error = nacos
+            .retry_write("app.yaml", MAX_WRITE_ATTEMPTS, error, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::WriteFailed {
+                state_known: false,
+                ..
+            }
+        ));
+
+        // Invalid writes are not retried.
+        let error = nacos
+            .ensure_config_published("", "a: 1", None, &metadata)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidId { .. }));
+        assert!(!is_transient(&Error::Refused("false".into())));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::ConfigFormat;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// A request to the server failed, or the server answered with an error status.
+    #[error("request {request_id} failed: {source}")]
+    Request {
+        /// Sent in the `RequestId` header, to find the request in the server's logs.
+        request_id: String,
+        source: reqwest::Error,
+    },
+    /// A [`crate::Transport`] failed to send a request.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("request {request_id} failed: {source}")]
+    Transport {
+        request_id: String,
+        source: crate::TransportError,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("config is not valid UTF-8: {0}")]
+    Decode(std::str::Utf8Error),
+    #[error("failed to parse config as {format:?}: {message}")]
+    Parse {
+        format: ConfigFormat,
+        message: String,
+    },
+    #[error("failed to deserialize merged config: {0}")]
+    Merged(serde_json::Error),
+    #[error("failed to resolve placeholders: {0}")]
+    Placeholder(String),
+    #[error("invalid server address {0:?}")]
+    InvalidServer(String),
+    #[error("invalid {kind} {id:?}: {reason}")]
+    InvalidId {
+        kind: &'static str,
+        id: String,
+        reason: &'static str,
+    },
+    #[error("invalid value {value:?} for {key}, expected {expected}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+    #[error("server did not apply the change: {0}")]
+    Refused(String),
+    #[error("failed to decrypt config: {0}")]
+    Decrypt(String),
+    #[error("failed to encrypt config: {0}")]
+    Encrypt(String),
+    #[error("config filter {filter} failed: {reason}")]
+    Filter { filter: String, reason: String },
+    #[error("config {data_id} is larger than the limit of {limit} bytes")]
+    TooLarge { data_id: String, limit: usize },
+    /// The content of a config didn't match the md5 sent with it, even after fetching it again.
+    #[error("content of config {data_id} does not match its md5")]
+    Corrupted { data_id: String },
+    /// The server refused a request, e.g. for lack of permission on a namespace.
+    #[error("request {request_id} was refused: {message}")]
+    Forbidden {
+        request_id: String,
+        /// As answered by the server.
+        message: String,
+    },
+    /// The server redirected a request to a server that is not in the cluster's list.
+    #[error("request {request_id} was redirected outside the cluster, to {location}")]
+    Redirected {
+        request_id: String,
+        /// Without its query, which may hold credentials.
+        location: String,
+    },
+    /// A retried write failed, see [`crate::Nacos::ensure_config_published`].
+    #[error(
+        "write of config {data_id} failed, {}: {source}",
+        if *state_known { "the server does not have it" } else { "the server's state is unknown" }
+    )]
+    WriteFailed {
+        data_id: String,
+        /// Whether the server was seen without the write after the last attempt.
+        state_known: bool,
+        source: Box<Error>,
+    },
+    /// A config fetch was answered with an HTML page, not by Nacos.
+    #[error(
+        "config {data_id} was answered with an HTML page{}, likely by a proxy in front of Nacos",
+        title.as_ref().map(|title| format!(" titled {:?}", title)).unwrap_or_default()
+    )]
+    HtmlPage {
+        data_id: String,
+        title: Option<String>,
+    },
+    #[error("config rejected by its validator: {0}")]
+    Rejected(String),
+    #[error("invalid JSON Schema: {0}")]
+    InvalidSchema(String),
+    #[error("cannot infer the format of a config of type {0:?}, specify one explicitly")]
+    UnknownConfigType(Option<String>),
+    #[error("{0} requires the `{1}` feature")]
+    FeatureRequired(&'static str, &'static str),
+    #[error("{0:?} configs require the `{1}` feature")]
+    UnsupportedFormat(ConfigFormat, &'static str),
+}
+
+impl Error {
+    /// Whether the server reported that the config does not exist.
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::NOT_FOUND)
+    }
+
+    /// The error status the server answered with, if any.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            Self::Http(e) | Self::Request { source: e, .. } => e.status(),
+            Self::Forbidden { .. } => Some(reqwest::StatusCode::FORBIDDEN),
+            _ => None,
+        }
+    }
+
+    /// The id of the failed request, see [`crate::with_request_id`].
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Self::Request { request_id, .. }
+            | Self::Forbidden { request_id, .. }
+            | Self::Redirected { request_id, .. } => Some(request_id),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Transport { request_id, .. } => Some(request_id),
+            _ => None,
+        }
+    }
+}
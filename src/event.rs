@@ -0,0 +1,45 @@
+use tokio::sync::broadcast;
+
+use crate::{ConfigDiff, Nacos};
+
+/// Number of events buffered for lagging subscribers.
+pub(crate) const EVENT_CAPACITY: usize = 64;
+
+/// Notable things happening to watched configs, see [`Nacos::subscribe_events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigEvent {
+    /// A new value was rejected by the config's validator and was not delivered.
+    Rejected {
+        group: String,
+        data_id: String,
+        reason: String,
+    },
+    /// A config was larger than [`Nacos::with_max_config_size`] and was not delivered.
+    TooLarge {
+        group: String,
+        data_id: String,
+        limit: usize,
+    },
+    /// A tracked config was deleted. Watches keep the previous value and wait for the config
+    /// to be created again.
+    Deleted { group: String, data_id: String },
+    /// A tracked config changed, see [`Nacos::with_config_diffs`].
+    Changed {
+        group: String,
+        data_id: String,
+        diff: ConfigDiff,
+    },
+}
+
+impl Nacos {
+    /// Subscribes to events of this client and its clones.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ConfigEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: ConfigEvent) {
+        // Nobody listening is fine.
+        let _ = self.events.send(event);
+    }
+}
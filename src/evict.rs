@@ -0,0 +1,108 @@
+use crate::{key::ConfigKey, runtime::SystemTime, Nacos};
+
+impl Nacos {
+    /// Tracks at most `max` configs, forgetting the least recently fetched or polled of those
+    /// that are not waited on. A forgotten config is fetched right away when waited on again.
+    ///
+    /// Configs also stop being tracked when nothing waits on them anymore.
+    pub fn with_max_tracked_configs(mut self, max: usize) -> Self {
+        self.max_tracked_configs = Some(max);
+        self
+    }
+
+    /// Drops the tracked state of configs, which the caller ensures nothing waits on.
+    pub(crate) fn forget(&self, keys: &[ConfigKey]) {
+        if keys.is_empty() {
+            return;
+        }
+        {
+            let mut current_config = self.current_config.lock().unwrap();
+            let mut last_polls = self.last_polls.lock().unwrap();
+            let mut snapshots_served = self.snapshots_served.lock().unwrap();
+            for key in keys {
+                current_config.untrack(key);
+                last_polls.remove(key);
+                snapshots_served.remove(key);
+            }
+        }
+        self.cached_content.rcu(|cached| {
+            let mut cached = std::collections::HashMap::clone(cached);
+            for key in keys {
+                cached.remove(key);
+            }
+            cached
+        });
+        tracing::debug!(configs = keys.len(), "Stopped tracking configs");
+    }
+
+    /// Forgets the least recently used configs beyond the bound, down to 90% of it so that
+    /// evictions are rare.
+    pub(crate) fn evict_tracked(&self) {
+        let Some(max) = self.max_tracked_configs else {
+            return;
+        };
+        if self.current_config.lock().unwrap().len() <= max {
+            return;
+        }
+        let waited = self.listener.waited();
+        let (tracked, mut idle) = {
+            let current_config = self.current_config.lock().unwrap();
+            let last_polls = self.last_polls.lock().unwrap();
+            let idle: Vec<(Option<SystemTime>, ConfigKey)> = current_config
+                .keys()
+                .filter(|key| !waited.contains(*key))
+                .map(|key| (last_polls.get(key).copied(), key.clone()))
+                .collect();
+            (current_config.len(), idle)
+        };
+        let excess = tracked.saturating_sub(max - max / 10);
+        idle.sort();
+        let evicted: Vec<_> = idle.into_iter().take(excess).map(|(_, key)| key).collect();
+        self.forget(&evicted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn evict() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_max_tracked_configs(2);
+        let key = |data_id| nacos.config_key("DEFAULT_GROUP", data_id);
+        let now = SystemTime::now();
+        let _waiter = nacos.listener.subscribe(&nacos, &key("waited.yaml"));
+        for (age, data_id) in [(3, "waited.yaml"), (2, "old.yaml"), (1, "new.yaml")] {
+            nacos
+                .current_config
+                .lock()
+                .unwrap()
+                .track(key(data_id), None);
+            nacos
+                .last_polls
+                .lock()
+                .unwrap()
+                .insert(key(data_id), now - Duration::from_secs(age));
+        }
+        nacos.evict_tracked();
+
+        let mut tracked: Vec<_> = nacos
+            .current_config
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        tracked.sort();
+        assert_eq!(tracked, [key("new.yaml"), key("waited.yaml")]);
+        assert_eq!(nacos.last_polls.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,217 @@
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+use std::{io::Write, path::Path};
+
+#[cfg(feature = "config")]
+use serde_json::Value;
+
+#[cfg(feature = "config")]
+use crate::{
+    live::{AbortOnDrop, RETRY_DELAY},
+    runtime, Nacos, Result,
+};
+
+/// Returned by [`Nacos::export_env`], stops updating the file when dropped.
+#[cfg(feature = "config")]
+pub struct EnvExport {
+    path: PathBuf,
+    _task: AbortOnDrop,
+}
+
+#[cfg(feature = "config")]
+impl EnvExport {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(feature = "config")]
+impl Nacos {
+    /// Merges configs like [`Self::get_layered_config_as`] and writes them to `path` as
+    /// `KEY=value` lines, rewriting the file whenever they change. Keys are flattened and
+    /// upper-cased, so `spring.datasource.url` becomes `SPRING_DATASOURCE_URL`.
+    ///
+    /// Files are replaced atomically, so readers never see a partial file.
+    pub async fn export_env(
+        &self,
+        data_ids: &[&str],
+        path: impl Into<PathBuf>,
+    ) -> Result<EnvExport> {
+        let path = path.into();
+        let mut handle = self.watch_layered_live::<Value>(data_ids, None).await?;
+        let mut content = render_env(&handle.load());
+        write_atomically(&path, content.as_bytes()).await?;
+
+        let target = path.clone();
+        let nacos = self.clone();
+        let task = self.spawn(async move {
+            loop {
+                handle.changed().await;
+                let updated = render_env(&handle.load());
+                if updated == content {
+                    continue;
+                }
+                while let Err(e) = write_atomically(&target, updated.as_bytes()).await {
+                    tracing::warn!(path = %target.display(), error = %e, "Failed to write config");
+                    nacos.sleep(RETRY_DELAY).await;
+                }
+                content = updated;
+            }
+        });
+        Ok(EnvExport {
+            path,
+            _task: AbortOnDrop(vec![task]),
+        })
+    }
+}
+
+/// Writes to a temporary file next to `path` and renames it over `path`, syncing the file
+/// first so that a crash leaves either the old content or the new one. The permissions of an
+/// existing file are kept, and applied before writing, e.g. for private keys.
+#[cfg(feature = "config")]
+pub(crate) async fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let (path, content) = (path.to_path_buf(), content.to_vec());
+    runtime::unblock(move || write_atomically_blocking(&path, &content)).await
+}
+
+pub(crate) fn write_atomically_blocking(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".nacos-tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(feature = "config")]
+fn render_env(tree: &Value) -> String {
+    let mut lines = Vec::new();
+    flatten(tree, String::new(), &mut lines);
+    lines.sort();
+    lines
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}\n", quote(&value)))
+        .collect()
+}
+
+#[cfg(feature = "config")]
+fn flatten(node: &Value, key: String, lines: &mut Vec<(String, String)>) {
+    let value = match node {
+        Value::Object(map) => {
+            for (name, value) in map {
+                let name = env_key(name);
+                let key = if key.is_empty() {
+                    name
+                } else {
+                    format!("{key}_{name}")
+                };
+                flatten(value, key, lines);
+            }
+            return;
+        }
+        Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join(","),
+        value => scalar(value),
+    };
+    if !key.is_empty() {
+        lines.push((key, value));
+    }
+}
+
+#[cfg(feature = "config")]
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(feature = "config")]
+fn env_key(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(feature = "config")]
+/// Double quotes values that a shell or dotenv parser would otherwise split or expand.
+fn quote(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@+%".contains(c);
+    if value.chars().all(plain) {
+        return value.to_string();
+    }
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '$' | '`' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "config")]
+    use serde_json::json;
+
+    use super::*;
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn env() {
+        let tree = json!({
+            "spring": {"datasource": {"url": "jdbc:mysql://db:3306/app", "pool-size": 8}},
+            "greeting": "hello \"world\"\n$HOME",
+            "hosts": ["a", "b"],
+            "debug": true,
+            "empty": null,
+        });
+        assert_eq!(
+            render_env(&tree),
+            "DEBUG=true\n\
+             EMPTY=\n\
+             GREETING=\"hello \\\"world\\\"\\n\\$HOME\"\n\
+             HOSTS=a,b\n\
+             SPRING_DATASOURCE_POOL_SIZE=8\n\
+             SPRING_DATASOURCE_URL=jdbc:mysql://db:3306/app\n"
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[tokio::test]
+    async fn atomic_write() {
+        let path = std::env::temp_dir().join(format!("nacos-export-{}.env", std::process::id()));
+        write_atomically(&path, b"A=1\n").await.unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        write_atomically(&path, b"A=2\n").await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"A=2\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
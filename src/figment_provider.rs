@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use figment::{
+    providers::Serialized,
+    value::{Dict, Map},
+    Metadata, Profile, Provider,
+};
+use serde_json::Value;
+
+use crate::{ConfigFormat, ConfigHandle, Nacos, Result};
+
+/// A figment [`Provider`] of layered configs, see [`Nacos::get_layered_config_as`].
+///
+/// Figments read their providers when they are built, so rebuild the figment after
+/// [`Self::changed`] to pick up changes of a watched provider.
+#[derive(Clone)]
+pub struct NacosProvider {
+    source: Source,
+    name: String,
+}
+
+#[derive(Clone)]
+enum Source {
+    Fetched(Arc<Value>),
+    Watched(ConfigHandle<Value>),
+}
+
+impl NacosProvider {
+    /// Fetches the configs once.
+    pub async fn fetch(
+        nacos: &Nacos,
+        data_ids: &[&str],
+        format: Option<ConfigFormat>,
+    ) -> Result<Self> {
+        let tree = nacos.get_layered_config_as(data_ids, format).await?;
+        Ok(Self::new(Source::Fetched(Arc::new(tree)), data_ids))
+    }
+
+    /// Keeps the configs up to date in the background.
+    pub async fn watch(
+        nacos: &Nacos,
+        data_ids: &[&str],
+        format: Option<ConfigFormat>,
+    ) -> Result<Self> {
+        let handle = nacos.watch_layered_live(data_ids, format).await?;
+        Ok(Self::new(Source::Watched(handle), data_ids))
+    }
+
+    /// Waits for the configs to change, see [`ConfigHandle::changed`]. Never returns for
+    /// providers that are not watched.
+    pub async fn changed(&mut self) {
+        match &mut self.source {
+            Source::Fetched(_) => std::future::pending().await,
+            Source::Watched(handle) => handle.changed().await,
+        }
+    }
+
+    fn new(source: Source, data_ids: &[&str]) -> Self {
+        Self {
+            source,
+            name: format!("Nacos config {}", data_ids.join(", ")),
+        }
+    }
+}
+
+impl Provider for NacosProvider {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(self.name.clone())
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        let tree = match &self.source {
+            Source::Fetched(tree) => tree.clone(),
+            Source::Watched(handle) => handle.load(),
+        };
+        Serialized::defaults(&*tree).data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use figment::Figment;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn provider() {
+        #[derive(serde::Deserialize)]
+        struct App {
+            name: String,
+            port: u16,
+        }
+        let tree = json!({"name": "app", "port": 80});
+        let provider = NacosProvider::new(Source::Fetched(Arc::new(tree)), &["app.yaml"]);
+        let app: App = Figment::from(Serialized::defaults(json!({"port": 8080})))
+            .merge(provider)
+            .extract()
+            .unwrap();
+        assert_eq!(app.name, "app");
+        assert_eq!(app.port, 80);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::{Error, FetchedConfig, Nacos, Result};
+
+pub type FilterFuture = Pin<Box<dyn Future<Output = Result<ConfigContext, String>> + Send>>;
+
+/// A config passing through a [`ConfigFilter`].
+#[derive(Clone)]
+pub struct ConfigContext {
+    pub data_id: String,
+    pub group: String,
+    pub namespace: Option<String>,
+    pub content: Bytes,
+}
+
+/// Transforms configs on their way from and to the server, like the config filters of the Java
+/// client. Filters run in ascending [`Self::order`]: on fetched configs after decryption, and
+/// on published configs before encryption.
+pub trait ConfigFilter: Send + Sync + 'static {
+    /// Identifies the filter in errors.
+    fn name(&self) -> &str;
+
+    fn order(&self) -> i32 {
+        0
+    }
+
+    /// Filters a fetched config, before it is validated and delivered.
+    fn on_fetch(&self, config: ConfigContext) -> FilterFuture {
+        Box::pin(async move { Ok(config) })
+    }
+
+    /// Filters a config about to be published.
+    fn on_publish(&self, config: ConfigContext) -> FilterFuture {
+        Box::pin(async move { Ok(config) })
+    }
+}
+
+impl Nacos {
+    /// Adds a filter to the chain applied to every fetched and published config.
+    pub fn with_filter(mut self, filter: impl ConfigFilter) -> Self {
+        let filter: Arc<dyn ConfigFilter> = Arc::new(filter);
+        // After filters of the same order, so that they run in the order they were added.
+        let index = self
+            .filters
+            .partition_point(|other| other.order() <= filter.order());
+        self.filters.insert(index, filter);
+        self
+    }
+
+    pub(crate) async fn filter_fetched(
+        &self,
+        group: &str,
+        data_id: &str,
+        config: &mut FetchedConfig,
+    ) -> Result<()> {
+        if self.filters.is_empty() {
+            return Ok(());
+        }
+        let context = self.context(group, data_id, config.content.clone());
+        let context = self
+            .run_filters(context, |filter, context| filter.on_fetch(context))
+            .await?;
+        config.content = context.content;
+        Ok(())
+    }
+
+    pub(crate) async fn filter_published(&self, data_id: &str, content: &str) -> Result<String> {
+        if self.filters.is_empty() {
+            return Ok(content.to_string());
+        }
+        let context = self.context(&self.group, data_id, content.to_string().into());
+        let context = self
+            .run_filters(context, |filter, context| filter.on_publish(context))
+            .await?;
+        String::from_utf8(context.content.into()).map_err(|e| Error::Decode(e.utf8_error()))
+    }
+
+    fn context(&self, group: &str, data_id: &str, content: Bytes) -> ConfigContext {
+        ConfigContext {
+            data_id: data_id.to_string(),
+            group: group.to_string(),
+            namespace: self.namespace.clone(),
+            content,
+        }
+    }
+
+    async fn run_filters(
+        &self,
+        mut context: ConfigContext,
+        apply: impl Fn(&dyn ConfigFilter, ConfigContext) -> FilterFuture,
+    ) -> Result<ConfigContext> {
+        for filter in &self.filters {
+            context = apply(filter.as_ref(), context)
+                .await
+                .map_err(|reason| Error::Filter {
+                    filter: filter.name().to_string(),
+                    reason,
+                })?;
+        }
+        Ok(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    struct Append(&'static str, i32);
+
+    impl ConfigFilter for Append {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn order(&self) -> i32 {
+            self.1
+        }
+
+        fn on_fetch(&self, mut config: ConfigContext) -> FilterFuture {
+            let suffix = self.0;
+            Box::pin(async move {
+                let mut content = config.content.to_vec();
+                content.extend_from_slice(suffix.as_bytes());
+                config.content = content.into();
+                Ok(config)
+            })
+        }
+    }
+
+    struct Reject;
+
+    impl ConfigFilter for Reject {
+        fn name(&self) -> &str {
+            "reject"
+        }
+
+        fn on_publish(&self, _: ConfigContext) -> FilterFuture {
+            Box::pin(async { Err("read only".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn chain() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_filter(Append("b", 1))
+        .with_filter(Append("c", 1))
+        .with_filter(Append("a", -1))
+        .with_filter(Reject);
+
+        let mut config = FetchedConfig::default();
+        nacos
+            .filter_fetched("DEFAULT_GROUP", "app.yaml", &mut config)
+            .await
+            .unwrap();
+        assert_eq!(config.content, "abc");
+
+        let error = nacos.filter_published("app.yaml", "x").await.unwrap_err();
+        assert!(matches!(error, Error::Filter { filter, .. } if filter == "reject"));
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use md5::{Digest, Md5};
+use serde_json::Value;
+
+use crate::{ConfigHandle, Nacos, Result};
+
+/// A feature flag, either a boolean or an object with `enabled` (default `true`) and a rollout
+/// `percentage` (default `100`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flag {
+    pub enabled: bool,
+    pub percentage: u8,
+}
+
+impl Flag {
+    fn from_value(value: &Value) -> Option<Self> {
+        let enabled = |value: &Value| match value {
+            Value::Bool(enabled) => Some(*enabled),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        let percentage = |value: &Value| match value {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().trim_end_matches('%').parse().ok(),
+            _ => None,
+        };
+        match value {
+            Value::Object(map) => Some(Flag {
+                enabled: map.get("enabled").map_or(Some(true), enabled)?,
+                percentage: map
+                    .get("percentage")
+                    .map_or(Some(100.0), percentage)?
+                    .clamp(0.0, 100.0) as u8,
+            }),
+            value => Some(Flag {
+                enabled: enabled(value)?,
+                percentage: 100,
+            }),
+        }
+    }
+
+    /// Whether the flag is on for everyone.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled && self.percentage >= 100
+    }
+
+    /// Whether the flag is on for `key`, e.g. a user id. Each key lands in a stable bucket per
+    /// flag, so raising the percentage only ever adds keys.
+    pub fn is_enabled_for(&self, name: &str, key: &str) -> bool {
+        self.enabled && bucket(name, key) < self.percentage
+    }
+}
+
+fn bucket(name: &str, key: &str) -> u8 {
+    let digest = Md5::new()
+        .chain_update(name)
+        .chain_update(":")
+        .chain_update(key)
+        .finalize();
+    (u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 100) as u8
+}
+
+/// Feature flags of a hot-reloaded config, created by [`Nacos::watch_flags`]. Unknown or
+/// invalid flags are off.
+#[derive(Clone)]
+pub struct FeatureFlags {
+    handle: ConfigHandle<Value>,
+}
+
+impl FeatureFlags {
+    pub fn get(&self, name: &str) -> Option<Flag> {
+        flag(&self.handle.load(), name)
+    }
+
+    /// Whether the flag is on for everyone.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|flag| flag.is_enabled())
+    }
+
+    /// Whether the flag is on for `key`, see [`Flag::is_enabled_for`].
+    pub fn is_enabled_for(&self, name: &str, key: &str) -> bool {
+        self.get(name)
+            .is_some_and(|flag| flag.is_enabled_for(name, key))
+    }
+
+    /// All valid flags.
+    pub fn flags(&self) -> HashMap<String, Flag> {
+        match &*self.handle.load() {
+            Value::Object(map) => map
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), Flag::from_value(value)?)))
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    /// Waits until the flag changes, including being added or removed, and returns it.
+    pub async fn flag_changed(&mut self, name: &str) -> Option<Flag> {
+        let current = self.get(name);
+        loop {
+            self.handle.changed().await;
+            let flag = self.get(name);
+            if flag != current {
+                return flag;
+            }
+        }
+    }
+}
+
+fn flag(flags: &Value, name: &str) -> Option<Flag> {
+    Flag::from_value(flags.get(name)?)
+}
+
+impl Nacos {
+    /// Watches a config of feature flags, keyed by flag name at the top level.
+    pub async fn watch_flags(&self, data_id: &str) -> Result<FeatureFlags> {
+        Ok(FeatureFlags {
+            handle: self.watch_layered_live(&[data_id], None).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let flags = json!({
+            "new_checkout": true,
+            "legacy": "false",
+            "beta": {"percentage": "30"},
+            "paused": {"enabled": false, "percentage": 100},
+            "broken": [1],
+        });
+        assert!(flag(&flags, "new_checkout").unwrap().is_enabled());
+        assert!(!flag(&flags, "legacy").unwrap().is_enabled());
+        assert!(!flag(&flags, "paused")
+            .unwrap()
+            .is_enabled_for("paused", "a"));
+        assert_eq!(flag(&flags, "broken"), None);
+        assert_eq!(flag(&flags, "missing"), None);
+
+        let beta = flag(&flags, "beta").unwrap();
+        assert!(!beta.is_enabled());
+        let enabled = (0..1000)
+            .filter(|i| beta.is_enabled_for("beta", &i.to_string()))
+            .count();
+        assert!((200..400).contains(&enabled), "{enabled}");
+        assert_eq!(
+            beta.is_enabled_for("beta", "user-1"),
+            beta.is_enabled_for("beta", "user-1")
+        );
+
+        // Raising the percentage keeps earlier keys enabled.
+        let wider = Flag {
+            percentage: 60,
+            ..beta
+        };
+        assert!((0..1000)
+            .map(|i| i.to_string())
+            .filter(|key| beta.is_enabled_for("beta", key))
+            .all(|key| wider.is_enabled_for("beta", &key)));
+    }
+}
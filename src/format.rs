@@ -0,0 +1,137 @@
+use serde::de::{value::MapDeserializer, DeserializeOwned};
+
+use crate::{Error, Properties, Result};
+
+/// Content format of a config, used by the typed APIs to deserialize it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFormat {
+    Json,
+    /// Requires the `yaml` feature.
+    Yaml,
+    /// Requires the `toml` feature.
+    Toml,
+    /// Java `.properties`, deserialized as a flat map of string keys to string values.
+    Properties,
+}
+
+impl ConfigFormat {
+    /// Maps a Nacos config type (`json`, `yaml`, ...) to a format.
+    pub fn from_config_type(config_type: &str) -> Option<Self> {
+        match config_type.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "properties" => Some(Self::Properties),
+            _ => None,
+        }
+    }
+
+    /// The Nacos config type of the format.
+    pub fn config_type(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Properties => "properties",
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, content: &[u8]) -> Result<T> {
+        match self {
+            Self::Json => serde_json::from_slice(content).map_err(|e| self.parse_error(e)),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::from_slice(content).map_err(|e| self.parse_error(e)),
+            #[cfg(not(feature = "yaml"))]
+            Self::Yaml => Err(Error::UnsupportedFormat(self, "yaml")),
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_slice(content).map_err(|e| self.parse_error(e)),
+            #[cfg(not(feature = "toml"))]
+            Self::Toml => Err(Error::UnsupportedFormat(self, "toml")),
+            Self::Properties => {
+                let content = std::str::from_utf8(content).map_err(|e| self.parse_error(e))?;
+                let properties = Properties::parse(content)?;
+                T::deserialize(MapDeserializer::<_, serde::de::value::Error>::new(
+                    properties.into_iter(),
+                ))
+                .map_err(|e| self.parse_error(e))
+            }
+        }
+    }
+
+    fn parse_error(self, error: impl std::fmt::Display) -> Error {
+        Error::Parse {
+            format: self,
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn config_type() {
+        assert_eq!(
+            ConfigFormat::from_config_type("JSON"),
+            Some(ConfigFormat::Json)
+        );
+        assert_eq!(
+            ConfigFormat::from_config_type("yml"),
+            Some(ConfigFormat::Yaml)
+        );
+        assert_eq!(
+            ConfigFormat::from_config_type("toml"),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(ConfigFormat::from_config_type("text"), None);
+        for format in [
+            ConfigFormat::Json,
+            ConfigFormat::Yaml,
+            ConfigFormat::Properties,
+        ] {
+            assert_eq!(
+                ConfigFormat::from_config_type(format.config_type()),
+                Some(format)
+            );
+        }
+    }
+
+    #[test]
+    fn json() {
+        let map: HashMap<String, u32> = ConfigFormat::Json.deserialize(br#"{"a": 1}"#).unwrap();
+        assert_eq!(map["a"], 1);
+        assert!(ConfigFormat::Json.deserialize::<u32>(b"a").is_err());
+    }
+
+    #[test]
+    fn properties() {
+        let map: HashMap<String, String> = ConfigFormat::Properties
+            .deserialize(b"server.port=8080\nname=demo")
+            .unwrap();
+        assert_eq!(map["server.port"], "8080");
+        assert_eq!(map["name"], "demo");
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml() {
+        let map: HashMap<String, Vec<u32>> = ConfigFormat::Yaml
+            .deserialize(b"a:\n  - 1\n  - 2\n")
+            .unwrap();
+        assert_eq!(map["a"], [1, 2]);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        #[derive(serde::Deserialize)]
+        struct Server {
+            port: u16,
+        }
+        let server: Server = ConfigFormat::Toml.deserialize(b"port = 8080").unwrap();
+        assert_eq!(server.port, 8080);
+    }
+}
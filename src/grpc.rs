@@ -0,0 +1,121 @@
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Endpoint};
+use tower::discover::Change;
+
+use crate::{Instance, Nacos, Result, ServiceSubscription};
+
+/// Number of pending endpoint changes buffered for a channel.
+const CHANGE_CAPACITY: usize = 64;
+
+impl Nacos {
+    /// Builds a tonic [`Channel`] balanced over the available instances of a service, kept in
+    /// sync with the service in the background.
+    pub async fn grpc_channel(&self, service_name: &str) -> Result<Channel> {
+        self.grpc_channel_with(service_name, |endpoint, _| endpoint)
+            .await
+    }
+
+    /// Like [`Self::grpc_channel`], configuring the endpoint of every instance with
+    /// `configure`, e.g. to set timeouts or TLS.
+    pub async fn grpc_channel_with<F>(&self, service_name: &str, configure: F) -> Result<Channel>
+    where
+        F: FnMut(Endpoint, &Instance) -> Endpoint + Send + 'static,
+    {
+        let subscription = self.subscribe_service(service_name).await?;
+        Ok(balance(subscription, service_name, configure))
+    }
+}
+
+/// A channel balanced over the available instances of `subscription`.
+fn balance<F>(subscription: ServiceSubscription, service_name: &str, mut configure: F) -> Channel
+where
+    F: FnMut(Endpoint, &Instance) -> Endpoint + Send + 'static,
+{
+    let (channel, sender) = Channel::balance_channel(CHANGE_CAPACITY);
+    let service_name = service_name.to_string();
+    let mut discover = subscription.discover(move |instance| {
+        Endpoint::from_shared(format!("http://{}", instance.addr()))
+            .map(|endpoint| configure(endpoint, instance))
+    });
+    // Balance channels run on Tokio themselves, and so do the changes fed to them.
+    tokio::spawn(async move {
+        while let Some(Ok(change)) = discover.next().await {
+            let change = match change {
+                Change::Insert(addr, Ok(endpoint)) => Change::Insert(addr, endpoint),
+                Change::Insert(addr, Err(e)) => {
+                    tracing::warn!(endpoint = %addr, service = %service_name, error = %e, "Invalid endpoint");
+                    Change::Remove(addr)
+                }
+                Change::Remove(addr) => Change::Remove(addr),
+            };
+            if sender.send(change).await.is_err() {
+                // The channel was dropped.
+                break;
+            }
+        }
+    });
+    channel
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::{live::AbortOnDrop, naming::ServiceState};
+
+    fn instance(ip: &str, healthy: bool) -> Instance {
+        Instance {
+            instance_id: None,
+            ip: ip.into(),
+            port: 50051,
+            weight: 1.0,
+            healthy,
+            enabled: true,
+            ephemeral: true,
+            cluster_name: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Waits for the background task to configure `count` endpoints.
+    async fn configured(uris: &Mutex<Vec<String>>, count: usize) -> Vec<String> {
+        for _ in 0..100 {
+            if uris.lock().unwrap().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        uris.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn endpoints() {
+        let (sender, receiver) = watch::channel(ServiceState::new(vec![
+            instance("10.0.0.1", true),
+            instance("10.0.0.2", false),
+            // Not a valid authority, skipped without stopping the channel.
+            instance("bad host", true),
+        ]));
+        let subscription = ServiceSubscription::new(receiver, AbortOnDrop(vec![]));
+        let uris = Arc::new(Mutex::new(Vec::new()));
+        let seen = uris.clone();
+        let _channel = balance(subscription, "orders", move |endpoint, _| {
+            seen.lock().unwrap().push(endpoint.uri().to_string());
+            endpoint
+        });
+        assert_eq!(configured(&uris, 1).await, ["http://10.0.0.1:50051/"]);
+
+        sender.send_modify(|state| *state = ServiceState::new(vec![instance("10.0.0.3", true)]));
+        assert_eq!(
+            configured(&uris, 2).await,
+            ["http://10.0.0.1:50051/", "http://10.0.0.3:50051/"]
+        );
+    }
+}
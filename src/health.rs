@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use crate::{runtime::SystemTime, Nacos, Result};
+
+/// Status of a client, see [`Nacos::health`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Health {
+    /// Why the server is not ready, `None` if it is.
+    pub server_error: Option<String>,
+    /// Why logging in with the client's credentials failed, `None` if it succeeded or the
+    /// client has none.
+    pub auth_error: Option<String>,
+    /// Every config watched by this client and its clones, sorted by namespace, group and data id.
+    pub listeners: Vec<ListenerHealth>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerHealth {
+    /// Namespace of the config, empty for the public one.
+    pub tenant: String,
+    pub group: String,
+    pub data_id: String,
+    /// When the server last answered a fetch or long poll of the config.
+    pub last_poll: Option<SystemTime>,
+    /// Whether the config was last served from its snapshot, see [`Nacos::with_snapshot_dir`].
+    pub from_snapshot: bool,
+}
+
+impl Health {
+    pub fn server_reachable(&self) -> bool {
+        self.server_error.is_none()
+    }
+
+    /// Whether the server is ready, logging in works and every listener polled within
+    /// `max_staleness`. Long polls take up to 30 seconds, so `max_staleness` should be
+    /// comfortably longer.
+    pub fn is_ready(&self, max_staleness: Duration) -> bool {
+        let now = SystemTime::now();
+        self.server_reachable()
+            && self.auth_error.is_none()
+            && self.listeners.iter().all(|listener| {
+                listener.last_poll.is_some_and(|last_poll| {
+                    now.duration_since(last_poll)
+                        .map_or(true, |age| age <= max_staleness)
+                })
+            })
+    }
+}
+
+impl Nacos {
+    /// Checks the server's readiness and reports the state of the listeners.
+    pub async fn health(&self) -> Health {
+        let url = self.make_url("/nacos/v1/console/health/readiness");
+        let request = self.client.get(url);
+        let server_error = self
+            .send("nacos.health", request, &[])
+            .await
+            .err()
+            .map(|e| e.to_string());
+
+        #[cfg(feature = "auth")]
+        let auth_error = self.access_token().await.err().map(|e| e.to_string());
+        #[cfg(not(feature = "auth"))]
+        let auth_error = None;
+
+        Health {
+            server_error,
+            auth_error,
+            listeners: self.listener_health(),
+        }
+    }
+
+    /// Fails unless the server can be reached and, with credentials, accepts them.
+    pub async fn check_connection(&self) -> Result<()> {
+        #[cfg(feature = "auth")]
+        self.access_token().await?;
+        let url = self.make_url("/nacos/v1/console/server/state");
+        let request = self.client.get(url);
+        self.send("nacos.server.state", request, &[]).await?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "config"))]
+    fn listener_health(&self) -> Vec<ListenerHealth> {
+        Vec::new()
+    }
+
+    /// The state of the configs watched by this client and its clones.
+    #[cfg(feature = "config")]
+    fn listener_health(&self) -> Vec<ListenerHealth> {
+        let last_polls = self.last_polls.lock().unwrap().clone();
+        let snapshots_served = self.snapshots_served.lock().unwrap().clone();
+        let mut listeners: Vec<_> = self
+            .current_config
+            .lock()
+            .unwrap()
+            .keys()
+            .map(|key| ListenerHealth {
+                tenant: key.tenant.clone(),
+                group: key.group.clone(),
+                data_id: key.data_id.clone(),
+                last_poll: last_polls.get(key).copied(),
+                from_snapshot: snapshots_served.contains(key),
+            })
+            .collect();
+        listeners.sort_by(|a, b| {
+            (&a.tenant, &a.group, &a.data_id).cmp(&(&b.tenant, &b.group, &b.data_id))
+        });
+        listeners
+    }
+
+    #[cfg(feature = "config")]
+    pub(crate) fn record_poll(&self, key: &crate::key::ConfigKey) {
+        self.last_polls
+            .lock()
+            .unwrap()
+            .insert(key.clone(), SystemTime::now());
+    }
+}
+
+#[cfg(all(test, feature = "config"))]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn health() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let polled = nacos.config_key("DEFAULT_GROUP", "polled");
+        let never = nacos.config_key("DEFAULT_GROUP", "never");
+        for key in [&polled, &never] {
+            nacos
+                .current_config
+                .lock()
+                .unwrap()
+                .track(key.clone(), None);
+        }
+        nacos.record_poll(&polled);
+        nacos.snapshots_served.lock().unwrap().insert(never.clone());
+
+        let health = nacos.health().await;
+        assert!(!health.server_reachable());
+        let listeners: Vec<_> = health
+            .listeners
+            .iter()
+            .map(|l| l.data_id.as_str())
+            .collect();
+        assert_eq!(listeners, ["never", "polled"]);
+        assert!(health.listeners[1].last_poll.is_some());
+        assert!(health.listeners[0].from_snapshot);
+
+        let health = Health {
+            server_error: None,
+            ..health
+        };
+        assert!(!health.is_ready(Duration::from_secs(60)));
+        let health = Health {
+            listeners: health.listeners[1..].to_vec(),
+            ..health
+        };
+        assert!(health.is_ready(Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,86 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+
+use crate::{Error, Result};
+
+/// Bytes searched for the title of a page, and enough to tell whether a response is one.
+pub(crate) const MAX_TITLE_SEARCH: usize = 4096;
+
+/// Characters of a page title shown in errors.
+const MAX_TITLE_LEN: usize = 100;
+
+/// Fails if a config fetch was answered with an HTML page instead of the config, e.g. the
+/// sign-in page of a gateway in front of Nacos, often with status 200. Nacos always sends the
+/// `Content-MD5` of configs, so configs that are HTML documents are still delivered.
+pub(crate) fn check_not_html(data_id: &str, headers: &HeaderMap, content: &[u8]) -> Result<()> {
+    if super::content_md5(headers).is_some() {
+        return Ok(());
+    }
+    let html_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .trim_start()
+                .to_ascii_lowercase()
+                .starts_with("text/html")
+        });
+    if !html_type && !looks_like_html(content) {
+        return Ok(());
+    }
+    Err(Error::HtmlPage {
+        data_id: data_id.to_string(),
+        title: title(content),
+    })
+}
+
+fn looks_like_html(content: &[u8]) -> bool {
+    let content = content.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(content);
+    let start = content
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(content.len());
+    let prefix = &content[start..content.len().min(start + 14)];
+    let prefix = prefix.to_ascii_lowercase();
+    prefix.starts_with(b"<!doctype html") || prefix.starts_with(b"<html")
+}
+
+/// The `<title>` of a page, with its whitespace collapsed.
+fn title(content: &[u8]) -> Option<String> {
+    let head = &content[..content.len().min(MAX_TITLE_SEARCH)];
+    let head = String::from_utf8_lossy(head);
+    let lower = head.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</")?;
+    let title: Vec<_> = head[start..end].split_whitespace().collect();
+    let title = title.join(" ");
+    (!title.is_empty()).then(|| title.chars().take(MAX_TITLE_LEN).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_page() {
+        let page =
+            b"\n<!DOCTYPE html>\n<html><head><TITLE>\n  Sign in - SSO </TITLE></head></html>";
+        let error = check_not_html("app.yaml", &HeaderMap::new(), page).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::HtmlPage { title: Some(title), .. } if title == "Sign in - SSO"
+        ));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, "text/html; charset=UTF-8".parse().unwrap());
+        assert!(check_not_html("app.yaml", &headers, b"Bad gateway").is_err());
+        assert!(check_not_html("app.yaml", &HeaderMap::new(), b"a: <html>").is_ok());
+
+        // Nacos serving a config that is a page.
+        headers.insert(
+            "Content-MD5",
+            crate::protocol::md5_hex(page).parse().unwrap(),
+        );
+        assert!(check_not_html("index.html", &headers, page).is_ok());
+    }
+}
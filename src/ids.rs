@@ -0,0 +1,142 @@
+use std::{fmt, ops::Deref, str::FromStr};
+
+use crate::{Error, Result};
+
+/// A data id that the server accepts: at most 256 ASCII letters, digits and `-_.:`.
+///
+/// Derefs to `str`, so it can be passed to every API taking a data id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DataId(String);
+
+/// A group that the server accepts: at most 128 ASCII letters, digits and `-_.:`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Group(String);
+
+macro_rules! identifier {
+    ($name:ident, $kind:literal, $max_len:literal) => {
+        impl $name {
+            pub const MAX_LEN: usize = $max_len;
+
+            pub fn new(id: impl Into<String>) -> Result<Self> {
+                let id = id.into();
+                validate($kind, &id, Self::MAX_LEN)?;
+                Ok(Self(id))
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(id: &str) -> Result<Self> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = Error;
+
+            fn try_from(id: &str) -> Result<Self> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = Error;
+
+            fn try_from(id: String) -> Result<Self> {
+                Self::new(id)
+            }
+        }
+    };
+}
+
+identifier!(DataId, "data id", 256);
+identifier!(Group, "group", 128);
+
+/// Checks an identifier like the server's `ParamUtils`.
+pub(crate) fn validate(kind: &'static str, id: &str, max_len: usize) -> Result<()> {
+    let reason = if id.is_empty() {
+        "it is empty"
+    } else if id.len() > max_len {
+        "it is too long"
+    } else if id.contains(|c: char| !is_valid_char(c)) {
+        "only ASCII letters, digits and `-_.:` are allowed"
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidId {
+        kind,
+        id: id.to_string(),
+        reason,
+    })
+}
+
+/// Checks the identifiers of a config before sending them.
+pub(crate) fn validate_key(group: &str, data_id: &str) -> Result<()> {
+    validate("group", group, Group::MAX_LEN)?;
+    validate("data id", data_id, DataId::MAX_LEN)
+}
+
+fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        let data_id: DataId = "com.example:app-dev_1.yaml".parse().unwrap();
+        assert_eq!(&*data_id, "com.example:app-dev_1.yaml");
+        assert!(Group::new("DEFAULT_GROUP").is_ok());
+
+        for invalid in ["", "app yaml", "app#1", "配置", &"a".repeat(257)] {
+            assert!(
+                matches!(
+                    DataId::new(invalid),
+                    Err(Error::InvalidId {
+                        kind: "data id",
+                        ..
+                    })
+                ),
+                "{}",
+                invalid
+            );
+        }
+        assert!(Group::new("g".repeat(129)).is_err());
+        let message = DataId::new("app yaml").unwrap_err().to_string();
+        assert_eq!(
+            message,
+            "invalid data id \"app yaml\": only ASCII letters, digits and `-_.:` are allowed"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+use crate::{ConfigFormat, Nacos, Result};
+
+/// Identifies a config across groups and namespaces: the same data id may exist in several.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConfigKey {
+    /// Id of the namespace, empty for the public one.
+    pub tenant: String,
+    pub group: String,
+    pub data_id: String,
+}
+
+impl ConfigKey {
+    /// A config of the public namespace.
+    pub fn new(group: impl Into<String>, data_id: impl Into<String>) -> Self {
+        Self {
+            tenant: String::new(),
+            group: group.into(),
+            data_id: data_id.into(),
+        }
+    }
+
+    /// In the namespace with id `tenant`, the public one if empty or `public`.
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = crate::namespace::normalize(Some(tenant.into())).unwrap_or_default();
+        self
+    }
+}
+
+impl Nacos {
+    /// Like [`Self::get_config`] for the config at `key`, whatever the client's group and
+    /// namespace.
+    pub async fn get_config_by_key(&self, key: &ConfigKey) -> Result<Bytes> {
+        let nacos = self.in_namespace(key.tenant.as_str());
+        Ok(nacos.fetch_config(&key.group, &key.data_id).await?.content)
+    }
+
+    /// Like [`Self::get_config_as`] for the config at `key`.
+    pub async fn get_config_as_by_key<T: DeserializeOwned>(
+        &self,
+        key: &ConfigKey,
+        format: Option<ConfigFormat>,
+    ) -> Result<T> {
+        let nacos = self.in_namespace(key.tenant.as_str());
+        nacos
+            .fetch_config(&key.group, &key.data_id)
+            .await?
+            .deserialize(format)
+    }
+
+    #[cfg(feature = "config")]
+    /// Like [`Self::wait_for_new_config`] for the config at `key`.
+    pub async fn wait_for_new_config_by_key(&self, key: &ConfigKey) -> Result<Bytes> {
+        let nacos = self.in_namespace(key.tenant.as_str());
+        Ok(nacos
+            .wait_for_changed_config(&key.group, &key.data_id)
+            .await?
+            .content)
+    }
+
+    #[cfg(feature = "config")]
+    /// Like [`Self::watch_live`] for the config at `key`, so that one client can watch configs
+    /// of several groups and namespaces, all long polled together.
+    pub async fn watch_live_by_key<T>(
+        &self,
+        key: &ConfigKey,
+        format: Option<ConfigFormat>,
+    ) -> Result<crate::ConfigHandle<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        self.in_namespace(key.tenant.as_str())
+            .in_group(key.group.as_str())
+            .watch_live(&key.data_id, format)
+            .await
+    }
+
+    /// The key of a config in the namespace of this client.
+    pub(crate) fn config_key(&self, group: &str, data_id: &str) -> ConfigKey {
+        ConfigKey {
+            tenant: self.namespace.clone().unwrap_or_default(),
+            group: group.to_string(),
+            data_id: data_id.to_string(),
+        }
+    }
+
+    /// Scopes calls to `group` instead of the client's, as in
+    /// `nacos.in_group("orders").get_config("app.yaml")`. The returned client is a clone,
+    /// sharing the tracked configs, validators and connection pool.
+    pub fn in_group(&self, group: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Scopes calls to the namespace with id `namespace`, the public one if empty or
+    /// `public`, like [`Self::in_group`].
+    pub fn in_namespace(&self, namespace: impl Into<String>) -> Self {
+        Self {
+            namespace: crate::namespace::normalize(Some(namespace.into())),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn scoped() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let orders = nacos.in_group("ORDERS").in_namespace("dev");
+        assert_eq!(orders.group, "ORDERS");
+        assert_ne!(
+            nacos.config_key("ORDERS", "app.yaml"),
+            orders.config_key("ORDERS", "app.yaml")
+        );
+        assert_eq!(orders.in_namespace("").namespace, None);
+        assert_eq!(orders.in_namespace("public").namespace, None);
+        assert_eq!(
+            ConfigKey::new("APP", "app.yaml").with_tenant("public"),
+            ConfigKey::new("APP", "app.yaml")
+        );
+
+        #[cfg(feature = "config")]
+        {
+            // Tracked state is shared, and keyed by namespace.
+            let key = orders.config_key("ORDERS", "app.yaml");
+            orders.update_md5(key.clone(), [1; 16]).await;
+            assert_eq!(
+                nacos.current_config.lock().unwrap().get(&key),
+                Some(Some([1; 16]))
+            );
+            assert_eq!(nacos.current_config.lock().unwrap().len(), 1);
+            assert_eq!(key, ConfigKey::new("ORDERS", "app.yaml").with_tenant("dev"));
+        }
+    }
+}
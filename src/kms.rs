@@ -0,0 +1,66 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use crate::{Nacos, Result};
+
+/// Prefix of data ids whose content is encrypted with a KMS key, following the Alibaba Cloud
+/// convention.
+pub(crate) const CIPHER_PREFIX: &str = "cipher-";
+
+pub type KmsFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
+/// A key management service that decrypts `cipher-` configs, see [`Nacos::with_kms`].
+pub trait Kms: Send + Sync + 'static {
+    /// Decrypts the content of a config, failing with a reason.
+    fn decrypt(&self, data_id: &str, ciphertext: String) -> KmsFuture;
+}
+
+impl Nacos {
+    /// Decrypts the content of configs whose data id starts with `cipher-` with `kms`, so that
+    /// all fetch and watch APIs return plaintext. [`EncryptionPlugin`]s take precedence for
+    /// their algorithm's prefix.
+    ///
+    /// Only configs encrypted directly with a KMS key are supported, not envelope encrypted
+    /// `cipher-kms-aes-` ones.
+    pub fn with_kms(mut self, kms: impl Kms) -> Self {
+        self.kms = Some(Arc::new(kms));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::FetchedConfig;
+
+    struct Reverse;
+
+    impl Kms for Reverse {
+        fn decrypt(&self, _: &str, ciphertext: String) -> KmsFuture {
+            Box::pin(async move { Ok(ciphertext.chars().rev().collect()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn decrypt() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_kms(Reverse);
+        let mut config = FetchedConfig {
+            content: "terces\n".into(),
+            md5: [1; 16],
+            ..Default::default()
+        };
+        nacos.decrypt("plain.yaml", &mut config).await.unwrap();
+        assert_eq!(config.content, "terces\n");
+        nacos.decrypt("cipher-db.yaml", &mut config).await.unwrap();
+        assert_eq!(config.content, "secret");
+        // The served md5 keeps the listener in sync with the server.
+        assert_eq!(config.md5, [1; 16]);
+    }
+}
@@ -0,0 +1,365 @@
+use std::io;
+
+use reqwest::{header::CONTENT_TYPE, Client, StatusCode};
+use serde_json::{json, Map, Value};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    live::{AbortOnDrop, RETRY_DELAY},
+    ConfigEvent, Error, Nacos, Result,
+};
+
+const SERVICE_ACCOUNT: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// The kind of Kubernetes object that configs are mirrored into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorKind {
+    ConfigMap,
+    Secret,
+}
+
+impl MirrorKind {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::ConfigMap => "configmaps",
+            Self::Secret => "secrets",
+        }
+    }
+
+    /// Secrets take plain text in `stringData` and base64 in `data`.
+    fn data_field(self) -> &'static str {
+        match self {
+            Self::ConfigMap => "data",
+            Self::Secret => "stringData",
+        }
+    }
+}
+
+/// Mirrors Nacos configs into ConfigMaps or Secrets through the Kubernetes API, for workloads
+/// that can't use this crate but can mount or read those objects.
+#[derive(Clone)]
+pub struct KubernetesMirror {
+    client: Client,
+    api_server: String,
+    token: String,
+    namespace: String,
+}
+
+impl KubernetesMirror {
+    /// Targets `namespace` on `api_server`, e.g. `https://10.0.0.1:443`, with a bearer token.
+    pub fn new(
+        client: Client,
+        api_server: impl Into<String>,
+        token: impl Into<String>,
+        namespace: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            api_server: api_server.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            namespace: namespace.into(),
+        }
+    }
+
+    /// Uses the pod's service account, its namespace and the API server from the environment.
+    pub fn in_cluster() -> Result<Self> {
+        let host = std::env::var("KUBERNETES_SERVICE_HOST").map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "KUBERNETES_SERVICE_HOST is not set",
+            )
+        })?;
+        let port = std::env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".into());
+        let token = std::fs::read_to_string(format!("{SERVICE_ACCOUNT}/token"))?;
+        let namespace = std::fs::read_to_string(format!("{SERVICE_ACCOUNT}/namespace"))?;
+        let ca = std::fs::read(format!("{SERVICE_ACCOUNT}/ca.crt"))?;
+        let client = Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&ca)?)
+            .build()?;
+        let host = if host.contains(':') {
+            format!("[{host}]")
+        } else {
+            host
+        };
+        Ok(Self::new(
+            client,
+            format!("https://{host}:{port}"),
+            token.trim(),
+            namespace.trim(),
+        ))
+    }
+
+    /// Writes configs of `nacos` into the object `name`, one key per data id, and keeps them
+    /// updated until the returned value is dropped. The object is created if it doesn't exist.
+    ///
+    /// Mirroring is one way, changes made to the object are overwritten on the next update.
+    /// Keys of configs that don't exist are removed, as are other keys of the object. Data ids
+    /// must be valid keys, which don't allow `:`.
+    pub async fn mirror(
+        &self,
+        nacos: &Nacos,
+        data_ids: &[&str],
+        kind: MirrorKind,
+        name: &str,
+    ) -> Result<Mirror> {
+        for data_id in data_ids {
+            check_key(data_id)?;
+        }
+        let mut contents = Map::new();
+        for data_id in data_ids {
+            match nacos.fetch_and_track(&nacos.group, data_id).await {
+                Ok(config) => {
+                    contents.insert(data_id.to_string(), config.to_text()?.into());
+                }
+                // Mirrored once it is created.
+                Err(e) if e.is_not_found() => {
+                    let key = nacos.config_key(&nacos.group, data_id);
+                    nacos.current_config.lock().unwrap().track(key, None);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        for key in self.keys(kind, name).await? {
+            contents.entry(key).or_insert(Value::Null);
+        }
+        self.apply(kind, name, contents).await?;
+
+        let mut tasks: Vec<_> = data_ids
+            .iter()
+            .map(|data_id| {
+                let mirror = self.clone();
+                let watched = nacos.clone();
+                let data_id = data_id.to_string();
+                let name = name.to_string();
+                nacos.spawn(async move {
+                    loop {
+                        let synced =
+                            match watched.wait_for_changed_config(&watched.group, &data_id).await {
+                                Ok(config) => match config.to_text() {
+                                    Ok(text) => {
+                                        let contents =
+                                            [(data_id.clone(), text.into())].into_iter().collect();
+                                        mirror.apply(kind, &name, contents).await
+                                    }
+                                    Err(e) => Err(e),
+                                },
+                                Err(e) => Err(e),
+                            };
+                        if let Err(e) = synced {
+                            tracing::warn!(data_id, object = %name, error = %e, "Failed to mirror config");
+                            watched.sleep(RETRY_DELAY).await;
+                        }
+                    }
+                })
+            })
+            .collect();
+        tasks.push(nacos.spawn(self.clone().remove_deleted(
+            nacos.clone(),
+            data_ids.iter().map(|data_id| data_id.to_string()).collect(),
+            kind,
+            name.to_string(),
+        )));
+        Ok(Mirror {
+            _tasks: AbortOnDrop(tasks),
+        })
+    }
+
+    /// Removes the keys of configs when they are deleted.
+    async fn remove_deleted(
+        self,
+        nacos: Nacos,
+        data_ids: Vec<String>,
+        kind: MirrorKind,
+        name: String,
+    ) {
+        let mut events = nacos.subscribe_events();
+        loop {
+            match events.recv().await {
+                Ok(ConfigEvent::Deleted { group, data_id })
+                    if group == nacos.group && data_ids.contains(&data_id) => {}
+                // The missed events may be deletions.
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+                Ok(_) => continue,
+            }
+            let deleted: Map<String, Value> = data_ids
+                .iter()
+                .filter(|data_id| {
+                    let key = nacos.config_key(&nacos.group, data_id);
+                    nacos.current_config.lock().unwrap().get(&key) == Some(None)
+                })
+                .map(|data_id| (data_id.clone(), Value::Null))
+                .collect();
+            if deleted.is_empty() {
+                continue;
+            }
+            if let Err(e) = self.apply(kind, &name, deleted).await {
+                tracing::warn!(object = %name, error = %e, "Failed to remove deleted configs");
+            }
+        }
+    }
+
+    fn collection(&self, kind: MirrorKind) -> String {
+        format!(
+            "{}/api/v1/namespaces/{}/{}",
+            self.api_server,
+            self.namespace,
+            kind.resource()
+        )
+    }
+
+    /// The keys of the object, none if it doesn't exist.
+    async fn keys(&self, kind: MirrorKind, name: &str) -> Result<Vec<String>> {
+        let response = self
+            .client
+            .get(format!("{}/{name}", self.collection(kind)))
+            .bearer_auth(&self.token)
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let object: Value = response.error_for_status()?.json().await?;
+        // Secrets hold the keys of `stringData` in `data` too.
+        Ok(object["data"]
+            .as_object()
+            .map(|data| data.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// Merges `contents` into the object, creating it if needed. Keys set to `null` are
+    /// removed.
+    async fn apply(
+        &self,
+        kind: MirrorKind,
+        name: &str,
+        contents: Map<String, Value>,
+    ) -> Result<()> {
+        let collection = self.collection(kind);
+        let response = self
+            .client
+            .patch(format!("{collection}/{name}"))
+            .bearer_auth(&self.token)
+            .header(CONTENT_TYPE, "application/merge-patch+json")
+            .body(patch(kind, &contents).to_string())
+            .send()
+            .await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+            return Ok(());
+        }
+        let contents = contents
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .collect();
+        self.client
+            .post(collection)
+            .bearer_auth(&self.token)
+            .json(&new_object(kind, name, contents))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Keys of ConfigMaps and Secrets allow fewer characters than data ids.
+fn check_key(data_id: &str) -> Result<()> {
+    let reason = if data_id.len() > 253 {
+        "Kubernetes keys are at most 253 characters"
+    } else if data_id
+        .contains(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '_' | '.'))
+    {
+        "Kubernetes keys allow only ASCII letters, digits and `-_.`"
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidId {
+        kind: "data id",
+        id: data_id.to_string(),
+        reason,
+    })
+}
+
+/// The merge patch setting `contents` in the object.
+fn patch(kind: MirrorKind, contents: &Map<String, Value>) -> Value {
+    let (removed, set): (Map<_, _>, Map<_, _>) = contents
+        .clone()
+        .into_iter()
+        .partition(|(_, value)| value.is_null());
+    let mut patch = json!({ kind.data_field(): set });
+    // Keys of Secrets are removed from `data`, `stringData` is only written.
+    for (key, value) in removed {
+        patch["data"][key] = value;
+    }
+    patch
+}
+
+fn new_object(kind: MirrorKind, name: &str, contents: Map<String, Value>) -> Value {
+    let mut object = json!({
+        "apiVersion": "v1",
+        "kind": match kind {
+            MirrorKind::ConfigMap => "ConfigMap",
+            MirrorKind::Secret => "Secret",
+        },
+        "metadata": {
+            "name": name,
+            "labels": {"app.kubernetes.io/managed-by": "nacos"},
+        },
+    });
+    object[kind.data_field()] = Value::Object(contents);
+    object
+}
+
+/// Returned by [`KubernetesMirror::mirror`], stops mirroring when dropped.
+pub struct Mirror {
+    _tasks: AbortOnDrop,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object() {
+        let contents = [("app.yaml".to_string(), "a: 1".into())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            new_object(MirrorKind::Secret, "app", contents),
+            json!({
+                "apiVersion": "v1",
+                "kind": "Secret",
+                "metadata": {"name": "app", "labels": {"app.kubernetes.io/managed-by": "nacos"}},
+                "stringData": {"app.yaml": "a: 1"},
+            })
+        );
+    }
+
+    #[test]
+    fn removed_keys() {
+        let contents: Map<_, _> = [
+            ("app.yaml".to_string(), "a: 1".into()),
+            ("old.yaml".to_string(), Value::Null),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            patch(MirrorKind::Secret, &contents),
+            json!({"stringData": {"app.yaml": "a: 1"}, "data": {"old.yaml": null}})
+        );
+        assert_eq!(
+            patch(MirrorKind::ConfigMap, &contents),
+            json!({"data": {"app.yaml": "a: 1", "old.yaml": null}})
+        );
+    }
+
+    #[test]
+    fn keys() {
+        assert!(check_key("app-1.yaml").is_ok());
+        assert!(matches!(
+            check_key("com.example:app.yaml"),
+            Err(Error::InvalidId { reason, .. }) if reason.contains("`-_.`")
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(feature = "config")]
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+#[cfg(feature = "config")]
+use crate::{
+    live::{LiveConfig, RETRY_DELAY},
+    ConfigHandle,
+};
+use crate::{value, ConfigFormat, Error, FetchedConfig, Nacos, Result};
+
+impl Nacos {
+    /// Fetches several configs and merges them, later data ids taking precedence over earlier
+    /// ones. Data ids that don't exist are skipped. Properties are nested on `.` so that they
+    /// can override keys of structured layers.
+    ///
+    /// `${key}` and `${key:default}` placeholders are then resolved against the merged config
+    /// and environment variables.
+    pub async fn get_layered_config_as<T: DeserializeOwned>(
+        &self,
+        data_ids: &[&str],
+        format: Option<ConfigFormat>,
+    ) -> Result<T> {
+        let mut layers = Vec::with_capacity(data_ids.len());
+        for data_id in data_ids {
+            layers.push(self.fetch_layer(&self.group, data_id, format).await?);
+        }
+        merge_layers(&layers)
+    }
+
+    /// Like [`Self::watch_live`] for a layered config, re-merging whenever any layer changes.
+    #[cfg(feature = "config")]
+    pub async fn watch_layered_live<T>(
+        &self,
+        data_ids: &[&str],
+        format: Option<ConfigFormat>,
+    ) -> Result<ConfigHandle<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let mut layers = Vec::with_capacity(data_ids.len());
+        for data_id in data_ids {
+            layers.push(self.fetch_layer(&self.group, data_id, format).await?);
+        }
+        let config = LiveConfig::new(merge_layers::<T>(&layers)?);
+        let layers = Arc::new(Mutex::new(layers));
+
+        let tasks = data_ids
+            .iter()
+            .enumerate()
+            .map(|(index, data_id)| {
+                let nacos = self.clone();
+                let key = self.config_key(&self.group, data_id);
+                let layers = layers.clone();
+                let config = config.clone();
+                self.spawn(async move {
+                    loop {
+                        let layer = match nacos.listen_for_change(&key).await {
+                            Ok(_) => {
+                                #[cfg(feature = "metrics")]
+                                let notified = std::time::Instant::now();
+                                let layer =
+                                    nacos.fetch_layer(&key.group, &key.data_id, format).await;
+                                #[cfg(feature = "metrics")]
+                                if layer.is_ok() {
+                                    crate::meter::record_change(&key, notified.elapsed());
+                                }
+                                layer
+                            }
+                            Err(e) => Err(e),
+                        };
+                        match layer {
+                            Ok(layer) => {
+                                let mut layers = layers.lock().unwrap();
+                                layers[index] = layer;
+                                match merge_layers(&layers) {
+                                    Ok(merged) => config.store(merged),
+                                    Err(e) => {
+                                        tracing::warn!(data_id = %key.data_id, error = %e, "Ignoring invalid config")
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                tracing::warn!(data_id = %key.data_id, error = %e, "Failed to watch config");
+                                nacos.sleep(RETRY_DELAY).await;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        Ok(ConfigHandle::new(config, tasks))
+    }
+
+    /// Fetches a layer like [`Self::fetch_and_track`], returning `None` if it doesn't exist.
+    async fn fetch_layer(
+        &self,
+        group: &str,
+        data_id: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<Option<Value>> {
+        #[cfg(feature = "config")]
+        let fetched = self.fetch_and_track(group, data_id).await;
+        #[cfg(not(feature = "config"))]
+        let fetched = self.fetch_config(group, data_id).await;
+        match fetched {
+            Ok(config) => config.to_tree(format).map(Some),
+            Err(e) if e.is_not_found() => {
+                // The server reports an empty md5 for configs that don't exist.
+                #[cfg(feature = "config")]
+                {
+                    let key = self.config_key(group, data_id);
+                    self.record_poll(&key);
+                    self.current_config.lock().unwrap().track(key, None);
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl FetchedConfig {
+    fn to_tree(&self, format: Option<ConfigFormat>) -> Result<Value> {
+        value::parse_tree(&self.utf8_content(), self.format(format)?)
+    }
+}
+
+fn merge_layers<T: DeserializeOwned>(layers: &[Option<Value>]) -> Result<T> {
+    let mut merged = Value::Object(Default::default());
+    for layer in layers.iter().flatten() {
+        value::merge(&mut merged, layer.clone());
+    }
+    value::resolve_placeholders(&mut merged).map_err(Error::Placeholder)?;
+    value::from_tree(merged).map_err(Error::Merged)
+}
@@ -1,18 +1,254 @@
+mod access_log;
+#[cfg(feature = "aliyun-kms")]
+mod aliyun_kms;
+#[cfg(feature = "auth")]
+mod auth;
+#[cfg(feature = "axum")]
+mod axum_state;
+#[cfg(not(target_arch = "wasm32"))]
+mod batch;
+mod binding;
+#[cfg(feature = "blocking")]
+mod blocking;
+mod builder;
+#[cfg(feature = "charset")]
+mod charset;
+mod client_config;
+#[cfg(feature = "admin")]
+mod cluster;
+#[cfg(any(feature = "snapshot-gzip", feature = "snapshot-zstd"))]
+mod compression;
+#[cfg(feature = "admin")]
+mod config_admin;
+#[cfg(feature = "config-rs")]
+mod config_source;
+mod deadline;
+#[cfg(feature = "config")]
+mod diff;
+#[cfg(feature = "tower")]
+mod discover;
+mod dns;
+#[cfg(not(target_arch = "wasm32"))]
+mod download;
+mod encryption;
+mod ensure;
+mod error;
+#[cfg(feature = "config")]
+mod event;
+#[cfg(feature = "config")]
+mod evict;
+mod export;
+#[cfg(feature = "figment")]
+mod figment_provider;
+mod filter;
+#[cfg(feature = "config")]
+mod flags;
+mod format;
+#[cfg(feature = "tonic")]
+mod grpc;
+mod health;
+mod html;
+mod ids;
+mod key;
+mod kms;
+#[cfg(feature = "kubernetes")]
+mod kubernetes;
+mod layered;
+mod limit;
+#[cfg(feature = "config")]
+mod listener;
+mod live;
+#[cfg(feature = "config")]
+mod logging;
+#[cfg(feature = "config")]
+mod materialize;
+#[cfg(feature = "metrics")]
+mod meter;
+mod namespace;
+#[cfg(feature = "naming")]
+mod naming;
+#[cfg(feature = "otel")]
+mod otel;
+mod page;
+mod profile;
+mod properties;
+mod protocol;
+mod publish;
+mod redact;
+mod request_id;
+#[cfg(feature = "naming")]
+mod resolve;
+mod response_hook;
+mod runtime;
+#[cfg(feature = "json-schema")]
+mod schema;
+mod servers;
+mod service;
+#[cfg(feature = "access-key")]
+mod sign;
+mod snapshot;
+mod trace;
+mod transport;
+#[cfg(feature = "admin")]
+mod users;
+#[cfg(feature = "config")]
+mod validate;
+mod value;
+
+#[cfg(feature = "aliyun-kms")]
+pub use aliyun_kms::AliyunKms;
+#[cfg(feature = "axum")]
+pub use axum_state::CurrentConfig;
+pub use binding::NacosConfig;
+#[cfg(feature = "blocking")]
+pub use blocking::{BlockingNacos, BlockingWatch};
+pub use builder::NacosBuilder;
+#[cfg(feature = "charset")]
+pub use charset::Charset;
+pub use client_config::ClientConfig;
+#[cfg(feature = "admin")]
+pub use cluster::{ClusterNode, NamingMetrics, ServerState, Switches};
+#[cfg(feature = "snapshot-gzip")]
+pub use compression::Gzip;
+#[cfg(feature = "snapshot-zstd")]
+pub use compression::Zstd;
+#[cfg(feature = "admin")]
+pub use config_admin::{
+    Capacity, ConfigId, ConfigInfo, ConfigListeners, ConflictPolicy, HistoryEntry, ImportResult,
+};
+#[cfg(feature = "config-rs")]
+pub use config_source::{NacosSnapshot, NacosSource};
+pub use deadline::with_deadline;
+#[cfg(feature = "config")]
+pub use diff::{ConfigDiff, KeyChange, LineChange};
+#[cfg(feature = "tower")]
+pub use discover::ServiceDiscover;
+pub use encryption::{Encrypted, EncryptionFuture, EncryptionPlugin};
+pub use ensure::WriteOutcome;
+pub use error::{Error, Result};
+#[cfg(feature = "config")]
+pub use event::ConfigEvent;
+#[cfg(feature = "config")]
+pub use export::EnvExport;
+#[cfg(feature = "figment")]
+pub use figment_provider::NacosProvider;
+pub use filter::{ConfigContext, ConfigFilter, FilterFuture};
+#[cfg(feature = "config")]
+pub use flags::{FeatureFlags, Flag};
+pub use format::ConfigFormat;
+pub use health::{Health, ListenerHealth};
+pub use ids::{DataId, Group};
+pub use key::ConfigKey;
+pub use kms::{Kms, KmsFuture};
+#[cfg(feature = "kubernetes")]
+pub use kubernetes::{KubernetesMirror, Mirror, MirrorKind};
+#[cfg(feature = "config")]
+pub use live::ConfigHandle;
+#[cfg(all(feature = "config", feature = "tracing-subscriber"))]
+pub use logging::reload_env_filter;
+#[cfg(feature = "config")]
+pub use logging::{LogLevelWatcher, LogLevels};
+#[cfg(feature = "config")]
+pub use materialize::Materialized;
+pub use namespace::NamespaceHandle;
+#[cfg(feature = "admin")]
+pub use namespace::NamespaceInfo;
+#[cfg(feature = "naming")]
+pub use naming::{Instance, InstanceDelta, ServiceSubscription};
+pub use page::Page;
+pub use profile::Profiles;
+pub use properties::Properties;
+pub use publish::ChangeMetadata;
+pub use redact::set_log_content;
+pub use request_id::with_request_id;
+#[cfg(feature = "naming")]
+pub use resolve::NacosResolver;
+pub use response_hook::ResponseInfo;
+#[cfg(target_arch = "wasm32")]
+pub use runtime::Wasm;
+pub use runtime::{Runtime, RuntimeFuture, Tokio};
+#[cfg(feature = "naming")]
+pub use service::NamingService;
+pub use service::{ConfigService, ServiceFuture};
+pub use snapshot::SnapshotCompression;
+#[cfg(not(target_arch = "wasm32"))]
+pub use transport::{HttpRequest, HttpResponse, Transport, TransportError, TransportFuture};
+#[cfg(feature = "admin")]
+pub use users::{Action, Permission, RoleBinding, User};
+
+#[cfg(feature = "derive")]
+pub use nacos_derive::NacosConfig;
+
+#[cfg(test)]
+extern crate self as nacos;
+
 use bytes::Bytes;
-use md5::{Digest, Md5};
-use reqwest::{Client, Error};
-use std::{collections::HashMap, net::SocketAddr};
-use tokio::sync::Mutex;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+#[cfg(feature = "config")]
+use std::collections::HashMap;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+#[cfg(feature = "config")]
+use tokio::sync::broadcast;
 
-/// A `Nacos` service without authentication.
+/// A `Nacos` service, authenticated with [`Self::with_credentials`] if the server requires it.
+///
+/// Clones share the tracked config state and the HTTP connection pool.
+#[derive(Clone)]
 pub struct Nacos {
-    use_https: bool,
-    server_addr: SocketAddr,
+    servers: Arc<servers::ServerList>,
     namespace: Option<String>,
     group: String,
-    /// Data id to md5.
-    current_config: Mutex<HashMap<String, String>>,
+    app_name: Option<String>,
+    tag: Option<String>,
+    access_log: bool,
+    timeout: Option<Duration>,
+    max_config_size: Option<usize>,
+    #[cfg(feature = "config")]
+    max_tracked_configs: Option<usize>,
+    #[cfg(feature = "config")]
+    config_diffs: bool,
+    /// Config to md5, `None` if it doesn't exist.
+    #[cfg(feature = "config")]
+    current_config: Arc<std::sync::Mutex<protocol::TrackedConfigs>>,
+    /// Tracked config to its content, read without locking.
+    #[cfg(feature = "config")]
+    cached_content: Arc<arc_swap::ArcSwap<HashMap<key::ConfigKey, Bytes>>>,
     client: Client,
+    #[cfg(not(target_arch = "wasm32"))]
+    transport: Option<Arc<dyn Transport>>,
+    runtime: Arc<dyn Runtime>,
+    dns: dns::DnsCache,
+    #[cfg(feature = "charset")]
+    charset: Option<Charset>,
+    /// Config to validator.
+    #[cfg(feature = "config")]
+    validators: Arc<std::sync::Mutex<HashMap<key::ConfigKey, validate::Validator>>>,
+    #[cfg(feature = "config")]
+    events: broadcast::Sender<ConfigEvent>,
+    #[cfg(feature = "config")]
+    listener: Arc<listener::Listener>,
+    /// Config to when the server last answered a fetch or long poll of it.
+    #[cfg(feature = "config")]
+    last_polls: Arc<std::sync::Mutex<HashMap<key::ConfigKey, runtime::SystemTime>>>,
+    /// Cache of [`Self::list_namespaces`].
+    #[cfg(feature = "admin")]
+    namespaces: Arc<std::sync::Mutex<namespace::NamespaceCache>>,
+    #[cfg(feature = "auth")]
+    auth: Arc<auth::Auth>,
+    kms: Option<Arc<dyn Kms>>,
+    encryption_plugins: Vec<Arc<dyn EncryptionPlugin>>,
+    encrypt_on_publish: bool,
+    /// Sorted by order.
+    filters: Vec<Arc<dyn ConfigFilter>>,
+    response_hooks: Vec<response_hook::ResponseHook>,
+    snapshot_dir: Option<std::path::PathBuf>,
+    snapshot_compression: Option<Arc<dyn SnapshotCompression>>,
+    #[cfg(feature = "snapshot-encryption")]
+    snapshot_keyring: Option<snapshot::Keyring>,
+    /// The configs last served from their snapshot.
+    snapshots_served: Arc<std::sync::Mutex<std::collections::HashSet<key::ConfigKey>>>,
+    snapshot_writes: Arc<snapshot::SnapshotWrites>,
 }
 
 impl Nacos {
@@ -22,99 +258,720 @@ impl Nacos {
         namespace: Option<String>,
         group: String,
     ) -> Self {
+        let dns = dns::DnsCache::default();
         Self {
-            use_https,
-            server_addr,
-            namespace,
+            servers: Arc::new(servers::ServerList::new(vec![
+                (use_https, server_addr).into()
+            ])),
+            namespace: namespace::normalize(namespace),
             group,
+            app_name: None,
+            tag: None,
+            access_log: false,
+            timeout: None,
+            max_config_size: None,
+            #[cfg(feature = "config")]
+            max_tracked_configs: None,
+            #[cfg(feature = "config")]
+            config_diffs: false,
+            #[cfg(feature = "config")]
             current_config: Default::default(),
-            client: Client::new(),
+            #[cfg(feature = "config")]
+            cached_content: Default::default(),
+            client: dns.client(None),
+            #[cfg(not(target_arch = "wasm32"))]
+            transport: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: Arc::new(Tokio),
+            #[cfg(target_arch = "wasm32")]
+            runtime: Arc::new(Wasm),
+            dns,
+            #[cfg(feature = "charset")]
+            charset: None,
+            #[cfg(feature = "config")]
+            validators: Default::default(),
+            #[cfg(feature = "config")]
+            events: broadcast::channel(event::EVENT_CAPACITY).0,
+            #[cfg(feature = "config")]
+            listener: Default::default(),
+            #[cfg(feature = "config")]
+            last_polls: Default::default(),
+            #[cfg(feature = "admin")]
+            namespaces: Default::default(),
+            #[cfg(feature = "auth")]
+            auth: Default::default(),
+            kms: None,
+            encryption_plugins: Vec::new(),
+            encrypt_on_publish: false,
+            filters: Vec::new(),
+            response_hooks: Vec::new(),
+            snapshot_dir: None,
+            snapshot_compression: None,
+            #[cfg(feature = "snapshot-encryption")]
+            snapshot_keyring: None,
+            snapshots_served: Default::default(),
+            snapshot_writes: Default::default(),
+        }
+    }
+
+    /// Identifies the application in the `User-Agent` and `Client-AppName` headers, which show
+    /// in server access logs and the console's list of connected clients, and in the `appName`
+    /// parameter of config fetches and publishes, which the console attributes configs to.
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// Fetches, publishes and deletes the version of configs with `tag` in their `tag`
+    /// parameter.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub async fn get_config(&self, data_id: &str) -> Result<Bytes> {
+        Ok(self.fetch_config(&self.group, data_id).await?.content)
+    }
+
+    #[cfg(feature = "config")]
+    /// The content of a config as last fetched by [`Self::wait_for_new_config`] or a watch,
+    /// without a request or a lock, e.g. for services that read it on every request. `None`
+    /// if the config isn't tracked or doesn't exist.
+    pub fn get_cached_config(&self, data_id: &str) -> Option<Bytes> {
+        let key = self.config_key(&self.group, data_id);
+        self.cached_content.load().get(&key).cloned()
+    }
+
+    /// Fetches a config as a string, stripping any byte order mark. Fails if the content is not
+    /// valid UTF-8 (after decoding with the client's charset, if any).
+    pub async fn get_config_string(&self, data_id: &str) -> Result<String> {
+        self.fetch_config(&self.group, data_id).await?.to_text()
+    }
+
+    /// Fetches and deserializes a config. Unless `format` is given, it is picked from the
+    /// config type reported by the server.
+    pub async fn get_config_as<T: DeserializeOwned>(
+        &self,
+        data_id: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<T> {
+        self.fetch_config(&self.group, data_id)
+            .await?
+            .deserialize(format)
+    }
+
+    #[cfg(feature = "config")]
+    /// Like [`Self::wait_for_new_config`], deserializing the config as in
+    /// [`Self::get_config_as`].
+    pub async fn wait_for_new_config_as<T: DeserializeOwned>(
+        &self,
+        data_id: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<T> {
+        self.wait_for_changed_config(&self.group, data_id)
+            .await?
+            .deserialize(format)
+    }
+
+    pub async fn load<T: NacosConfig>(&self) -> Result<T> {
+        self.fetch_config(T::GROUP.unwrap_or(&self.group), T::DATA_ID)
+            .await?
+            .deserialize(T::FORMAT)
+    }
+
+    #[cfg(feature = "config")]
+    pub async fn watch<T: NacosConfig>(&self) -> Result<T> {
+        self.wait_for_changed_config(T::GROUP.unwrap_or(&self.group), T::DATA_ID)
+            .await?
+            .deserialize(T::FORMAT)
+    }
+
+    #[cfg(feature = "config")]
+    pub async fn wait_for_new_config(&self, data_id: &str) -> Result<Bytes> {
+        Ok(self
+            .wait_for_changed_config(&self.group, data_id)
+            .await?
+            .content)
+    }
+}
+
+/// Fetches of a config whose content doesn't match its md5 before giving up.
+const MAX_FETCH_ATTEMPTS: usize = 3;
+
+/// Redirects to other servers of the cluster followed by a request, e.g. during maintenance.
+const MAX_REDIRECTS: usize = 2;
+
+/// The request to send to `server` instead, with the same path and query.
+fn redirect(
+    request: reqwest::RequestBuilder,
+    server: &servers::Server,
+) -> Option<reqwest::RequestBuilder> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (client, request) = request.build_split();
+        let mut request = request.ok()?;
+        let mut url: reqwest::Url = server.base_url().parse().ok()?;
+        url.set_path(request.url().path());
+        url.set_query(request.url().query());
+        *request.url_mut() = url;
+        Some(reqwest::RequestBuilder::from_parts(client, request))
+    }
+    // The browser follows redirects itself.
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (request, server);
+        None
+    }
+}
+
+/// Fails on error statuses, keeping the message of the server when it refuses a request.
+async fn check_status(request_id: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status() == reqwest::StatusCode::FORBIDDEN {
+        let body = response.text().await.unwrap_or_default();
+        // Spring answers with a JSON error, the auth filter of older servers with the text.
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|error| Some(error.get("message")?.as_str()?.to_string()))
+            .unwrap_or_else(|| body.trim().to_string());
+        return Err(Error::Forbidden {
+            request_id: request_id.to_string(),
+            message,
+        });
+    }
+    response
+        .error_for_status()
+        .map_err(|source| Error::Request {
+            request_id: request_id.to_string(),
+            source: redact::redact_url(source),
+        })
+}
+
+/// The md5 in the `Content-MD5` header of a response, if any.
+fn content_md5(headers: &reqwest::header::HeaderMap) -> Option<protocol::ContentMd5> {
+    headers
+        .get("Content-MD5")
+        .and_then(|md5| md5.to_str().ok())
+        .and_then(protocol::parse_md5)
+}
+
+#[derive(Default, Clone)]
+struct FetchedConfig {
+    content: Bytes,
+    /// Md5 of the content as served, which the server compares tracked md5s against.
+    md5: protocol::ContentMd5,
+    /// Value of the `Encrypted-Data-Key` response header.
+    encrypted_data_key: Option<String>,
+    /// Value of the `Config-Type` response header.
+    config_type: Option<String>,
+    /// Whether the server couldn't be reached and the content is that of the snapshot.
+    from_snapshot: bool,
+    /// Charset of the `Content-Type` response header.
+    #[cfg(feature = "charset")]
+    declared_encoding: Option<&'static encoding_rs::Encoding>,
+    /// Charset of the client that fetched this config.
+    #[cfg(feature = "charset")]
+    charset: Option<Charset>,
+}
+
+impl FetchedConfig {
+    fn deserialize<T: DeserializeOwned>(&self, format: Option<ConfigFormat>) -> Result<T> {
+        self.format(format)?.deserialize(&self.utf8_content())
+    }
+
+    #[cfg(not(feature = "charset"))]
+    fn utf8_content(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Borrowed(&self.content)
+    }
+
+    fn to_text(&self) -> Result<String> {
+        let content = self.utf8_content();
+        let content = content.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&content);
+        Ok(std::str::from_utf8(content)
+            .map_err(Error::Decode)?
+            .to_string())
+    }
+
+    fn format(&self, format: Option<ConfigFormat>) -> Result<ConfigFormat> {
+        format
+            .or_else(|| {
+                self.config_type
+                    .as_deref()
+                    .and_then(ConfigFormat::from_config_type)
+            })
+            .ok_or_else(|| Error::UnknownConfigType(self.config_type.clone()))
+    }
+}
+
+impl Nacos {
+    fn make_url(&self, path: &str) -> String {
+        self.servers.current().url(path)
+    }
+
+    /// Fetches a config from the server, or else its snapshot. Configs with a validator are
+    /// only snapshotted once it accepts them, see [`Self::fetch_and_track`].
+    async fn fetch_config(&self, group: &str, data_id: &str) -> Result<FetchedConfig> {
+        let (config, served) = self.fetch_unsaved(group, data_id).await?;
+        #[cfg(feature = "config")]
+        let validated =
+            (self.validators.lock().unwrap()).contains_key(&self.config_key(group, data_id));
+        #[cfg(not(feature = "config"))]
+        let validated = false;
+        if let (Some(served), false) = (served, validated) {
+            self.save_snapshot(group, data_id, &served).await;
+        }
+        Ok(config)
+    }
+
+    /// Fetches a config from the server, or else its snapshot, and decrypts and filters it.
+    /// Also returns the config as served by the server, to snapshot.
+    async fn fetch_unsaved(
+        &self,
+        group: &str,
+        data_id: &str,
+    ) -> Result<(FetchedConfig, Option<FetchedConfig>)> {
+        ids::validate_key(group, data_id)?;
+        let span = trace::fetch_span(self, group, data_id);
+        let fetched = async {
+            let (mut config, served) = match self.fetch_from_server(group, data_id).await {
+                Ok(served) => (served.clone(), Some(served)),
+                Err(e) => (self.fetch_snapshot(group, data_id, e).await?, None),
+            };
+            self.decrypt(data_id, &mut config).await?;
+            self.filter_fetched(group, data_id, &mut config).await?;
+            Ok::<_, Error>((config, served))
+        };
+        let (config, served) = trace::timed(span.clone(), fetched).await?;
+        span.record("from_snapshot", config.from_snapshot);
+        Ok((config, served))
+    }
+
+    /// A request for the content of a config. Parameters are percent-encoded by the query
+    /// serializer, tenants being free-form.
+    fn fetch_request(&self, key: &key::ConfigKey) -> reqwest::RequestBuilder {
+        let url = self.make_url(protocol::CONFIGS_PATH);
+        self.client
+            .get(url)
+            .query(&protocol::config_params(key))
+            .query(&self.app_params())
+    }
+
+    /// Fetches the content of a config as served, again if it doesn't match its md5.
+    async fn fetch_from_server(&self, group: &str, data_id: &str) -> Result<FetchedConfig> {
+        let mut attempt = 1;
+        loop {
+            match self.fetch_content(group, data_id).await {
+                Err(Error::Corrupted { .. }) if attempt < MAX_FETCH_ATTEMPTS => {
+                    tracing::warn!(
+                        data_id,
+                        attempt,
+                        "Fetching config again, its md5 didn't match"
+                    );
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fetches the content of a config as served, checking it against the `Content-MD5`
+    /// header to catch responses truncated by proxies.
+    async fn fetch_content(&self, group: &str, data_id: &str) -> Result<FetchedConfig> {
+        let request = self.fetch_request(&self.config_key(group, data_id));
+        let attributes = [("nacos.group", group), ("nacos.data_id", data_id)];
+        let response = self
+            .send("nacos.config.fetch", request, &attributes)
+            .await?;
+        let expected_md5 = content_md5(response.headers());
+        let config_type = response
+            .headers()
+            .get("Config-Type")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        #[cfg(feature = "charset")]
+        let declared_encoding = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(charset::declared_encoding);
+        let encrypted_data_key = response
+            .headers()
+            .get("Encrypted-Data-Key")
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+        let headers = response.headers().clone();
+        let content = self.read_content(group, data_id, response).await?;
+        html::check_not_html(data_id, &headers, &content)?;
+        let md5 = protocol::md5(&content);
+        if expected_md5.is_some_and(|expected| expected != md5) {
+            return Err(Error::Corrupted {
+                data_id: data_id.to_string(),
+            });
         }
+        Ok(FetchedConfig {
+            md5,
+            encrypted_data_key,
+            content,
+            config_type,
+            from_snapshot: false,
+            #[cfg(feature = "charset")]
+            declared_encoding,
+            #[cfg(feature = "charset")]
+            charset: self.charset,
+        })
     }
 
-    pub async fn wait_for_new_config(&self, data_id: &str) -> Result<Bytes, Error> {
-        if !self.current_config.lock().await.contains_key(data_id) {
-            // New config that we never saw. Get it from server.
-            let config = self.get_config(data_id).await?;
-            self.update_md5(data_id, &config).await;
-            Ok(config)
-        } else {
-            loop {
-                let md5 = self
-                    .current_config
-                    .lock()
+    #[cfg(feature = "config")]
+    async fn wait_for_changed_config(&self, group: &str, data_id: &str) -> Result<FetchedConfig> {
+        let key = self.config_key(group, data_id);
+        // A config that we never saw is fetched right away.
+        let tracked = self.current_config.lock().unwrap().is_tracked(&key);
+        loop {
+            let tracked_md5 = self.current_config.lock().unwrap().get(&key);
+            let change = if tracked {
+                Some(self.listen_for_change(&key).await?)
+            } else {
+                None
+            };
+            #[cfg(feature = "metrics")]
+            let notified = std::time::Instant::now();
+            // Every waiter on the change gets the same content, fetched once.
+            let fetched = match change {
+                Some(change) => change
+                    .get_or_try_init(|| self.fetch_and_track(group, data_id))
                     .await
-                    .get(data_id)
-                    .unwrap()
-                    .clone();
-                let mut listening_configs = data_id.to_string();
-                listening_configs.push(2 as char);
-                listening_configs.push_str(&self.group);
-                listening_configs.push(2 as char);
-                listening_configs.push_str(&md5);
-                if let Some(namespace) = &self.namespace {
-                    listening_configs.push(2 as char);
-                    listening_configs.push_str(namespace);
+                    .cloned(),
+                None => self.fetch_and_track(group, data_id).await,
+            };
+            match fetched {
+                // Keep waiting, the previous value stays current.
+                Err(Error::Rejected(_) | Error::TooLarge { .. }) if tracked => continue,
+                // Keep waiting for the config to be created again.
+                Err(e) if tracked && e.is_not_found() => {
+                    self.track_deleted(group, data_id);
+                    continue;
+                }
+                // Notified without a change, e.g. when a long poll resumes after an outage with
+                // an md5 that went stale, or the snapshot of an unreachable server.
+                Ok(config) if tracked && tracked_md5 == Some(Some(config.md5)) => {
+                    if config.from_snapshot {
+                        self.sleep(live::RETRY_DELAY).await;
+                    }
+                    continue;
                 }
-                listening_configs.push(1 as char);
-
-                let url = self.make_url("/nacos/v1/cs/configs/listener");
-                let request = self.client.post(url);
-                let request = request.header("Long-Pulling-Timeout", "30000");
-                let request = request.query(&[("Listening-Configs", &listening_configs)]);
-
-                let response = request.send().await?;
-                let response = response.error_for_status()?;
-                let config = response.bytes().await?;
-                if config.is_empty() {
-                    log::debug!("No new config for {}", data_id);
-                } else {
-                    self.update_md5(data_id, &config).await;
-                    return Ok(config);
+                result => {
+                    #[cfg(feature = "metrics")]
+                    if tracked && result.is_ok() {
+                        meter::record_change(&key, notified.elapsed());
+                    }
+                    return result;
                 }
             }
         }
     }
-}
 
-impl Nacos {
-    fn make_url(&self, path: &str) -> String {
-        format!(
-            "{}://{}{}",
-            if self.use_https { "https" } else { "http" },
-            self.server_addr,
-            path
+    #[cfg(feature = "config")]
+    /// Tracks a config as missing, reporting it as deleted if it existed.
+    fn track_deleted(&self, group: &str, data_id: &str) {
+        let key = self.config_key(group, data_id);
+        let previous = self.current_config.lock().unwrap().track(key, None);
+        // Every waiter on the change gets here, the first one reports it.
+        if previous != Some(None) {
+            tracing::info!(group, data_id, "Config was deleted");
+            self.emit(ConfigEvent::Deleted {
+                group: group.to_string(),
+                data_id: data_id.to_string(),
+            });
+        }
+    }
+
+    #[cfg(feature = "config")]
+    /// Fetches a config, tracks its md5 and validates it, snapshotting it once valid so that
+    /// a rejected value never replaces the last good snapshot.
+    async fn fetch_and_track(&self, group: &str, data_id: &str) -> Result<FetchedConfig> {
+        let key = self.config_key(group, data_id);
+        let (config, served) = match self.fetch_unsaved(group, data_id).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                if e.is_not_found() {
+                    self.cache_content(&key, None);
+                }
+                return Err(e);
+            }
+        };
+        if !config.from_snapshot {
+            self.record_poll(&key);
+        }
+        self.update_md5(key.clone(), config.md5).await;
+        tracing::debug!(
+            group,
+            data_id,
+            content = %redact::Content(&config.content),
+            "Fetched config"
+        );
+        self.validate(&key, &config.content).await?;
+        if let Some(served) = served {
+            self.save_snapshot(group, data_id, &served).await;
+        }
+        if let Some(previous) = self.cached_content.load().get(&key) {
+            let previous = FetchedConfig {
+                content: previous.clone(),
+                ..config.clone()
+            };
+            self.emit_diff(group, data_id, &previous, &config);
+        }
+        self.cache_content(&key, Some(config.content.clone()));
+        Ok(config)
+    }
+
+    #[cfg(feature = "config")]
+    /// Replaces the cached content of a config, copying the map so that reads never wait.
+    fn cache_content(&self, key: &key::ConfigKey, content: Option<Bytes>) {
+        self.cached_content.rcu(|cached| {
+            let mut cached = HashMap::clone(cached);
+            match &content {
+                Some(content) => cached.insert(key.clone(), content.clone()),
+                None => cached.remove(key),
+            };
+            cached
+        });
+    }
+
+    /// Sends a request with the client's access token and signature, if any, and fails on error
+    /// statuses. With the `otel` feature, the request runs in a client span named `operation`
+    /// and carries its trace context.
+    ///
+    /// Redirects to other servers of the cluster are followed, sending the request there with
+    /// its token and signature made again, and other redirects fail.
+    async fn send(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+        attributes: &[(&'static str, &str)],
+    ) -> Result<reqwest::Response> {
+        // The redirected requests keep the id.
+        let request_id = request_id::current();
+        let redirected = self.send_redirected(operation, request, attributes, &request_id);
+        let span = trace::request_span(self, operation, attributes);
+        let response = trace::timed(
+            span.clone(),
+            request_id::with_request_id(request_id.clone(), redirected),
         )
+        .await;
+        let status = match &response {
+            Ok(response) => Some(response.status()),
+            Err(e) => e.status(),
+        };
+        if let Some(status) = status {
+            span.record("status", status.as_u16());
+        }
+        response
     }
 
-    async fn get_config(&self, data_id: &str) -> Result<Bytes, Error> {
-        let url = self.make_url("/nacos/v1/cs/configs");
-        let mut request = self.client.get(url);
-        if let Some(namespace) = &self.namespace {
-            request = request.query(&[("tenant", namespace.as_str())]);
+    async fn send_redirected(
+        &self,
+        operation: &'static str,
+        mut request: reqwest::RequestBuilder,
+        attributes: &[(&'static str, &str)],
+        request_id: &str,
+    ) -> Result<reqwest::Response> {
+        let mut redirects = 0;
+        loop {
+            let unsent = request.try_clone();
+            let response = self.send_signed(operation, request, attributes).await?;
+            let status = response.status();
+            if !status.is_redirection() || status == reqwest::StatusCode::NOT_MODIFIED {
+                return Ok(response);
+            }
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| response.url().join(location).ok());
+            let redirected = match (&location, unsent) {
+                (Some(location), Some(unsent)) if redirects < MAX_REDIRECTS => self
+                    .servers
+                    .redirected(location)
+                    .and_then(|server| redirect(unsent, server)),
+                _ => None,
+            };
+            let Some(redirected) = redirected else {
+                let location = location.map_or_else(String::new, |mut location| {
+                    location.set_query(None);
+                    location.set_fragment(None);
+                    location.to_string()
+                });
+                return Err(Error::Redirected {
+                    request_id: request_id.to_string(),
+                    location,
+                });
+            };
+            tracing::info!(
+                server = %self.servers.current().base_url(),
+                "Following a redirect"
+            );
+            request = redirected;
+            redirects += 1;
         }
-        request = request.query(&[("group", self.group.as_str()), ("dataId", data_id)]);
-        let response = request.send().await?;
-        let response = response.error_for_status()?;
-        response.bytes().await
     }
 
-    async fn update_md5(&self, data_id: &str, config: &Bytes) {
-        let mut hasher = Md5::new();
-        hasher.update(&config);
-        let md5 = hasher.finalize();
-        let md5 = hex::encode(md5);
+    async fn send_signed(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+        attributes: &[(&'static str, &str)],
+    ) -> Result<reqwest::Response> {
+        #[cfg(feature = "access-key")]
+        {
+            let offset = self.clock_offset();
+            let unsigned = request.try_clone();
+            let request = self.sign(request, attributes);
+            let response = self
+                .send_with_token(operation, request, attributes, 0)
+                .await;
+            // A signature with a skewed timestamp is refused, sign it again with the server's.
+            if let (Err(e), Some(unsigned)) = (&response, unsigned) {
+                if e.status() == Some(reqwest::StatusCode::FORBIDDEN)
+                    && self.clock_offset() != offset
+                {
+                    let request = self.sign(unsigned, attributes);
+                    return self
+                        .send_with_token(operation, request, attributes, 1)
+                        .await;
+                }
+            }
+            response
+        }
+        #[cfg(not(feature = "access-key"))]
+        self.send_with_token(operation, request, attributes, 0)
+            .await
+    }
+
+    async fn send_with_token(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+        attributes: &[(&'static str, &str)],
+        retry: u32,
+    ) -> Result<reqwest::Response> {
+        #[cfg(feature = "auth")]
+        {
+            let Some(token) = self.access_token().await? else {
+                return self
+                    .send_unauthenticated(operation, request, attributes, retry)
+                    .await;
+            };
+            let unauthenticated = request.try_clone();
+            let request = request.query(&[("accessToken", &token)]);
+            match self
+                .send_unauthenticated(operation, request, attributes, retry)
+                .await
+            {
+                // The token may have expired early, e.g. after a server restart. Other refusals
+                // are about permissions, which a new token doesn't change.
+                Err(e) if auth::is_refused_token(&e) => {
+                    self.invalidate_token(&token).await;
+                    let Some(request) = unauthenticated else {
+                        return Err(e);
+                    };
+                    let token = self.access_token().await?.unwrap_or_default();
+                    let request = request.query(&[("accessToken", &token)]);
+                    self.send_unauthenticated(operation, request, attributes, retry + 1)
+                        .await
+                }
+                response => response,
+            }
+        }
+        #[cfg(not(feature = "auth"))]
+        self.send_unauthenticated(operation, request, attributes, retry)
+            .await
+    }
+
+    /// Sends a request, the `retry`th retry of an operation.
+    async fn send_unauthenticated(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+        attributes: &[(&'static str, &str)],
+        retry: u32,
+    ) -> Result<reqwest::Response> {
+        let request_id = request_id::current();
+        let request = self.identify(request).header("RequestId", &request_id);
+        let request = deadline::apply(request);
+        let (request, access) = match self.access(request) {
+            Ok(request) => request,
+            Err(source) => return Err(Error::Request { request_id, source }),
+        };
+        // Requests on wasm32 aren't timed, as its std has no clock.
+        #[cfg(not(target_arch = "wasm32"))]
+        let started = std::time::Instant::now();
+        #[cfg(feature = "otel")]
+        let response = otel::send(self, operation, request, attributes, &request_id).await;
+        #[cfg(not(feature = "otel"))]
+        let response = {
+            let _ = attributes;
+            match self.execute(request, &request_id).await {
+                Ok(response) => check_status(&request_id, self.observe(operation, response)).await,
+                Err(e) => Err(e),
+            }
+        };
+        #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+        meter::record_request(operation, started.elapsed(), &response);
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(access) = access {
+            let latency = started.elapsed();
+            access_log::record(access, attributes, &request_id, retry, latency, &response);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = (access, retry);
+        response
+    }
+
+    /// Adds the headers that identify the client, like the Java client's.
+    fn identify(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let version = env!("CARGO_PKG_VERSION");
+        let mut user_agent = format!("{}/{}", env!("CARGO_PKG_NAME"), version);
+        if let Some(app_name) = &self.app_name {
+            user_agent = format!("{} ({})", user_agent, app_name);
+        }
+        let request = request
+            .header(reqwest::header::USER_AGENT, user_agent)
+            .header("Client-Version", format!("Nacos-Rust-Client:v{}", version));
+        match &self.app_name {
+            Some(app_name) => request.header("Client-AppName", app_name),
+            None => request,
+        }
+    }
 
-        self.current_config.lock().await.insert(data_id.into(), md5);
+    /// The `appName` and `tag` parameters of config requests.
+    fn app_params(&self) -> Vec<(&'static str, &str)> {
+        [("appName", &self.app_name), ("tag", &self.tag)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+            .collect()
+    }
+
+    /// Looks at a response before its status is checked.
+    fn observe(&self, operation: &'static str, response: reqwest::Response) -> reqwest::Response {
+        #[cfg(feature = "access-key")]
+        self.observe_server_time(&response);
+        self.run_response_hooks(operation, &response);
+        response
+    }
+
+    #[cfg(feature = "config")]
+    async fn update_md5(&self, key: key::ConfigKey, md5: protocol::ContentMd5) {
+        self.current_config.lock().unwrap().track(key, Some(md5));
+        self.evict_tracked();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{collections::HashMap, str::FromStr};
 
     use super::*;
 
+    #[cfg(feature = "config")]
     async fn test(namespace: Option<String>) {
         let nacos = Nacos::new(
             false,
@@ -128,6 +985,274 @@ mod tests {
         nacos.wait_for_new_config(data_id).await.unwrap();
     }
 
+    #[test]
+    // Fields differ between features.
+    #[allow(clippy::needless_update)]
+    fn format_from_config_type() {
+        let config = FetchedConfig {
+            content: Bytes::from_static(b"port=8080"),
+            config_type: Some("properties".into()),
+            ..Default::default()
+        };
+        let map: HashMap<String, String> = config.deserialize(None).unwrap();
+        assert_eq!(map["port"], "8080");
+        assert!(config.deserialize::<u16>(Some(ConfigFormat::Json)).is_err());
+
+        let config = FetchedConfig {
+            content: Bytes::from_static(b"{}"),
+            config_type: Some("text".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.deserialize::<HashMap<String, String>>(None),
+            Err(Error::UnknownConfigType(Some(_)))
+        ));
+        assert!(config
+            .deserialize::<HashMap<String, String>>(Some(ConfigFormat::Json))
+            .is_ok());
+    }
+
+    #[test]
+    fn text() {
+        let text = |content: &'static [u8]| {
+            FetchedConfig {
+                content: Bytes::from_static(content),
+                ..Default::default()
+            }
+            .to_text()
+        };
+        assert_eq!(text(b"\xEF\xBB\xBFa=1").unwrap(), "a=1");
+        assert_eq!(text("名称".as_bytes()).unwrap(), "名称");
+        assert!(matches!(text(b"\xff"), Err(Error::Decode(_))));
+    }
+
+    #[cfg(feature = "config")]
+    #[tokio::test]
+    async fn validator() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:8848").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let mut events = nacos.subscribe_events();
+        let key = nacos.config_key("DEFAULT_GROUP", "app.json");
+        nacos.set_validator("app.json", |content: &Bytes| {
+            if content.is_empty() {
+                Err("empty config")
+            } else {
+                Ok(())
+            }
+        });
+        nacos
+            .validate(&key, &Bytes::from_static(b"{}"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            nacos.validate(&key, &Bytes::new()).await,
+            Err(Error::Rejected(reason)) if reason == "empty config"
+        ));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            ConfigEvent::Rejected {
+                group: key.group.clone(),
+                data_id: key.data_id.clone(),
+                reason: "empty config".into(),
+            }
+        );
+
+        nacos.remove_validator("app.json");
+        nacos.validate(&key, &Bytes::new()).await.unwrap();
+    }
+
+    #[test]
+    fn identify() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:8848").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let request = nacos
+            .identify(nacos.client.get("http://a/"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.headers()["User-Agent"],
+            concat!("nacos/", env!("CARGO_PKG_VERSION"))
+        );
+        assert!(request.headers().get("Client-AppName").is_none());
+
+        let nacos = nacos.with_app_name("orders");
+        let request = nacos
+            .identify(nacos.client.get("http://a/"))
+            .build()
+            .unwrap();
+        assert!(request.headers()["User-Agent"]
+            .to_str()
+            .unwrap()
+            .ends_with(" (orders)"));
+        assert_eq!(request.headers()["Client-AppName"], "orders");
+        assert_eq!(
+            nacos.with_tag("beta").app_params(),
+            [("appName", "orders"), ("tag", "beta")]
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive() {
+        #[derive(serde::Deserialize, NacosConfig)]
+        #[nacos(data_id = "app.properties", format = "properties")]
+        struct App {}
+
+        #[derive(serde::Deserialize, NacosConfig)]
+        #[nacos(data_id = "db.yaml", group = "DB")]
+        struct Db {}
+
+        assert_eq!(App::DATA_ID, "app.properties");
+        assert_eq!(App::GROUP, None);
+        assert_eq!(App::FORMAT, Some(ConfigFormat::Properties));
+        assert_eq!(Db::GROUP, Some("DB"));
+        assert_eq!(Db::FORMAT, None);
+    }
+
+    #[test]
+    fn encoded_params() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            Some("dev ns#1+é".into()),
+            "DEFAULT_GROUP".into(),
+        )
+        .with_tag("canary&1");
+        let request = nacos
+            .fetch_request(&nacos.config_key("DEFAULT_GROUP", "app.yaml"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().query(),
+            Some("tenant=dev+ns%231%2B%C3%A9&group=DEFAULT_GROUP&dataId=app.yaml&tag=canary%261")
+        );
+    }
+
+    #[test]
+    fn redirected_request() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let request = nacos.fetch_request(&nacos.config_key("DEFAULT_GROUP", "app.yaml"));
+        let server = servers::parse_server_list("https://nacos-2:9848", false).unwrap();
+        let request = redirect(request, &server[0]).unwrap().build().unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://nacos-2:9848/nacos/v1/cs/configs?group=DEFAULT_GROUP&dataId=app.yaml"
+        );
+    }
+
+    /// A server answering every request with `content`, as Nacos serves configs.
+    pub(crate) async fn serve(content: &'static str) -> SocketAddr {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0; 4096];
+                    let _ = stream.read(&mut request).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-MD5: {}\r\nContent-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        protocol::md5_hex(content.as_bytes()),
+                        content.len(),
+                        content
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        addr
+    }
+
+    #[cfg(feature = "config")]
+    #[tokio::test]
+    async fn rejected_snapshot() {
+        let dir = std::env::temp_dir().join(format!("nacos-rejected-{}", std::process::id()));
+        let nacos = Nacos::new(false, serve("bad: 1").await, None, "DEFAULT_GROUP".into())
+            .with_snapshot_dir(&dir);
+        nacos.set_validator("app.yaml", |content: &Bytes| {
+            if content.starts_with(b"bad") {
+                Err("bad")
+            } else {
+                Ok(())
+            }
+        });
+        let good = FetchedConfig {
+            content: Bytes::from_static(b"a: 1"),
+            ..Default::default()
+        };
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &good)
+            .await;
+        let fetched = nacos.fetch_and_track("DEFAULT_GROUP", "app.yaml").await;
+        assert!(matches!(fetched, Err(Error::Rejected(_))));
+        // The rejected value doesn't replace the last good one.
+        nacos.flush_snapshots().await;
+        let snapshot = std::fs::read(dir.join("public/DEFAULT_GROUP/app.yaml")).unwrap();
+        assert_eq!(snapshot, b"a: 1");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn deleted() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let mut events = nacos.subscribe_events();
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        nacos
+            .current_config
+            .lock()
+            .unwrap()
+            .track(key, Some([1; 16]));
+        nacos.track_deleted("DEFAULT_GROUP", "app.yaml");
+        nacos.track_deleted("DEFAULT_GROUP", "app.yaml");
+        assert_eq!(
+            events.try_recv().unwrap(),
+            ConfigEvent::Deleted {
+                group: "DEFAULT_GROUP".into(),
+                data_id: "app.yaml".into()
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn cached_content() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        assert_eq!(nacos.get_cached_config("app.yaml"), None);
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        nacos.cache_content(&key, Some(Bytes::from_static(b"a: 1")));
+        assert_eq!(nacos.clone().get_cached_config("app.yaml").unwrap(), "a: 1");
+        nacos.cache_content(&key, None);
+        assert_eq!(nacos.get_cached_config("app.yaml"), None);
+    }
+
+    #[cfg(feature = "config")]
     #[tokio::test]
     async fn it_works() {
         test(None).await;
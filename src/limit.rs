@@ -0,0 +1,164 @@
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use bytes::BytesMut;
+use reqwest::{header::HeaderMap, Response};
+
+#[cfg(feature = "config")]
+use crate::ConfigEvent;
+use crate::{Error, Nacos, Result};
+
+impl Nacos {
+    /// Rejects configs larger than `bytes` without reading them. Watches keep the previous
+    /// value, as when a validator rejects a config.
+    pub fn with_max_config_size(mut self, bytes: usize) -> Self {
+        self.max_config_size = Some(bytes);
+        self
+    }
+
+    /// Reads the content of a config, up to the size limit.
+    pub(crate) async fn read_content(
+        &self,
+        group: &str,
+        data_id: &str,
+        response: Response,
+    ) -> Result<Bytes> {
+        let Some(limit) = self.max_config_size else {
+            return Ok(response.bytes().await?);
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > limit as u64)
+        {
+            return Err(self
+                .too_large(group, data_id, response.headers(), limit)
+                .await);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut response = response;
+            let mut content = BytesMut::new();
+            while let Some(chunk) = response.chunk().await? {
+                if content.len() + chunk.len() > limit {
+                    let headers = response.headers();
+                    return Err(self.too_large(group, data_id, headers, limit).await);
+                }
+                content.extend_from_slice(&chunk);
+            }
+            Ok(content.freeze())
+        }
+        // Browser responses are read whole.
+        #[cfg(target_arch = "wasm32")]
+        {
+            let headers = response.headers().clone();
+            let content = response.bytes().await?;
+            if content.len() > limit {
+                return Err(self.too_large(group, data_id, &headers, limit).await);
+            }
+            Ok(content)
+        }
+    }
+
+    async fn too_large(
+        &self,
+        group: &str,
+        data_id: &str,
+        headers: &HeaderMap,
+        limit: usize,
+    ) -> Error {
+        // Tracking the md5 of the rejected value makes listeners wait for the next change
+        // rather than fetch it again.
+        #[cfg(feature = "config")]
+        {
+            let key = self.config_key(group, data_id);
+            if let Some(md5) = crate::content_md5(headers) {
+                self.current_config.lock().unwrap().update(&key, md5);
+            }
+            self.emit(ConfigEvent::TooLarge {
+                group: key.group,
+                data_id: key.data_id,
+                limit,
+            });
+        }
+        #[cfg(not(feature = "config"))]
+        let _ = (group, headers);
+        Error::TooLarge {
+            data_id: data_id.to_string(),
+            limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn response(content: &'static str) -> Response {
+        hyper::Response::builder()
+            .header("Content-MD5", crate::protocol::md5_hex(content.as_bytes()))
+            .body(content)
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn max_config_size() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_max_config_size(4);
+        #[cfg(feature = "config")]
+        let mut events = nacos.subscribe_events();
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        #[cfg(feature = "config")]
+        nacos.update_md5(key.clone(), [0; 16]).await;
+
+        let content = nacos
+            .read_content(&key.group, &key.data_id, response("a: 1"))
+            .await;
+        assert_eq!(content.unwrap(), "a: 1");
+        #[cfg(feature = "config")]
+        assert!(events.try_recv().is_err());
+
+        let content = nacos
+            .read_content(&key.group, &key.data_id, response("a: 10"))
+            .await;
+        assert!(matches!(content, Err(Error::TooLarge { limit: 4, .. })));
+        #[cfg(feature = "config")]
+        {
+            assert!(matches!(
+                events.try_recv(),
+                Ok(ConfigEvent::TooLarge { limit: 4, .. })
+            ));
+            assert_eq!(
+                nacos.current_config.lock().unwrap().get(&key),
+                Some(Some(crate::protocol::md5(b"a: 10")))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn not_from_snapshot() {
+        let dir = std::env::temp_dir().join(format!("nacos-too-large-{}", std::process::id()));
+        let server = crate::tests::serve("a: 10").await;
+        let nacos = Nacos::new(false, server, None, "DEFAULT_GROUP".into())
+            .with_max_config_size(4)
+            .with_snapshot_dir(&dir);
+        let snapshot = crate::FetchedConfig {
+            content: bytes::Bytes::from_static(b"a: 1"),
+            ..Default::default()
+        };
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &snapshot)
+            .await;
+        // The oversized value is an error, not an outage.
+        let config = nacos.get_config("app.yaml").await;
+        assert!(matches!(config, Err(Error::TooLarge { limit: 4, .. })));
+        nacos.flush_snapshots().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,507 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use tokio::sync::{watch, Notify, OnceCell};
+
+use crate::{
+    deadline,
+    key::ConfigKey,
+    live::RETRY_DELAY,
+    protocol::{self, ContentMd5},
+    runtime::Instant,
+    trace, Error, FetchedConfig, Nacos, Result,
+};
+
+/// Most configs listened to by one long poll, as in the Java client.
+const SHARD_SIZE: usize = 3000;
+
+/// How long the server is first asked to hold long polls without changes.
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Bounds of the long poll timeout, which adapts to what the network path allows.
+const MIN_LONG_POLL_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_LONG_POLL_TIMEOUT: Duration = Duration::from_secs(60);
+/// Long polls held for their full timeout in a row before the timeout is lengthened.
+const STABLE_POLLS: u32 = 3;
+const LONG_POLL_STEP: Duration = Duration::from_secs(5);
+
+/// A change of a config, whose content the first of its waiters fetches for all of them.
+pub(crate) type Change = Arc<OnceCell<FetchedConfig>>;
+
+/// Configs long polled together, with their tracked md5.
+type Shard = Vec<(ConfigKey, Option<ContentMd5>)>;
+
+/// The configs that a client and its clones wait on, long polled together by one task.
+#[derive(Default)]
+pub(crate) struct Listener {
+    state: Mutex<State>,
+    /// Wakes the task when a config starts being waited on.
+    added: Notify,
+    timeout: PollTimeout,
+}
+
+/// The `Long-Pulling-Timeout` of long polls. It shortens when long polls are cut before it,
+/// e.g. by a proxy closing idle connections, and lengthens while they are held in full.
+struct PollTimeout {
+    millis: AtomicU64,
+    /// Long polls held in full since the timeout last changed.
+    stable: AtomicU32,
+}
+
+impl Default for PollTimeout {
+    fn default() -> Self {
+        Self {
+            millis: AtomicU64::new(LONG_POLL_TIMEOUT.as_millis() as u64),
+            stable: AtomicU32::new(0),
+        }
+    }
+}
+
+impl PollTimeout {
+    fn get(&self) -> Duration {
+        Duration::from_millis(self.millis.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, timeout: Duration) {
+        let timeout = timeout.clamp(MIN_LONG_POLL_TIMEOUT, MAX_LONG_POLL_TIMEOUT);
+        if timeout != self.get() {
+            tracing::debug!(timeout_ms = timeout.as_millis() as u64, "Long polling");
+        }
+        self.millis
+            .store(timeout.as_millis() as u64, Ordering::Relaxed);
+        self.stable.store(0, Ordering::Relaxed);
+    }
+
+    /// Notes a long poll that the server held until `timeout` without changes.
+    fn held(&self, timeout: Duration) {
+        if self.stable.fetch_add(1, Ordering::Relaxed) + 1 >= STABLE_POLLS {
+            self.set(timeout + LONG_POLL_STEP);
+        }
+    }
+
+    /// Notes a long poll that was cut after `held`, short of `timeout`.
+    fn cut(&self, held: Duration, timeout: Duration) {
+        if held < timeout {
+            // Leave a margin before whatever cuts connections.
+            self.set(held * 3 / 4);
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Woken when the config changed, then removed.
+    waiters: HashMap<ConfigKey, watch::Sender<Change>>,
+    /// Configs long polled since they are waited on, which the server may hold polls of.
+    probed: HashSet<ConfigKey>,
+    running: bool,
+}
+
+impl Listener {
+    /// Waits on a config, starting the task if needed.
+    pub(crate) fn subscribe(&self, nacos: &Nacos, key: &ConfigKey) -> watch::Receiver<Change> {
+        let mut state = self.state.lock().unwrap();
+        let receiver = match state.waiters.get(key) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = watch::channel(Change::default());
+                state.waiters.insert(key.clone(), sender);
+                if state.running {
+                    self.added.notify_one();
+                }
+                receiver
+            }
+        };
+        if !state.running {
+            state.running = true;
+            nacos.spawn(run(nacos.clone()));
+        }
+        receiver
+    }
+
+    /// The configs waited on.
+    pub(crate) fn waited(&self) -> HashSet<ConfigKey> {
+        self.state.lock().unwrap().waiters.keys().cloned().collect()
+    }
+
+    /// Every config still waited on with its tracked md5, or `None` to stop the task. Configs
+    /// that nothing waits on anymore stop being tracked.
+    fn watched(&self, nacos: &Nacos) -> Option<Shard> {
+        let mut state = self.state.lock().unwrap();
+        let mut dropped = Vec::new();
+        state.waiters.retain(|key, sender| {
+            let waited = sender.receiver_count() > 0;
+            if !waited {
+                dropped.push(key.clone());
+            }
+            waited
+        });
+        for key in &dropped {
+            state.probed.remove(key);
+        }
+        nacos.forget(&dropped);
+        if state.waiters.is_empty() {
+            state.running = false;
+            return None;
+        }
+        let mut keys: Vec<_> = state.waiters.keys().cloned().collect();
+        keys.sort();
+        Some(with_md5s(nacos, keys))
+    }
+
+    /// The configs of a shard that are still waited on, with their tracked md5.
+    fn refresh(&self, nacos: &Nacos, shard: Shard) -> Shard {
+        let mut state = self.state.lock().unwrap();
+        let mut keys = Vec::with_capacity(shard.len());
+        let mut dropped = Vec::new();
+        for (key, _) in shard {
+            match state.waiters.get(&key).map(watch::Sender::receiver_count) {
+                Some(0) => {
+                    state.waiters.remove(&key);
+                    state.probed.remove(&key);
+                    dropped.push(key);
+                }
+                Some(_) => keys.push(key),
+                // Changed, and tracked until waited on again.
+                None => {}
+            }
+        }
+        nacos.forget(&dropped);
+        with_md5s(nacos, keys)
+    }
+
+    /// Whether a shard has configs never long polled, whose tracked md5 may be stale, e.g.
+    /// restored from snapshots after a restart.
+    fn has_new(&self, shard: &[(ConfigKey, Option<ContentMd5>)]) -> bool {
+        let state = self.state.lock().unwrap();
+        shard.iter().any(|(key, _)| !state.probed.contains(key))
+    }
+
+    fn probed(&self, shard: &[(ConfigKey, Option<ContentMd5>)]) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .probed
+            .extend(shard.iter().map(|(key, _)| key.clone()));
+    }
+
+    /// Wakes the waiters of configs that changed.
+    fn notify(&self, changed: &[ConfigKey]) {
+        let mut state = self.state.lock().unwrap();
+        for key in changed {
+            if let Some(sender) = state.waiters.remove(key) {
+                sender.send_replace(Change::default());
+            }
+        }
+    }
+}
+
+fn with_md5s(nacos: &Nacos, keys: Vec<ConfigKey>) -> Shard {
+    nacos.current_config.lock().unwrap().with_md5s(keys)
+}
+
+impl Nacos {
+    /// Waits until the server reports that the config differs from the tracked md5. Configs
+    /// are long polled together, the waits of all clients sharing the requests, and so is the
+    /// content of each change.
+    pub(crate) async fn listen_for_change(&self, key: &ConfigKey) -> Result<Change> {
+        let mut changed = self.listener.subscribe(self, key);
+        // Fails only if the listener gave up on the key, which a fetch then sorts out.
+        let _ = deadline::wait(self, changed.changed()).await?;
+        let change = changed.borrow().clone();
+        Ok(change)
+    }
+
+    /// Long polls a shard of configs, returning those that changed. Without `hang_up`, the
+    /// server answers right away even if none did.
+    async fn poll(
+        &self,
+        shard: &[(ConfigKey, Option<ContentMd5>)],
+        timeout: Duration,
+        hang_up: bool,
+    ) -> Result<Vec<ConfigKey>> {
+        let request = self.poll_request(shard, timeout, hang_up);
+        let response = self.send("nacos.config.listen", request, &[]).await?;
+        // The server answers with the keys that changed, not their content.
+        Ok(protocol::changed_keys(&response.text().await?))
+    }
+
+    /// Sends `Listening-Configs` in a form body like the Java client, as gateways may truncate
+    /// the query string of thousands of configs.
+    fn poll_request(
+        &self,
+        shard: &[(ConfigKey, Option<ContentMd5>)],
+        timeout: Duration,
+        hang_up: bool,
+    ) -> reqwest::RequestBuilder {
+        let listening_configs = protocol::listening_configs_of(shard);
+        let url = self.make_url(protocol::LISTENER_PATH);
+        let request = self
+            .client
+            .post(url)
+            .header("Long-Pulling-Timeout", timeout.as_millis().to_string());
+        let request = if hang_up {
+            request
+        } else {
+            request.header("Long-Pulling-Timeout-No-Hangup", "true")
+        };
+        let request = match self.timeout {
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(client_timeout) => request.timeout(timeout + client_timeout),
+            _ => request,
+        };
+        request.form(&[("Listening-Configs", &listening_configs)])
+    }
+}
+
+/// Long polls the waited on configs until there are none left.
+async fn run(nacos: Nacos) {
+    let listener = nacos.listener.clone();
+    while let Some(keys) = listener.watched(&nacos) {
+        let mut polls = FuturesUnordered::new();
+        for shard in keys.chunks(SHARD_SIZE) {
+            polls.push(poll(nacos.clone(), shard.to_vec()));
+        }
+        loop {
+            tokio::select! {
+                // Dropping the polls cancels them, to poll again with the new config.
+                _ = listener.added.notified() => break,
+                polled = polls.next() => match polled {
+                    Some((shard, changed)) => {
+                        listener.notify(&changed);
+                        let shard = listener.refresh(&nacos, shard);
+                        if !shard.is_empty() {
+                            polls.push(poll(nacos.clone(), shard));
+                        }
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+}
+
+/// Long polls a shard once, the polls of a round running together in the listener's task.
+async fn poll(nacos: Nacos, shard: Shard) -> (Shard, Vec<ConfigKey>) {
+    let timeout = nacos.listener.timeout.get();
+    // Like the Java client, new configs are first checked without waiting, so that a stale
+    // md5 is found out right away.
+    let hang_up = !nacos.listener.has_new(&shard);
+    let started = Instant::now();
+    let span = trace::listen_span(&nacos, shard.len(), hang_up);
+    let result = trace::timed(span.clone(), nacos.poll(&shard, timeout, hang_up)).await;
+    let held = started.elapsed();
+    if let Ok(changed) = &result {
+        span.record("changed", changed.len());
+    }
+    match result {
+        Ok(mut changed) => {
+            nacos.listener.probed(&shard);
+            // The server answers a little before the timeout.
+            if hang_up && changed.is_empty() && held >= timeout * 9 / 10 {
+                nacos.listener.timeout.held(timeout);
+            }
+            for (key, _) in &shard {
+                nacos.record_poll(key);
+            }
+            if changed.is_empty() {
+                tracing::debug!(configs = shard.len(), "No new config among those listened");
+            }
+            // The server answers again, fetch what was served from snapshots even if it
+            // didn't change.
+            let served = nacos.snapshots_served.lock().unwrap().clone();
+            changed.extend(
+                shard
+                    .iter()
+                    .map(|(key, _)| key)
+                    .filter(|key| served.contains(*key) && !changed.contains(*key))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+            (shard, changed)
+        }
+        Err(e) => {
+            if is_cut(&e) {
+                nacos.listener.timeout.cut(held, timeout);
+            }
+            tracing::warn!(error = %e, "Failed to listen for config changes");
+            nacos.sleep(RETRY_DELAY).await;
+            (shard, Vec::new())
+        }
+    }
+}
+
+/// Whether a long poll failed after reaching the server, as when a proxy drops it.
+fn is_cut(error: &Error) -> bool {
+    let (Error::Http(source) | Error::Request { source, .. }) = error else {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Error::Transport { source, .. } = error {
+            return !source.is_connect();
+        }
+        return false;
+    };
+    match source.status() {
+        Some(status) => matches!(
+            status,
+            reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::GATEWAY_TIMEOUT
+        ),
+        #[cfg(not(target_arch = "wasm32"))]
+        None => !source.is_connect() && (source.is_body() || source.is_request()),
+        // Failed fetches on wasm32 don't tell failed connections apart.
+        #[cfg(target_arch = "wasm32")]
+        None => source.is_body(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn shared_waits() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        nacos.update_md5(key.clone(), [1; 16]).await;
+        let listener = &nacos.listener;
+        let mut first = listener.subscribe(&nacos, &key);
+        let mut second = nacos.clone().listener.subscribe(&nacos, &key);
+        assert_eq!(
+            listener.watched(&nacos).unwrap(),
+            [(key.clone(), Some([1; 16]))]
+        );
+
+        listener.notify(std::slice::from_ref(&key));
+        first.changed().await.unwrap();
+        second.changed().await.unwrap();
+        let content = Bytes::from_static(b"port=8080");
+        let fetched = first
+            .borrow()
+            .get_or_init(|| async {
+                FetchedConfig {
+                    content: content.clone(),
+                    ..Default::default()
+                }
+            })
+            .await
+            .content
+            .clone();
+        // The second waiter gets the same bytes without fetching.
+        let shared = second.borrow().get().unwrap().content.clone();
+        assert_eq!(shared.as_ptr(), fetched.as_ptr());
+        // Waiting again needs a new subscription, with the md5 of the new value.
+        assert!(listener.refresh(&nacos, vec![(key, None)]).is_empty());
+    }
+
+    #[test]
+    fn poll_request() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            Some("dev ns".into()),
+            "DEFAULT_GROUP".into(),
+        );
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        let request = nacos
+            .poll_request(&[(key.clone(), None)], LONG_POLL_TIMEOUT, true)
+            .build()
+            .unwrap();
+        assert_eq!(request.url().query(), None);
+        assert_eq!(request.headers()["Long-Pulling-Timeout"], "30000");
+        assert!(request
+            .headers()
+            .get("Long-Pulling-Timeout-No-Hangup")
+            .is_none());
+        assert_eq!(
+            request.headers()[reqwest::header::CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(
+            request.body().unwrap().as_bytes().unwrap(),
+            b"Listening-Configs=app.yaml%02DEFAULT_GROUP%02%02dev+ns%01"
+        );
+        let request = nacos
+            .poll_request(&[(key, None)], LONG_POLL_TIMEOUT, false)
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["Long-Pulling-Timeout-No-Hangup"], "true");
+    }
+
+    #[tokio::test]
+    async fn first_poll() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let key = nacos.config_key("DEFAULT_GROUP", "app.yaml");
+        let waiter = nacos.listener.subscribe(&nacos, &key);
+        let shard = nacos.listener.watched(&nacos).unwrap();
+        assert!(nacos.listener.has_new(&shard));
+        nacos.listener.probed(&shard);
+        assert!(!nacos.listener.has_new(&shard));
+
+        // Configs waited on again after nothing did are new again.
+        drop(waiter);
+        assert!(nacos.listener.watched(&nacos).is_none());
+        let _waiter = nacos.listener.subscribe(&nacos, &key);
+        assert!(nacos.listener.has_new(&shard));
+    }
+
+    #[tokio::test]
+    async fn across_groups() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let app = ConfigKey::new("APP", "app.yaml");
+        let db = ConfigKey::new("DB", "app.yaml").with_tenant("dev");
+        let _waiters = [
+            nacos.listener.subscribe(&nacos, &app),
+            nacos.listener.subscribe(&nacos, &db),
+        ];
+        let shard = nacos.listener.watched(&nacos).unwrap();
+        let request = nacos
+            .poll_request(&shard, LONG_POLL_TIMEOUT, true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.body().unwrap().as_bytes().unwrap(),
+            b"Listening-Configs=app.yaml%02APP%02%01app.yaml%02DB%02%02dev%01"
+        );
+    }
+
+    #[test]
+    fn poll_timeout() {
+        let timeout = PollTimeout::default();
+        timeout.cut(Duration::from_secs(20), timeout.get());
+        assert_eq!(timeout.get(), Duration::from_secs(15));
+        timeout.cut(Duration::from_secs(2), timeout.get());
+        assert_eq!(timeout.get(), MIN_LONG_POLL_TIMEOUT);
+
+        for _ in 0..STABLE_POLLS {
+            timeout.held(timeout.get());
+        }
+        assert_eq!(timeout.get(), MIN_LONG_POLL_TIMEOUT + LONG_POLL_STEP);
+        for _ in 0..100 {
+            timeout.held(timeout.get());
+        }
+        assert_eq!(timeout.get(), MAX_LONG_POLL_TIMEOUT);
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(feature = "config")]
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "config")]
+use arc_swap::ArcSwap;
+#[cfg(any(feature = "config", feature = "naming"))]
+use futures_util::future::AbortHandle;
+#[cfg(feature = "config")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "config")]
+use tokio::sync::watch;
+
+#[cfg(feature = "config")]
+use crate::{ConfigFormat, Nacos, Result};
+
+/// Delay before listening again after a failed listen or fetch.
+pub(crate) const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[cfg(feature = "config")]
+/// A typed config kept up to date by a background task, see [`Nacos::watch_live`].
+///
+/// The task stops when the last clone of the handle is dropped.
+pub struct ConfigHandle<T> {
+    live: Arc<LiveConfig<T>>,
+    updates: watch::Receiver<()>,
+    _tasks: Arc<AbortOnDrop>,
+}
+
+#[cfg(feature = "config")]
+impl<T> ConfigHandle<T> {
+    pub(crate) fn new(live: Arc<LiveConfig<T>>, tasks: Vec<AbortHandle>) -> Self {
+        Self {
+            updates: live.updates.subscribe(),
+            live,
+            _tasks: Arc::new(AbortOnDrop(tasks)),
+        }
+    }
+
+    /// Returns the latest config that was successfully parsed.
+    pub fn load(&self) -> Arc<T> {
+        self.live.value.load_full()
+    }
+
+    /// Waits for an update that this handle did not wait for yet. Updates that happened while
+    /// not waiting are reported right away.
+    pub async fn changed(&mut self) {
+        // The sender lives as long as `self.live`.
+        let _ = self.updates.changed().await;
+    }
+}
+
+#[cfg(feature = "config")]
+impl<T> Clone for ConfigHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            live: self.live.clone(),
+            updates: self.updates.clone(),
+            _tasks: self._tasks.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+/// The value behind a [`ConfigHandle`], updated by its tasks.
+pub(crate) struct LiveConfig<T> {
+    value: ArcSwap<T>,
+    updates: watch::Sender<()>,
+}
+
+#[cfg(feature = "config")]
+impl<T> LiveConfig<T> {
+    pub(crate) fn new(value: T) -> Arc<Self> {
+        Arc::new(Self {
+            value: ArcSwap::from_pointee(value),
+            updates: watch::channel(()).0,
+        })
+    }
+
+    pub(crate) fn store(&self, value: T) {
+        self.value.store(Arc::new(value));
+        self.updates.send_replace(());
+    }
+}
+
+#[cfg(any(feature = "config", feature = "naming"))]
+/// Stops the tasks of a handle when dropped.
+pub(crate) struct AbortOnDrop(pub(crate) Vec<AbortHandle>);
+
+#[cfg(any(feature = "config", feature = "naming"))]
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl Nacos {
+    /// Fetches a config and keeps it up to date in the background. Updates that fail to parse
+    /// are logged and skipped, leaving the previous value in place.
+    pub async fn watch_live<T>(
+        &self,
+        data_id: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<ConfigHandle<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let group = self.group.clone();
+        let initial = self.fetch_and_track(&group, data_id).await?;
+        let config = LiveConfig::new(initial.deserialize(format)?);
+
+        let nacos = self.clone();
+        let data_id = data_id.to_string();
+        let updated = config.clone();
+        let task = self.spawn(async move {
+            loop {
+                match nacos.wait_for_changed_config(&group, &data_id).await {
+                    Ok(fetched) => match fetched.deserialize(format) {
+                        Ok(value) => updated.store(value),
+                        Err(e) => tracing::warn!(data_id, error = %e, "Ignoring invalid config"),
+                    },
+                    Err(e) => {
+                        tracing::warn!(data_id, error = %e, "Failed to watch config");
+                        nacos.sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+        });
+        Ok(ConfigHandle::new(config, vec![task]))
+    }
+}
@@ -0,0 +1,177 @@
+use std::str::FromStr;
+
+use log::LevelFilter;
+use serde_json::Value;
+
+use crate::{live::AbortOnDrop, Nacos, Result};
+
+/// Log levels of a Spring-style logging config: `logging.level.root` for the default level
+/// and `logging.level.<target>` for targets. Nested target keys are joined with `::`, so
+/// `logging.level.my_app.db` in properties is the `my_app::db` target.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogLevels {
+    pub root: Option<LevelFilter>,
+    pub targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogLevels {
+    pub fn from_tree(tree: &Value) -> Self {
+        let mut levels = Self::default();
+        if let Some(level) = tree.pointer("/logging/level") {
+            levels.collect(level, None);
+        }
+        levels
+    }
+
+    fn collect(&mut self, node: &Value, target: Option<&str>) {
+        match node {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let target = match target {
+                        Some(target) => format!("{target}::{key}"),
+                        None => key.clone(),
+                    };
+                    self.collect(value, Some(&target));
+                }
+            }
+            Value::String(level) => match (target, parse_level(level)) {
+                (Some("root") | None, Some(level)) => self.root = Some(level),
+                (Some(target), Some(level)) => self.targets.push((target.to_string(), level)),
+                (_, None) => tracing::warn!(level, "Ignoring invalid log level"),
+            },
+            _ => {}
+        }
+    }
+
+    /// Filter directives like `info,my_app::db=debug`, as understood by `env_logger` and
+    /// `tracing_subscriber::EnvFilter`.
+    pub fn directives(&self) -> String {
+        let root = self.root.map(|level| level.as_str().to_ascii_lowercase());
+        let targets = self
+            .targets
+            .iter()
+            .map(|(target, level)| format!("{target}={}", level.as_str().to_ascii_lowercase()));
+        root.into_iter()
+            .chain(targets)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The most verbose level of all.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .chain(self.root)
+            .max()
+            .unwrap_or(LevelFilter::Info)
+    }
+
+    /// Sets [`log::set_max_level`]. Per-target levels are up to the logger implementation.
+    pub fn apply_to_log(&self) {
+        log::set_max_level(self.max_level());
+    }
+
+    /// Builds an `EnvFilter`, for a `tracing_subscriber::reload::Handle`.
+    #[cfg(feature = "tracing-subscriber")]
+    pub fn env_filter(&self) -> Result<tracing_subscriber::EnvFilter, String> {
+        tracing_subscriber::EnvFilter::try_new(self.directives()).map_err(|e| e.to_string())
+    }
+}
+
+fn parse_level(level: &str) -> Option<LevelFilter> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        // Spring's most severe level.
+        "fatal" => Some(LevelFilter::Error),
+        level => LevelFilter::from_str(level).ok(),
+    }
+}
+
+/// Returned by [`Nacos::watch_log_levels`], stops watching when dropped.
+pub struct LogLevelWatcher {
+    _task: AbortOnDrop,
+}
+
+impl Nacos {
+    /// Watches a logging config and calls `apply` with its levels initially and on every
+    /// change, e.g. with [`LogLevels::apply_to_log`] or [`reload_env_filter`].
+    pub async fn watch_log_levels<F>(&self, data_id: &str, mut apply: F) -> Result<LogLevelWatcher>
+    where
+        F: FnMut(&LogLevels) + Send + 'static,
+    {
+        let mut handle = self.watch_layered_live::<Value>(&[data_id], None).await?;
+        let task = self.spawn(async move {
+            let mut current = None;
+            loop {
+                let levels = LogLevels::from_tree(&handle.load());
+                if current.as_ref() != Some(&levels) {
+                    tracing::info!(levels = ?levels.directives(), "Applying log levels");
+                    apply(&levels);
+                    current = Some(levels);
+                }
+                handle.changed().await;
+            }
+        });
+        Ok(LogLevelWatcher {
+            _task: AbortOnDrop(vec![task]),
+        })
+    }
+}
+
+/// Applies log levels to the `EnvFilter` of a `tracing_subscriber` reload layer, for
+/// [`Nacos::watch_log_levels`].
+#[cfg(feature = "tracing-subscriber")]
+pub fn reload_env_filter<S: 'static>(
+    handle: tracing_subscriber::reload::Handle<tracing_subscriber::EnvFilter, S>,
+) -> impl FnMut(&LogLevels) + Send + 'static {
+    move |levels| match levels.env_filter() {
+        Ok(filter) => {
+            if let Err(e) = handle.reload(filter) {
+                tracing::warn!(error = %e, "Failed to reload log filter");
+            }
+        }
+        Err(e) => tracing::warn!(levels = ?levels.directives(), error = %e, "Invalid log levels"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn levels() {
+        let tree = json!({"logging": {"level": {
+            "root": "WARN",
+            "my_app": {"db": "debug"},
+            "hyper::client": "fatal",
+            "bad": "loud",
+        }}});
+        let levels = LogLevels::from_tree(&tree);
+        assert_eq!(levels.root, Some(LevelFilter::Warn));
+        assert_eq!(
+            levels.targets,
+            [
+                ("hyper::client".to_string(), LevelFilter::Error),
+                ("my_app::db".to_string(), LevelFilter::Debug),
+            ]
+        );
+        assert_eq!(
+            levels.directives(),
+            "warn,hyper::client=error,my_app::db=debug"
+        );
+        assert_eq!(levels.max_level(), LevelFilter::Debug);
+        assert_eq!(LogLevels::from_tree(&json!({})), LogLevels::default());
+    }
+
+    #[cfg(feature = "tracing-subscriber")]
+    #[test]
+    fn env_filter() {
+        let levels = LogLevels {
+            root: Some(LevelFilter::Info),
+            targets: vec![("nacos".into(), LevelFilter::Trace)],
+        };
+        assert_eq!(levels.env_filter().unwrap().to_string(), "nacos=trace,info");
+    }
+}
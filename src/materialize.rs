@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    export::write_atomically,
+    live::{AbortOnDrop, RETRY_DELAY},
+    Nacos, Result,
+};
+
+/// Returned by [`Nacos::materialize`], stops updating the file when dropped.
+pub struct Materialized {
+    path: PathBuf,
+    _task: AbortOnDrop,
+}
+
+impl Materialized {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Nacos {
+    /// Writes a config to `path` and rewrites it whenever the config changes, e.g. to
+    /// distribute nginx snippets or TLS material. Files are replaced atomically, keeping their
+    /// permissions. Fails if the config can't be fetched or written the first time.
+    pub async fn materialize(
+        &self,
+        data_id: &str,
+        path: impl Into<PathBuf>,
+    ) -> Result<Materialized> {
+        self.materialize_with_hook(data_id, path, |_| {}).await
+    }
+
+    /// Like [`Self::materialize`], calling `on_write` after every write, the first one
+    /// included, e.g. to have nginx reload with `kill -HUP`:
+    ///
+    /// ```no_run
+    /// # async fn run(nacos: nacos::Nacos, pid: u32) -> nacos::Result<()> {
+    /// let _file = nacos
+    ///     .materialize_with_hook("nginx.conf", "/etc/nginx/conf.d/app.conf", move |_| {
+    ///         let _ = std::process::Command::new("kill")
+    ///             .args(["-HUP", &pid.to_string()])
+    ///             .status();
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn materialize_with_hook(
+        &self,
+        data_id: &str,
+        path: impl Into<PathBuf>,
+        mut on_write: impl FnMut(&Path) + Send + 'static,
+    ) -> Result<Materialized> {
+        let path = path.into();
+        let group = self.group.clone();
+        let initial = self.fetch_and_track(&group, data_id).await?;
+        write_atomically(&path, &initial.content).await?;
+        on_write(&path);
+
+        let nacos = self.clone();
+        let data_id = data_id.to_string();
+        let target = path.clone();
+        let task = self.spawn(async move {
+            loop {
+                let content = match nacos.wait_for_changed_config(&group, &data_id).await {
+                    Ok(config) => config.content,
+                    Err(e) => {
+                        tracing::warn!(data_id, error = %e, "Failed to watch config");
+                        nacos.sleep(RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                while let Err(e) = write_atomically(&target, &content).await {
+                    tracing::warn!(path = %target.display(), error = %e, "Failed to write config");
+                    nacos.sleep(RETRY_DELAY).await;
+                }
+                on_write(&target);
+            }
+        });
+        Ok(Materialized {
+            path,
+            _task: AbortOnDrop(vec![task]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable() {
+        let path = std::env::temp_dir().join(format!("nacos-materialize-{}", std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let written = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let hooked = written.clone();
+        let result = nacos
+            .materialize_with_hook("nginx.conf", &path, move |_| {
+                hooked.store(true, std::sync::atomic::Ordering::Relaxed)
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(!written.load(std::sync::atomic::Ordering::Relaxed));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use metrics::{counter, histogram};
+use reqwest::Response;
+
+#[cfg(feature = "config")]
+use crate::key::ConfigKey;
+use crate::{Error, Result};
+
+/// Requests to the server by `operation` and `outcome` (`ok` or `error`).
+const REQUESTS: &str = "nacos_requests_total";
+/// Duration of requests by `operation`, including long polls of `nacos.config.listen`.
+const REQUEST_DURATION: &str = "nacos_request_duration_seconds";
+/// Failed requests by `operation` and `error`: `status_<code>`, `connect`, `timeout`,
+/// `decode` or `other`.
+const REQUEST_ERRORS: &str = "nacos_request_errors_total";
+#[cfg(feature = "config")]
+/// Changes reported by the server by `group` and `data_id`.
+const CONFIG_CHANGES: &str = "nacos_config_changes_total";
+#[cfg(feature = "config")]
+/// Time from the server reporting a change to the new value being fetched.
+const LISTENER_LAG: &str = "nacos_listener_lag_seconds";
+#[cfg(feature = "config")]
+/// Values rejected by validators by `group` and `data_id`.
+const CONFIG_REJECTIONS: &str = "nacos_config_rejections_total";
+
+pub(crate) fn record_request(
+    operation: &'static str,
+    duration: Duration,
+    result: &Result<Response>,
+) {
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    counter!(REQUESTS, "operation" => operation, "outcome" => outcome).increment(1);
+    histogram!(REQUEST_DURATION, "operation" => operation).record(duration.as_secs_f64());
+    if let Err(e) = result {
+        counter!(REQUEST_ERRORS, "operation" => operation, "error" => error_kind(e)).increment(1);
+    }
+}
+
+fn error_kind(e: &Error) -> String {
+    if let Some(status) = e.status() {
+        return format!("status_{}", status.as_u16());
+    }
+    if let Error::Transport { source, .. } = e {
+        return match (source.is_timeout(), source.is_connect()) {
+            (true, _) => "timeout",
+            (_, true) => "connect",
+            _ => "other",
+        }
+        .into();
+    }
+    let Error::Request { source: e, .. } = e else {
+        return "other".into();
+    };
+    if e.is_timeout() {
+        "timeout".into()
+    } else if e.is_connect() {
+        "connect".into()
+    } else if e.is_decode() {
+        "decode".into()
+    } else {
+        "other".into()
+    }
+}
+
+#[cfg(feature = "config")]
+pub(crate) fn record_change(key: &ConfigKey, lag: Duration) {
+    let ConfigKey { group, data_id, .. } = key.clone();
+    counter!(CONFIG_CHANGES, "group" => group, "data_id" => data_id).increment(1);
+    histogram!(LISTENER_LAG).record(lag.as_secs_f64());
+}
+
+#[cfg(feature = "config")]
+pub(crate) fn record_rejection(key: &ConfigKey) {
+    let ConfigKey { group, data_id, .. } = key.clone();
+    counter!(CONFIG_REJECTIONS, "group" => group, "data_id" => data_id).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, SharedString, Unit};
+
+    use super::*;
+
+    /// Counts increments by metric name and labels, and records which histograms were used.
+    #[derive(Default)]
+    struct TestRecorder {
+        counters: Mutex<HashMap<String, Arc<AtomicU64>>>,
+        histograms: Mutex<Vec<String>>,
+    }
+
+    impl TestRecorder {
+        fn counter(&self, name: &str) -> u64 {
+            let counters = self.counters.lock().unwrap();
+            counters
+                .get(name)
+                .map_or(0, |value| value.load(Ordering::Relaxed))
+        }
+    }
+
+    fn name(key: &Key) -> String {
+        let labels: Vec<_> = key
+            .labels()
+            .map(|label| format!("{}={}", label.key(), label.value()))
+            .collect();
+        format!("{}{{{}}}", key.name(), labels.join(","))
+    }
+
+    impl metrics::Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let mut counters = self.counters.lock().unwrap();
+            Counter::from_arc(counters.entry(name(key)).or_default().clone())
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            self.histograms.lock().unwrap().push(name(key));
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn requests() {
+        let recorder = TestRecorder::default();
+        let response = |status| {
+            let response = hyper::Response::builder().status(status).body("").unwrap();
+            Response::from(response)
+                .error_for_status()
+                .map_err(|source| Error::Request {
+                    request_id: "1".into(),
+                    source,
+                })
+        };
+        metrics::with_local_recorder(&recorder, || {
+            record_request("nacos.config.fetch", Duration::ZERO, &response(200));
+            record_request("nacos.config.fetch", Duration::ZERO, &response(503));
+            record_request("nacos.config.fetch", Duration::ZERO, &response(503));
+        });
+        let ok = "nacos_requests_total{operation=nacos.config.fetch,outcome=ok}";
+        assert_eq!(recorder.counter(ok), 1);
+        let error = "nacos_requests_total{operation=nacos.config.fetch,outcome=error}";
+        assert_eq!(recorder.counter(error), 2);
+        let status = "nacos_request_errors_total{operation=nacos.config.fetch,error=status_503}";
+        assert_eq!(recorder.counter(status), 2);
+        assert_eq!(
+            recorder.histograms.lock().unwrap()[0],
+            "nacos_request_duration_seconds{operation=nacos.config.fetch}"
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn changes() {
+        let recorder = TestRecorder::default();
+        let key = ConfigKey::new("DEFAULT_GROUP", "app.yaml");
+        metrics::with_local_recorder(&recorder, || {
+            record_change(&key, Duration::from_millis(20));
+            record_rejection(&key);
+        });
+        let labels = "{group=DEFAULT_GROUP,data_id=app.yaml}";
+        assert_eq!(
+            recorder.counter(&format!("nacos_config_changes_total{}", labels)),
+            1
+        );
+        assert_eq!(
+            recorder.counter(&format!("nacos_config_rejections_total{}", labels)),
+            1
+        );
+        assert_eq!(
+            *recorder.histograms.lock().unwrap(),
+            ["nacos_listener_lag_seconds{}"]
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::ops::Deref;
+#[cfg(feature = "admin")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "admin")]
+use serde::Deserialize;
+
+use crate::Nacos;
+#[cfg(feature = "admin")]
+use crate::{publish::expect_true, Result};
+
+/// A namespace, as listed by [`Nacos::list_namespaces`].
+#[cfg(feature = "admin")]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceInfo {
+    /// The id used as `tenant` by the config APIs, empty for the public namespace.
+    #[serde(rename = "namespace")]
+    pub id: String,
+    #[serde(rename = "namespaceShowName")]
+    pub name: String,
+    #[serde(rename = "namespaceDesc", default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub quota: u32,
+    #[serde(default)]
+    pub config_count: u32,
+    /// 0 for the public namespace, 2 for custom ones.
+    #[serde(rename = "type", default)]
+    pub kind: u32,
+}
+
+/// Id of the public namespace on recent servers, the empty id on older ones.
+const PUBLIC_NAMESPACE: &str = "public";
+
+/// Whether a namespace id names the public namespace, which is then left out of requests so
+/// that `None`, `""` and `"public"` behave the same with every server version.
+pub(crate) fn is_public(namespace: &str) -> bool {
+    namespace.is_empty() || namespace == PUBLIC_NAMESPACE
+}
+
+/// The namespace of a client, `None` for the public one.
+pub(crate) fn normalize(namespace: Option<String>) -> Option<String> {
+    namespace.filter(|namespace| !is_public(namespace))
+}
+
+/// How long [`Nacos::list_namespaces`] serves namespaces from its cache.
+#[cfg(feature = "admin")]
+const NAMESPACE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Namespaces and when they were listed.
+#[cfg(feature = "admin")]
+pub(crate) type NamespaceCache = Option<(Instant, Vec<NamespaceInfo>)>;
+
+/// A client scoped to a namespace, see [`Nacos::namespace`]. Derefs to [`Nacos`], so every
+/// call through it is about configs of the namespace.
+#[derive(Clone)]
+pub struct NamespaceHandle(Nacos);
+
+impl NamespaceHandle {
+    /// The id of the namespace, empty for the public one.
+    pub fn id(&self) -> &str {
+        self.0.namespace.as_deref().unwrap_or_default()
+    }
+
+    pub fn into_inner(self) -> Nacos {
+        self.0
+    }
+}
+
+impl Deref for NamespaceHandle {
+    type Target = Nacos;
+
+    fn deref(&self) -> &Nacos {
+        &self.0
+    }
+}
+
+#[cfg(feature = "admin")]
+#[derive(Deserialize)]
+struct NamespaceList {
+    data: Vec<NamespaceInfo>,
+}
+
+impl Nacos {
+    /// A handle on the namespace with id `id`, the public one if empty or `public`, for
+    /// managing configs across namespaces with one client. Handles share the connection pool,
+    /// the access token and the tracked configs of the client.
+    pub fn namespace(&self, id: impl Into<String>) -> NamespaceHandle {
+        NamespaceHandle(self.in_namespace(id))
+    }
+}
+
+#[cfg(feature = "admin")]
+impl Nacos {
+    /// Lists namespaces, from a cache shared by clones for up to a minute. Changes made
+    /// through this client clear the cache.
+    pub async fn list_namespaces(&self) -> Result<Vec<NamespaceInfo>> {
+        let cached = self.namespaces.lock().unwrap().clone();
+        match cached {
+            Some((fetched, namespaces)) if fetched.elapsed() < NAMESPACE_CACHE_TTL => {
+                Ok(namespaces)
+            }
+            _ => self.refresh_namespaces().await,
+        }
+    }
+
+    /// Lists namespaces from the server and caches them.
+    pub async fn refresh_namespaces(&self) -> Result<Vec<NamespaceInfo>> {
+        let url = self.make_url("/nacos/v1/console/namespaces");
+        let request = self.client.get(url);
+        let response = self.send("nacos.namespace.list", request, &[]).await?;
+        let namespaces = response.json::<NamespaceList>().await?.data;
+        *self.namespaces.lock().unwrap() = Some((Instant::now(), namespaces.clone()));
+        Ok(namespaces)
+    }
+
+    /// Finds a namespace by its display name, which is easier to configure than its id.
+    /// Refreshes the cache once if the name is not in it.
+    pub async fn find_namespace_by_name(&self, name: &str) -> Result<Option<NamespaceInfo>> {
+        let find = |namespaces: Vec<NamespaceInfo>| {
+            namespaces
+                .into_iter()
+                .find(|namespace| namespace.name == name)
+        };
+        if let Some(namespace) = find(self.list_namespaces().await?) {
+            return Ok(Some(namespace));
+        }
+        Ok(find(self.refresh_namespaces().await?))
+    }
+
+    /// Creates a namespace. Without an `id`, the server generates one.
+    pub async fn create_namespace(
+        &self,
+        id: Option<&str>,
+        name: &str,
+        description: &str,
+    ) -> Result<()> {
+        let url = self.make_url("/nacos/v1/console/namespaces");
+        let form = [
+            ("customNamespaceId", id.unwrap_or_default()),
+            ("namespaceName", name),
+            ("namespaceDesc", description),
+        ];
+        let request = self.client.post(url).form(&form);
+        let response = self
+            .send(
+                "nacos.namespace.create",
+                request,
+                &[("nacos.namespace", name)],
+            )
+            .await?;
+        self.namespaces.lock().unwrap().take();
+        expect_true(&response.text().await?)
+    }
+
+    pub async fn update_namespace(&self, id: &str, name: &str, description: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/console/namespaces");
+        let form = [
+            ("namespace", id),
+            ("namespaceShowName", name),
+            ("namespaceDesc", description),
+        ];
+        let request = self.client.put(url).form(&form);
+        let response = self
+            .send(
+                "nacos.namespace.update",
+                request,
+                &[("nacos.namespace", id)],
+            )
+            .await?;
+        self.namespaces.lock().unwrap().take();
+        expect_true(&response.text().await?)
+    }
+
+    pub async fn delete_namespace(&self, id: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/console/namespaces");
+        let request = self.client.delete(url).query(&[("namespaceId", id)]);
+        let response = self
+            .send(
+                "nacos.namespace.delete",
+                request,
+                &[("nacos.namespace", id)],
+            )
+            .await?;
+        self.namespaces.lock().unwrap().take();
+        expect_true(&response.text().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    #[cfg(feature = "auth")]
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[cfg(feature = "admin")]
+    #[test]
+    fn namespace_list() {
+        let list: NamespaceList = serde_json::from_str(
+            r#"{"code": 200, "message": null, "data": [
+                {"namespace": "", "namespaceShowName": "public", "namespaceDesc": null,
+                 "quota": 200, "configCount": 3, "type": 0},
+                {"namespace": "8a1c", "namespaceShowName": "staging", "namespaceDesc": "Staging",
+                 "quota": 200, "configCount": 0, "type": 2}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(list.data[0].id, "");
+        assert_eq!(list.data[0].config_count, 3);
+        assert_eq!(list.data[1].name, "staging");
+        assert_eq!(list.data[1].description.as_deref(), Some("Staging"));
+    }
+
+    #[cfg(feature = "admin")]
+    #[tokio::test]
+    async fn find_by_name() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let staging = NamespaceInfo {
+            id: "8a1c".into(),
+            name: "staging".into(),
+            description: None,
+            quota: 200,
+            config_count: 0,
+            kind: 2,
+        };
+        *nacos.namespaces.lock().unwrap() = Some((Instant::now(), vec![staging.clone()]));
+        assert_eq!(
+            nacos.find_namespace_by_name("staging").await.unwrap(),
+            Some(staging)
+        );
+        // Unknown names refresh the cache, which fails without a server.
+        assert!(nacos.find_namespace_by_name("prod").await.is_err());
+    }
+
+    #[test]
+    fn handle() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            Some("dev".into()),
+            "DEFAULT_GROUP".into(),
+        );
+        let staging = nacos.namespace("8a1c");
+        assert_eq!(staging.id(), "8a1c");
+        assert_eq!(
+            staging.config_key("DEFAULT_GROUP", "app.yaml").tenant,
+            "8a1c"
+        );
+        #[cfg(feature = "auth")]
+        assert!(Arc::ptr_eq(&staging.auth, &nacos.auth));
+        assert_eq!(nacos.namespace("").id(), "");
+    }
+}
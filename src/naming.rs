@@ -0,0 +1,342 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::{
+    live::{AbortOnDrop, RETRY_DELAY},
+    Nacos, Result,
+};
+
+/// How often to refresh a subscription when the server doesn't say.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// An instance of a service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instance {
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    pub ip: String,
+    pub port: u16,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default = "default_true")]
+    pub healthy: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub ephemeral: bool,
+    #[serde(default)]
+    pub cluster_name: Option<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl Instance {
+    /// `ip:port`, which identifies the instance within its service.
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+
+    /// Whether the instance should receive traffic.
+    pub fn is_available(&self) -> bool {
+        self.healthy && self.enabled && self.weight > 0.0
+    }
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ServiceInfo {
+    #[serde(default)]
+    hosts: Vec<Instance>,
+    cache_millis: Option<u64>,
+}
+
+/// How the instances of a service changed, see [`ServiceSubscription::changed_delta`].
+/// Instances are identified by [`Instance::addr`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstanceDelta {
+    pub added: Vec<Instance>,
+    pub removed: Vec<Instance>,
+    /// The new values of instances that changed, e.g. their health or weight.
+    pub updated: Vec<Instance>,
+}
+
+impl InstanceDelta {
+    /// Reconciles two lists of instances.
+    fn between(old: &[Instance], new: &[Instance]) -> Self {
+        let old_by_addr: HashMap<_, _> = old
+            .iter()
+            .map(|instance| (instance.addr(), instance))
+            .collect();
+        let mut delta = Self::default();
+        let mut kept = 0;
+        for instance in new {
+            match old_by_addr.get(&instance.addr()) {
+                Some(&old) => {
+                    kept += 1;
+                    if old != instance {
+                        delta.updated.push(instance.clone());
+                    }
+                }
+                None => delta.added.push(instance.clone()),
+            }
+        }
+        if kept < old.len() {
+            let new_addrs: std::collections::HashSet<_> = new.iter().map(Instance::addr).collect();
+            delta.removed = old
+                .iter()
+                .filter(|instance| !new_addrs.contains(&instance.addr()))
+                .cloned()
+                .collect();
+        }
+        delta
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// The instances of a service after a refresh, with how they changed in it.
+#[derive(Clone, Default)]
+pub(crate) struct ServiceState {
+    pub(crate) instances: Arc<Vec<Instance>>,
+    /// Counts the refreshes that changed the instances.
+    version: u64,
+    delta: Arc<InstanceDelta>,
+}
+
+impl ServiceState {
+    pub(crate) fn new(instances: Vec<Instance>) -> Self {
+        Self {
+            instances: Arc::new(instances),
+            ..Default::default()
+        }
+    }
+
+    /// The state after a refresh, `None` if nothing changed.
+    fn refresh(&self, instances: Vec<Instance>) -> Option<Self> {
+        let delta = InstanceDelta::between(&self.instances, &instances);
+        (!delta.is_empty()).then(|| Self {
+            instances: Arc::new(instances),
+            version: self.version + 1,
+            delta: Arc::new(delta),
+        })
+    }
+}
+
+/// Instances of a service kept up to date by a background task, see
+/// [`Nacos::subscribe_service`].
+///
+/// The task stops when the last clone of the subscription is dropped.
+#[derive(Clone)]
+pub struct ServiceSubscription {
+    state: watch::Receiver<ServiceState>,
+    /// The state of the last delta returned.
+    seen: ServiceState,
+    _task: Arc<AbortOnDrop>,
+}
+
+impl ServiceSubscription {
+    pub(crate) fn new(state: watch::Receiver<ServiceState>, task: AbortOnDrop) -> Self {
+        let seen = state.borrow().clone();
+        Self {
+            state,
+            seen,
+            _task: Arc::new(task),
+        }
+    }
+
+    /// All instances, including unhealthy ones.
+    pub fn instances(&self) -> Arc<Vec<Instance>> {
+        self.state.borrow().instances.clone()
+    }
+
+    /// Waits for the instances to change since this subscription last waited.
+    pub async fn changed(&mut self) {
+        // The sender lives as long as the task.
+        let _ = self.state.changed().await;
+    }
+
+    /// Like [`Self::changed`], returning how the instances changed since the last delta, or
+    /// since subscribing. Subscribers that keep up share the delta of each refresh.
+    pub async fn changed_delta(&mut self) -> Arc<InstanceDelta> {
+        self.changed().await;
+        let state = self.state.borrow_and_update().clone();
+        let delta = if state.version == self.seen.version + 1 {
+            state.delta.clone()
+        } else {
+            Arc::new(InstanceDelta::between(
+                &self.seen.instances,
+                &state.instances,
+            ))
+        };
+        self.seen = state;
+        delta
+    }
+
+    #[cfg(feature = "tower")]
+    pub(crate) fn receiver(&self) -> watch::Receiver<ServiceState> {
+        self.state.clone()
+    }
+}
+
+impl Nacos {
+    /// Lists the instances of a service in the client's group.
+    pub async fn list_instances(
+        &self,
+        service_name: &str,
+        healthy_only: bool,
+    ) -> Result<Vec<Instance>> {
+        Ok(self.query_service(service_name, healthy_only).await?.hosts)
+    }
+
+    /// Lists the instances of a service and refreshes them in the background, as often as the
+    /// server asks.
+    pub async fn subscribe_service(&self, service_name: &str) -> Result<ServiceSubscription> {
+        let service = self.query_service(service_name, false).await?;
+        let mut interval = refresh_interval(&service);
+        let (sender, state) = watch::channel(ServiceState::new(service.hosts));
+
+        let nacos = self.clone();
+        let service_name = service_name.to_string();
+        let task = self.spawn(async move {
+            loop {
+                nacos.sleep(interval).await;
+                match nacos.query_service(&service_name, false).await {
+                    Ok(service) => {
+                        interval = refresh_interval(&service);
+                        sender.send_if_modified(|state| match state.refresh(service.hosts) {
+                            Some(refreshed) => {
+                                *state = refreshed;
+                                true
+                            }
+                            None => false,
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!(service = %service_name, error = %e, "Failed to refresh service");
+                        interval = RETRY_DELAY;
+                    }
+                }
+            }
+        });
+        Ok(ServiceSubscription::new(state, AbortOnDrop(vec![task])))
+    }
+
+    async fn query_service(&self, service_name: &str, healthy_only: bool) -> Result<ServiceInfo> {
+        let url = self.make_url("/nacos/v1/ns/instance/list");
+        let mut request = self.client.get(url);
+        if let Some(namespace) = &self.namespace {
+            request = request.query(&[("namespaceId", namespace.as_str())]);
+        }
+        request = request.query(&[
+            ("serviceName", service_name),
+            ("groupName", self.group.as_str()),
+            ("healthyOnly", if healthy_only { "true" } else { "false" }),
+        ]);
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.service", service_name),
+        ];
+        let response = self
+            .send("nacos.naming.query", request, &attributes)
+            .await?;
+        Ok(response.json().await?)
+    }
+}
+
+fn refresh_interval(service: &ServiceInfo) -> Duration {
+    service
+        .cache_millis
+        .filter(|&millis| millis > 0)
+        .map_or(DEFAULT_REFRESH_INTERVAL, Duration::from_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_info() {
+        let service: ServiceInfo = serde_json::from_str(
+            r#"{
+                "name": "DEFAULT_GROUP@@orders",
+                "cacheMillis": 3000,
+                "hosts": [{
+                    "instanceId": "10.0.0.1#8080#DEFAULT#DEFAULT_GROUP@@orders",
+                    "ip": "10.0.0.1",
+                    "port": 8080,
+                    "weight": 1.0,
+                    "healthy": false,
+                    "enabled": true,
+                    "ephemeral": true,
+                    "clusterName": "DEFAULT",
+                    "serviceName": "DEFAULT_GROUP@@orders",
+                    "metadata": {"version": "2"}
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(refresh_interval(&service), Duration::from_secs(3));
+        let instance = &service.hosts[0];
+        assert_eq!(instance.addr(), "10.0.0.1:8080");
+        assert_eq!(instance.metadata["version"], "2");
+        assert!(!instance.is_available());
+    }
+
+    fn instance(ip: &str, weight: f64) -> Instance {
+        Instance {
+            instance_id: None,
+            ip: ip.into(),
+            port: 80,
+            weight,
+            healthy: true,
+            enabled: true,
+            ephemeral: true,
+            cluster_name: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn delta() {
+        let state = ServiceState::new(vec![instance("10.0.0.1", 1.0), instance("10.0.0.2", 1.0)]);
+        assert!(state.refresh(state.instances.to_vec()).is_none());
+        let (sender, receiver) = watch::channel(state);
+        let mut subscription = ServiceSubscription::new(receiver, AbortOnDrop(vec![]));
+        let mut lagging = subscription.clone();
+
+        let refresh = |instances| {
+            sender.send_modify(|state| *state = state.refresh(instances).unwrap());
+        };
+        refresh(vec![instance("10.0.0.1", 0.5), instance("10.0.0.3", 1.0)]);
+        let delta = subscription.changed_delta().await;
+        assert_eq!(
+            *delta,
+            InstanceDelta {
+                added: vec![instance("10.0.0.3", 1.0)],
+                removed: vec![instance("10.0.0.2", 1.0)],
+                updated: vec![instance("10.0.0.1", 0.5)],
+            }
+        );
+
+        refresh(vec![instance("10.0.0.1", 1.0), instance("10.0.0.3", 1.0)]);
+        assert_eq!(subscription.changed_delta().await.updated.len(), 1);
+        // Deltas missed in between are combined.
+        let delta = lagging.changed_delta().await;
+        assert_eq!(delta.added, [instance("10.0.0.3", 1.0)]);
+        assert_eq!(delta.removed, [instance("10.0.0.2", 1.0)]);
+        assert!(delta.updated.is_empty());
+    }
+}
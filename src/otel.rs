@@ -0,0 +1,107 @@
+use opentelemetry::{
+    global,
+    propagation::Injector,
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    RequestBuilder, Response,
+};
+
+use crate::{check_status, Nacos, Result};
+
+/// Sends a request in a client span of the global tracer, propagating the span's context in
+/// the request headers through the global propagator.
+pub(crate) async fn send(
+    nacos: &Nacos,
+    operation: &'static str,
+    request: RequestBuilder,
+    attributes: &[(&'static str, &str)],
+    request_id: &str,
+) -> Result<Response> {
+    let tracer = global::tracer("nacos");
+    let mut span_attributes = vec![
+        KeyValue::new("rpc.system", "nacos"),
+        KeyValue::new("server.address", nacos.servers.current().host.clone()),
+        KeyValue::new("server.port", i64::from(nacos.servers.current().port)),
+    ];
+    if let Some(namespace) = &nacos.namespace {
+        span_attributes.push(KeyValue::new("nacos.namespace", namespace.clone()));
+    }
+    span_attributes.extend(
+        attributes
+            .iter()
+            .map(|&(key, value)| KeyValue::new(key, value.to_string())),
+    );
+    let span = tracer
+        .span_builder(operation)
+        .with_kind(SpanKind::Client)
+        .with_attributes(span_attributes)
+        .start(&tracer);
+    let cx = Context::current_with_span(span);
+
+    let mut headers = HeaderMap::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut HeaderInjector(&mut headers))
+    });
+    let result = match nacos
+        .execute(request.headers(headers), request_id)
+        .with_context(cx.clone())
+        .await
+    {
+        Ok(response) => check_status(request_id, nacos.observe(operation, response)).await,
+        Err(e) => Err(e),
+    };
+
+    let span = cx.span();
+    let status = match &result {
+        Ok(response) => Some(response.status()),
+        Err(e) => e.status(),
+    };
+    if let Some(status) = status {
+        span.set_attribute(KeyValue::new(
+            "http.response.status_code",
+            i64::from(status.as_u16()),
+        ));
+    }
+    if let Err(e) = &result {
+        span.set_status(Status::error(e.to_string()));
+    }
+    span.end();
+    result
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn send_error() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let request = nacos.client.get(nacos.make_url("/nacos/v1/cs/configs"));
+        let result = send(&nacos, "nacos.config.fetch", request, &[], "1").await;
+        assert!(matches!(result, Err(Error::Request { source, .. }) if source.is_connect()));
+    }
+}
@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+/// A page of results of the admin APIs.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub total_count: u64,
+    /// Starting from 1.
+    pub page_number: u64,
+    pub pages_available: u64,
+    pub page_items: Vec<T>,
+}
@@ -0,0 +1,92 @@
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "config")]
+use crate::ConfigHandle;
+use crate::{ConfigFormat, Nacos, Result};
+
+/// Data ids of an application following the spring-cloud-alibaba convention: `app`,
+/// `app.yaml` and `app-{profile}.yaml` for each active profile, in increasing precedence.
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    app_name: String,
+    profiles: Vec<String>,
+    file_extension: String,
+}
+
+impl Profiles {
+    pub fn new<P: Into<String>>(
+        app_name: impl Into<String>,
+        profiles: impl IntoIterator<Item = P>,
+    ) -> Self {
+        Self {
+            app_name: app_name.into(),
+            profiles: profiles.into_iter().map(Into::into).collect(),
+            file_extension: "yaml".into(),
+        }
+    }
+
+    /// Sets the file extension, `yaml` by default. It also selects the format of every layer.
+    pub fn file_extension(mut self, file_extension: impl Into<String>) -> Self {
+        self.file_extension = file_extension.into();
+        self
+    }
+
+    pub fn data_ids(&self) -> Vec<String> {
+        let app = &self.app_name;
+        let extension = &self.file_extension;
+        let mut data_ids = vec![app.clone(), format!("{app}.{extension}")];
+        data_ids.extend(
+            self.profiles
+                .iter()
+                .map(|profile| format!("{app}-{profile}.{extension}")),
+        );
+        data_ids
+    }
+
+    fn format(&self) -> Option<ConfigFormat> {
+        ConfigFormat::from_config_type(&self.file_extension)
+    }
+}
+
+impl Nacos {
+    /// Fetches the layered config of an application, see [`Profiles`].
+    pub async fn get_profile_config_as<T: DeserializeOwned>(
+        &self,
+        profiles: &Profiles,
+    ) -> Result<T> {
+        let data_ids = profiles.data_ids();
+        let data_ids: Vec<_> = data_ids.iter().map(String::as_str).collect();
+        self.get_layered_config_as(&data_ids, profiles.format())
+            .await
+    }
+
+    /// Like [`Self::watch_layered_live`] for the config of an application, see [`Profiles`].
+    #[cfg(feature = "config")]
+    pub async fn watch_profile_live<T>(&self, profiles: &Profiles) -> Result<ConfigHandle<T>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+    {
+        let data_ids = profiles.data_ids();
+        let data_ids: Vec<_> = data_ids.iter().map(String::as_str).collect();
+        self.watch_layered_live(&data_ids, profiles.format()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_ids() {
+        let profiles = Profiles::new("app", ["prod", "gray"]);
+        assert_eq!(
+            profiles.data_ids(),
+            ["app", "app.yaml", "app-prod.yaml", "app-gray.yaml"]
+        );
+        assert_eq!(profiles.format(), Some(ConfigFormat::Yaml));
+
+        let profiles = Profiles::new("app", Vec::<String>::new()).file_extension("properties");
+        assert_eq!(profiles.data_ids(), ["app", "app.properties"]);
+        assert_eq!(profiles.format(), Some(ConfigFormat::Properties));
+    }
+}
@@ -0,0 +1,365 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use indexmap::IndexMap;
+
+use crate::{redact::Content, ConfigFormat, Error, Result};
+
+/// Key-value pairs of a Java `.properties` config, in declaration order. Its `Debug` output
+/// summarizes the values, see [`crate::set_log_content`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Properties {
+    entries: IndexMap<String, String>,
+}
+
+impl fmt::Debug for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.entries
+                    .iter()
+                    .map(|(key, value)| (key, Content(value.as_bytes()))),
+            )
+            .finish()
+    }
+}
+
+impl Properties {
+    /// Parses `content` following the rules of `java.util.Properties::load`.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries = IndexMap::new();
+        let mut lines = natural_lines(content).enumerate();
+        while let Some((number, line)) = lines.next() {
+            let line = trim_start(line);
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            let mut logical = line.to_string();
+            while ends_with_continuation(&logical) {
+                logical.pop();
+                match lines.next() {
+                    Some((_, next)) => logical.push_str(trim_start(next)),
+                    None => break,
+                }
+            }
+            let (key, value) = split_key_value(&logical);
+            entries.insert(unescape(key, number)?, unescape(value, number)?);
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Parses `true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`, ignoring case.
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        self.get_parsed(key, "a boolean", |value| {
+            match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(true),
+                "false" | "no" | "off" | "0" => Some(false),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn get_i64(&self, key: &str) -> Result<Option<i64>> {
+        self.get_parsed(key, "an integer", |value| value.parse().ok())
+    }
+
+    /// Parses an integer with an optional unit (`ns`, `us`, `ms`, `s`, `m`, `h` or `d`), like
+    /// `30s`. Without a unit, it is in milliseconds.
+    pub fn get_duration(&self, key: &str) -> Result<Option<Duration>> {
+        self.get_parsed(key, "a duration like `30s`", parse_duration)
+    }
+
+    /// Splits a comma-separated value, trimming each item.
+    pub fn get_list(&self, key: &str) -> Option<Vec<&str>> {
+        let value = self.get(key)?.trim();
+        Some(if value.is_empty() {
+            Vec::new()
+        } else {
+            value.split(',').map(str::trim).collect()
+        })
+    }
+
+    fn get_parsed<T>(
+        &self,
+        key: &str,
+        expected: &'static str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<Option<T>> {
+        let Some(value) = self.get(key) else {
+            return Ok(None);
+        };
+        parse(value.trim())
+            .map(Some)
+            .ok_or_else(|| Error::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+                expected,
+            })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn into_map(self) -> IndexMap<String, String> {
+        self.entries
+    }
+}
+
+impl FromStr for Properties {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl IntoIterator for Properties {
+    type Item = (String, String);
+    type IntoIter = indexmap::map::IntoIter<String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit.trim() {
+        "ns" => Duration::from_nanos(amount),
+        "us" => Duration::from_micros(amount),
+        "" | "ms" => Duration::from_millis(amount),
+        "s" => Duration::from_secs(amount),
+        "m" => Duration::from_secs(amount.checked_mul(60)?),
+        "h" => Duration::from_secs(amount.checked_mul(60 * 60)?),
+        "d" => Duration::from_secs(amount.checked_mul(24 * 60 * 60)?),
+        _ => return None,
+    })
+}
+
+/// Splits on `\n`, `\r` or `\r\n`.
+fn natural_lines(content: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(content);
+    std::iter::from_fn(move || {
+        let current = rest?;
+        match current.find(['\r', '\n']) {
+            Some(end) => {
+                let skip = if current[end..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                rest = Some(&current[end + skip..]);
+                Some(&current[..end])
+            }
+            None => {
+                rest = None;
+                (!current.is_empty()).then_some(current)
+            }
+        }
+    })
+}
+
+fn trim_start(line: &str) -> &str {
+    line.trim_start_matches([' ', '\t', '\x0c'])
+}
+
+/// A line continues if it ends with an odd number of backslashes.
+fn ends_with_continuation(line: &str) -> bool {
+    line.bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+fn split_key_value(line: &str) -> (&str, &str) {
+    let bytes = line.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'\\' => end += 2,
+            b'=' | b':' | b' ' | b'\t' | b'\x0c' => break,
+            _ => end += 1,
+        }
+    }
+    let end = end.min(bytes.len());
+    let mut value = trim_start(&line[end..]);
+    if let Some(rest) = value.strip_prefix(['=', ':']) {
+        value = trim_start(rest);
+    }
+    (&line[..end], value)
+}
+
+fn unescape(s: &str, line: usize) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    let mut high_surrogate = None;
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            flush_surrogate(&mut out, &mut high_surrogate);
+            out.push(c);
+            continue;
+        }
+        let Some(escaped) = chars.next() else {
+            break;
+        };
+        if escaped != 'u' {
+            flush_surrogate(&mut out, &mut high_surrogate);
+        }
+        match escaped {
+            't' => out.push('\t'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            'f' => out.push('\x0c'),
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let unit = (hex.len() == 4)
+                    .then(|| u16::from_str_radix(&hex, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| Error::Parse {
+                        format: ConfigFormat::Properties,
+                        message: format!("line {}: malformed \\uxxxx encoding", line + 1),
+                    })?;
+                match (high_surrogate.take(), unit) {
+                    (Some(high), 0xDC00..=0xDFFF) => out.extend(
+                        char::decode_utf16([high, unit])
+                            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+                    ),
+                    (high, 0xD800..=0xDBFF) => {
+                        if high.is_some() {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                        }
+                        high_surrogate = Some(unit);
+                    }
+                    (high, _) => {
+                        if high.is_some() {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                        }
+                        out.push(
+                            char::from_u32(unit.into()).unwrap_or(char::REPLACEMENT_CHARACTER),
+                        );
+                    }
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    flush_surrogate(&mut out, &mut high_surrogate);
+    Ok(out)
+}
+
+fn flush_surrogate(out: &mut String, high_surrogate: &mut Option<u16>) {
+    if high_surrogate.take().is_some() {
+        out.push(char::REPLACEMENT_CHARACTER);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(content: &str) -> Vec<(String, String)> {
+        Properties::parse(content).unwrap().into_iter().collect()
+    }
+
+    fn pair(k: &str, v: &str) -> (String, String) {
+        (k.into(), v.into())
+    }
+
+    #[test]
+    fn separators_and_comments() {
+        let content = "# comment\n! also comment\n\nb = 1\na:2\r\nc 3\rd\n  e=\tspaced value  \n";
+        assert_eq!(
+            pairs(content),
+            [
+                pair("b", "1"),
+                pair("a", "2"),
+                pair("c", "3"),
+                pair("d", ""),
+                pair("e", "spaced value  "),
+            ]
+        );
+    }
+
+    #[test]
+    fn continuation() {
+        let content = "fruits = apple, \\\n    banana, \\\n    pear\nescaped = a\\\\\nnext = 1\n# comment \\\nlast = \\";
+        assert_eq!(
+            pairs(content),
+            [
+                pair("fruits", "apple, banana, pear"),
+                pair("escaped", "a\\"),
+                pair("next", "1"),
+                pair("last", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let content =
+            "key\\ with\\=seps = tab\\there\\n\\q\nunicode = \\u4f60\\u597D \\uD83D\\uDE00\n";
+        assert_eq!(
+            pairs(content),
+            [
+                pair("key with=seps", "tab\there\nq"),
+                pair("unicode", "你好 😀"),
+            ]
+        );
+        assert!(Properties::parse("bad = \\u12g4").is_err());
+        assert!(Properties::parse("short = \\u12").is_err());
+    }
+
+    #[test]
+    fn typed_getters() {
+        let properties = Properties::parse(
+            "on=ON\noff=0\nbad=maybe\nint= -42\nmillis=250\ntimeout=30s\nttl=2h\n\
+             list=a, b ,c\nempty=",
+        )
+        .unwrap();
+        assert_eq!(properties.get_bool("on").unwrap(), Some(true));
+        assert_eq!(properties.get_bool("off").unwrap(), Some(false));
+        assert_eq!(properties.get_bool("missing").unwrap(), None);
+        assert!(matches!(
+            properties.get_bool("bad"),
+            Err(Error::InvalidValue { key, .. }) if key == "bad"
+        ));
+        assert_eq!(properties.get_i64("int").unwrap(), Some(-42));
+        assert!(properties.get_i64("timeout").is_err());
+        assert_eq!(
+            properties.get_duration("millis").unwrap(),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            properties.get_duration("timeout").unwrap(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            properties.get_duration("ttl").unwrap(),
+            Some(Duration::from_secs(7200))
+        );
+        assert!(properties.get_duration("int").is_err());
+        assert_eq!(properties.get_list("list"), Some(vec!["a", "b", "c"]));
+        assert_eq!(properties.get_list("empty"), Some(vec![]));
+        assert_eq!(properties.get_list("missing"), None);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let properties = Properties::parse("a=1\nb=2\na=3").unwrap();
+        assert_eq!(properties.get("a"), Some("3"));
+        assert_eq!(properties.len(), 2);
+    }
+}
@@ -0,0 +1,264 @@
+//! The parts of the Nacos HTTP protocol that depend on neither the runtime nor the transport.
+
+#[cfg(feature = "config")]
+use std::collections::HashMap;
+
+use md5::{Digest, Md5};
+
+use crate::key::ConfigKey;
+
+#[cfg(feature = "config")]
+/// Separates the fields of a listened config.
+const FIELD_SEPARATOR: char = 2 as char;
+#[cfg(feature = "config")]
+/// Ends a listened config.
+const CONFIG_SEPARATOR: char = 1 as char;
+
+/// Path of the config API.
+pub(crate) const CONFIGS_PATH: &str = "/nacos/v1/cs/configs";
+#[cfg(feature = "config")]
+/// Path of the long polling API.
+pub(crate) const LISTENER_PATH: &str = "/nacos/v1/cs/configs/listener";
+
+/// The URL of `path` on a server, `host` being a name or an IP, IPv6 addresses in brackets.
+pub(crate) fn server_url(https: bool, host: &str, port: u16, path: &str) -> String {
+    let scheme = if https { "https" } else { "http" };
+    format!("{scheme}://{host}:{port}{path}")
+}
+
+/// Md5 digest of config content, hex encoded only when sent.
+pub(crate) type ContentMd5 = [u8; 16];
+
+pub(crate) fn md5(content: &[u8]) -> ContentMd5 {
+    Md5::digest(content).into()
+}
+
+/// Hex md5 of config content, as the server computes it.
+pub(crate) fn md5_hex(content: &[u8]) -> String {
+    hex::encode(md5(content))
+}
+
+/// Parses the hex md5 of a response header.
+pub(crate) fn parse_md5(hex: &str) -> Option<ContentMd5> {
+    let mut md5 = ContentMd5::default();
+    hex::decode_to_slice(hex, &mut md5).ok()?;
+    Some(md5)
+}
+
+#[cfg(feature = "config")]
+/// The `Listening-Configs` parameter of a long poll for a config whose content has `md5`,
+/// `None` if the config doesn't exist.
+pub(crate) fn listening_configs(key: &ConfigKey, md5: Option<&ContentMd5>) -> String {
+    let md5 = md5.map(hex::encode).unwrap_or_default();
+    let mut encoded = format!(
+        "{}{FIELD_SEPARATOR}{}{FIELD_SEPARATOR}{}",
+        key.data_id, key.group, md5
+    );
+    if !key.tenant.is_empty() {
+        encoded.push(FIELD_SEPARATOR);
+        encoded.push_str(&key.tenant);
+    }
+    encoded.push(CONFIG_SEPARATOR);
+    encoded
+}
+
+#[cfg(feature = "config")]
+/// The `Listening-Configs` parameter of a long poll for configs with their md5.
+pub(crate) fn listening_configs_of(configs: &[(ConfigKey, Option<ContentMd5>)]) -> String {
+    configs
+        .iter()
+        .map(|(key, md5)| listening_configs(key, md5.as_ref()))
+        .collect()
+}
+
+#[cfg(feature = "config")]
+/// The md5 of each tracked config, `None` for configs tracked as missing, as long polls
+/// compare them with the server's.
+#[derive(Debug, Default)]
+pub(crate) struct TrackedConfigs(HashMap<ConfigKey, Option<ContentMd5>>);
+
+#[cfg(feature = "config")]
+impl TrackedConfigs {
+    /// Tracks a config with `md5`, returning how it was tracked before, if it was.
+    pub(crate) fn track(
+        &mut self,
+        key: ConfigKey,
+        md5: Option<ContentMd5>,
+    ) -> Option<Option<ContentMd5>> {
+        self.0.insert(key, md5)
+    }
+
+    /// Updates the md5 of a config, if it is tracked.
+    pub(crate) fn update(&mut self, key: &ConfigKey, md5: ContentMd5) {
+        if let Some(tracked) = self.0.get_mut(key) {
+            *tracked = Some(md5);
+        }
+    }
+
+    pub(crate) fn untrack(&mut self, key: &ConfigKey) {
+        self.0.remove(key);
+    }
+
+    /// How a config is tracked, if it is.
+    pub(crate) fn get(&self, key: &ConfigKey) -> Option<Option<ContentMd5>> {
+        self.0.get(key).copied()
+    }
+
+    pub(crate) fn is_tracked(&self, key: &ConfigKey) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// The md5s to long poll `keys` with, untracked ones as missing.
+    pub(crate) fn with_md5s(&self, keys: Vec<ConfigKey>) -> Vec<(ConfigKey, Option<ContentMd5>)> {
+        keys.into_iter()
+            .map(|key| {
+                let md5 = self.get(&key).flatten();
+                (key, md5)
+            })
+            .collect()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &ConfigKey> {
+        self.0.keys()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[cfg(feature = "config")]
+/// The configs that a long poll reports as changed: URL encoded data ids, groups and
+/// tenants, separated like [`listening_configs`].
+pub(crate) fn changed_keys(body: &str) -> Vec<ConfigKey> {
+    let body = percent_decode(body.trim());
+    body.split(CONFIG_SEPARATOR)
+        .filter_map(|config| {
+            let mut fields = config.trim().split(FIELD_SEPARATOR);
+            let data_id = fields.next().filter(|data_id| !data_id.is_empty())?;
+            let group = fields.next()?;
+            let tenant = fields.next().unwrap_or_default();
+            Some(ConfigKey::new(group, data_id).with_tenant(tenant))
+        })
+        .collect()
+}
+
+#[cfg(feature = "config")]
+/// Decodes the output of Java's `URLEncoder`.
+fn percent_decode(encoded: &str) -> String {
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut bytes = encoded.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = [bytes.next().unwrap_or(0), bytes.next().unwrap_or(0)];
+                let value = std::str::from_utf8(&hex)
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                decoded.push(value.unwrap_or(b'?'));
+            }
+            byte => decoded.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The `tenant`, `group` and `dataId` parameters of a config request.
+pub(crate) fn config_params(key: &ConfigKey) -> Vec<(&'static str, &str)> {
+    let mut params = tenant_params(&key.tenant);
+    params.push(("group", key.group.as_str()));
+    params.push(("dataId", key.data_id.as_str()));
+    params
+}
+
+/// The `tenant` parameter of a request about configs of a namespace, left out for the public
+/// one.
+pub(crate) fn tenant_params(tenant: &str) -> Vec<(&'static str, &str)> {
+    let mut params = Vec::with_capacity(3);
+    if !tenant.is_empty() {
+        params.push(("tenant", tenant));
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn listening() {
+        let key = ConfigKey::new("DEFAULT_GROUP", "app.yaml");
+        assert_eq!(
+            listening_configs(&key, Some(&[0xab; 16])),
+            format!("app.yaml\u{2}DEFAULT_GROUP\u{2}{}\u{1}", "ab".repeat(16))
+        );
+        let key = key.with_tenant("dev");
+        assert_eq!(
+            listening_configs(&key, None),
+            "app.yaml\u{2}DEFAULT_GROUP\u{2}\u{2}dev\u{1}"
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn tracked() {
+        let key = ConfigKey::new("DEFAULT_GROUP", "app.yaml");
+        let missing = ConfigKey::new("DEFAULT_GROUP", "db.yaml");
+        let mut tracked = TrackedConfigs::default();
+        tracked.update(&key, [1; 16]);
+        assert!(!tracked.is_tracked(&key));
+        assert_eq!(tracked.track(key.clone(), None), None);
+        assert_eq!(tracked.get(&key), Some(None));
+        tracked.update(&key, [1; 16]);
+        assert_eq!(tracked.get(&key), Some(Some([1; 16])));
+        let polled = tracked.with_md5s(vec![key.clone(), missing.clone()]);
+        assert_eq!(polled, [(key.clone(), Some([1; 16])), (missing, None)]);
+        assert_eq!(
+            listening_configs_of(&polled),
+            format!(
+                "app.yaml\u{2}DEFAULT_GROUP\u{2}{}\u{1}db.yaml\u{2}DEFAULT_GROUP\u{2}\u{1}",
+                "01".repeat(16)
+            )
+        );
+        tracked.untrack(&key);
+        assert_eq!(tracked.len(), 0);
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(
+            server_url(true, "[::1]", 8848, CONFIGS_PATH),
+            "https://[::1]:8848/nacos/v1/cs/configs"
+        );
+        let key = ConfigKey::new("DEFAULT_GROUP", "app.yaml").with_tenant("dev");
+        assert_eq!(
+            config_params(&key),
+            [
+                ("tenant", "dev"),
+                ("group", "DEFAULT_GROUP"),
+                ("dataId", "app.yaml")
+            ]
+        );
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            parse_md5("d41d8cd98f00b204e9800998ecf8427e"),
+            Some(md5(b""))
+        );
+        assert_eq!(parse_md5("d41d"), None);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn changed() {
+        assert!(changed_keys("").is_empty());
+        assert_eq!(
+            changed_keys("app.yaml%02DEFAULT_GROUP%01db.yaml%02DB%02dev%01\n"),
+            [
+                ConfigKey::new("DEFAULT_GROUP", "app.yaml"),
+                ConfigKey::new("DB", "db.yaml").with_tenant("dev")
+            ]
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{encryption::Encrypted, protocol, ConfigFormat, Error, Nacos, Result};
+
+/// Who changes a config and why, shown in the history and audit views of the console instead
+/// of only the client's IP.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeMetadata {
+    src_user: Option<String>,
+    app_name: Option<String>,
+    description: Option<String>,
+}
+
+impl ChangeMetadata {
+    pub fn with_src_user(mut self, src_user: impl Into<String>) -> Self {
+        self.src_user = Some(src_user.into());
+        self
+    }
+
+    pub fn with_app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// The description of the config, only used when publishing.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    fn params(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("src_user", &self.src_user),
+            ("appName", &self.app_name),
+            ("desc", &self.description),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.as_deref()?)))
+        .collect()
+    }
+}
+
+impl Nacos {
+    /// Creates or replaces a config in the client's group, after the client's filters and, see
+    /// [`Self::with_publish_encryption`], encryption. `format` sets the config type shown in
+    /// the console and reported to readers.
+    pub async fn publish_config(
+        &self,
+        data_id: &str,
+        content: &str,
+        format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        self.publish_config_with(data_id, content, format, &ChangeMetadata::default())
+            .await
+    }
+
+    /// Like [`Self::publish_config`], attributing the change to `metadata`.
+    pub async fn publish_config_with(
+        &self,
+        data_id: &str,
+        content: &str,
+        format: Option<ConfigFormat>,
+        metadata: &ChangeMetadata,
+    ) -> Result<()> {
+        let encrypted = self.prepare_published(data_id, content).await?;
+        self.send_published(data_id, &encrypted, format, metadata)
+            .await
+    }
+
+    /// The content to publish, validated, filtered and encrypted.
+    pub(crate) async fn prepare_published(
+        &self,
+        data_id: &str,
+        content: &str,
+    ) -> Result<Encrypted> {
+        crate::ids::validate_key(&self.group, data_id)?;
+        let content = self.filter_published(data_id, content).await?;
+        self.encrypt(data_id, content).await
+    }
+
+    pub(crate) async fn send_published(
+        &self,
+        data_id: &str,
+        encrypted: &Encrypted,
+        format: Option<ConfigFormat>,
+        metadata: &ChangeMetadata,
+    ) -> Result<()> {
+        let url = self.make_url(protocol::CONFIGS_PATH);
+        let key = self.config_key(&self.group, data_id);
+        let mut form = protocol::config_params(&key);
+        form.push(("content", &encrypted.content));
+        if let Some(data_key) = &encrypted.data_key {
+            form.push(("encryptedDataKey", data_key.as_str()));
+        }
+        if let Some(format) = format {
+            form.push(("type", format.config_type()));
+        }
+        form.extend(metadata.params());
+        for (name, value) in self.app_params() {
+            // The app name of the change, if any, takes precedence.
+            if !form.iter().any(|(other, _)| *other == name) {
+                form.push((name, value));
+            }
+        }
+        let request = self.client.post(url).form(&form);
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.data_id", data_id),
+        ];
+        let response = self
+            .send("nacos.config.publish", request, &attributes)
+            .await?;
+        expect_true(&response.text().await?)
+    }
+
+    /// Deletes a config from the client's group. Deleting a config that doesn't exist succeeds.
+    pub async fn delete_config(&self, data_id: &str) -> Result<()> {
+        self.delete_config_with(data_id, &ChangeMetadata::default())
+            .await
+    }
+
+    /// Like [`Self::delete_config`], attributing the change to `metadata`.
+    pub async fn delete_config_with(&self, data_id: &str, metadata: &ChangeMetadata) -> Result<()> {
+        crate::ids::validate_key(&self.group, data_id)?;
+        self.send_deleted(data_id, metadata).await
+    }
+
+    pub(crate) async fn send_deleted(
+        &self,
+        data_id: &str,
+        metadata: &ChangeMetadata,
+    ) -> Result<()> {
+        let url = self.make_url(protocol::CONFIGS_PATH);
+        let key = self.config_key(&self.group, data_id);
+        let request = self
+            .client
+            .delete(url)
+            .query(&protocol::config_params(&key))
+            .query(&metadata.params());
+        let request = match &self.tag {
+            Some(tag) => request.query(&[("tag", tag)]),
+            None => request,
+        };
+        let attributes = [
+            ("nacos.group", self.group.as_str()),
+            ("nacos.data_id", data_id),
+        ];
+        let response = self
+            .send("nacos.config.delete", request, &attributes)
+            .await?;
+        expect_true(&response.text().await?)
+    }
+}
+
+/// Write APIs answer `true`, or `false` if the server did not apply the change.
+pub(crate) fn expect_true(body: &str) -> Result<()> {
+    match body.trim() {
+        "true" => Ok(()),
+        body => Err(Error::Refused(body.to_string())),
+    }
+}
+
+#[cfg(feature = "admin")]
+/// Admin APIs answer a JSON result whose code is 200 on success.
+pub(crate) fn expect_ok(body: &str) -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct RestResult {
+        code: u32,
+        #[serde(default)]
+        message: Option<String>,
+    }
+    match serde_json::from_str::<RestResult>(body) {
+        Ok(RestResult { code: 200, .. }) => Ok(()),
+        Ok(RestResult { message, .. }) => Err(Error::Refused(message.unwrap_or_default())),
+        Err(_) => Err(Error::Refused(body.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata() {
+        assert!(ChangeMetadata::default().params().is_empty());
+        let metadata = ChangeMetadata::default()
+            .with_src_user("alice")
+            .with_description("Raise the pool size");
+        assert_eq!(
+            metadata.params(),
+            [("src_user", "alice"), ("desc", "Raise the pool size")]
+        );
+    }
+
+    #[test]
+    fn write_response() {
+        assert!(expect_true("true\n").is_ok());
+        assert!(matches!(expect_true("false"), Err(Error::Refused(body)) if body == "false"));
+    }
+
+    #[cfg(feature = "admin")]
+    #[test]
+    fn admin_response() {
+        assert!(expect_ok(r#"{"code": 200, "message": "create user ok!", "data": null}"#).is_ok());
+        assert!(matches!(
+            expect_ok(r#"{"code": 400, "message": "user 'a' already exist!"}"#),
+            Err(Error::Refused(message)) if message == "user 'a' already exist!"
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static LOG_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// Lets `Debug` output and debug logs include config content, which they otherwise summarize
+/// as its size and md5. Credentials are never included.
+pub fn set_log_content(enabled: bool) {
+    LOG_CONTENT.store(enabled, Ordering::Relaxed);
+}
+
+/// Config content as it may appear in logs and `Debug` output.
+pub(crate) struct Content<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Display for Content<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_CONTENT.load(Ordering::Relaxed) {
+            f.write_str(&String::from_utf8_lossy(self.0))
+        } else {
+            write!(
+                f,
+                "<{} bytes, md5 {}>",
+                self.0.len(),
+                crate::protocol::md5_hex(self.0)
+            )
+        }
+    }
+}
+
+impl fmt::Debug for Content<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_CONTENT.load(Ordering::Relaxed) {
+            fmt::Debug::fmt(&String::from_utf8_lossy(self.0), f)
+        } else {
+            fmt::Display::fmt(self, f)
+        }
+    }
+}
+
+/// Masks the access token in the URL of a request error, which its `Display` includes.
+pub(crate) fn redact_url(error: reqwest::Error) -> reqwest::Error {
+    let Some(url) = error.url() else {
+        return error;
+    };
+    if !url.query_pairs().any(|(name, _)| name == "accessToken") {
+        return error;
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if name == "accessToken" {
+                "***".into()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    error.with_url(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn redacts_access_token() {
+        let error = reqwest::Client::new()
+            .get("http://127.0.0.1:1/nacos/v1/cs/configs?dataId=a&accessToken=secret")
+            .send()
+            .await
+            .unwrap_err();
+        let message = redact_url(error).to_string();
+        assert!(!message.contains("secret"), "{}", message);
+        assert!(message.contains("dataId=a&accessToken=***"), "{}", message);
+    }
+
+    #[test]
+    fn summarizes_content() {
+        let content = Content(b"password=hunter2");
+        assert!(content.to_string().starts_with("<16 bytes, md5 "));
+        assert!(!format!("{:?}", content).contains("hunter2"));
+    }
+}
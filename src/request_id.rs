@@ -0,0 +1,59 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Sends every request made by `future` with `request_id` in its `RequestId` header, e.g. to
+/// reuse the id of the incoming request being served. Other requests get a generated id.
+pub fn with_request_id<F: Future>(
+    request_id: impl Into<String>,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    REQUEST_ID.scope(request_id.into(), future)
+}
+
+/// The id of the current [`with_request_id`] scope, or else a new one.
+pub(crate) fn current() -> String {
+    REQUEST_ID
+        .try_with(Clone::clone)
+        .unwrap_or_else(|_| generate())
+}
+
+/// Unique within the process, and across processes in practice. Only numbered on wasm32,
+/// whose std has neither a clock nor process ids.
+fn generate() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        format!(
+            "{:x}-{:x}-{:x}",
+            started,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+    #[cfg(target_arch = "wasm32")]
+    format!("{:x}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn request_id() {
+        assert_ne!(current(), current());
+        let scoped = with_request_id("abc", async { (current(), current()) }).await;
+        assert_eq!(scoped, ("abc".to_string(), "abc".to_string()));
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use tokio::sync::Mutex;
+
+use crate::{runtime, Nacos, Result, ServiceSubscription};
+
+/// Resolves service names to the addresses of their available instances, subscribing to each
+/// service on first use.
+///
+/// Use it with [`reqwest::ClientBuilder::dns_resolver`] to send requests to URLs like
+/// `http://orders:8080/api`. Note that the port of the URL replaces the port of the instances.
+#[derive(Clone)]
+pub struct NacosResolver {
+    nacos: Nacos,
+    subscriptions: Arc<Mutex<HashMap<String, ServiceSubscription>>>,
+    /// Rotates the addresses between resolutions to spread connections.
+    next: Arc<AtomicUsize>,
+    dns_fallback: bool,
+}
+
+impl NacosResolver {
+    pub fn new(nacos: Nacos) -> Self {
+        Self {
+            nacos,
+            subscriptions: Default::default(),
+            next: Default::default(),
+            dns_fallback: false,
+        }
+    }
+
+    /// Resolves names without available instances with the system resolver, so that the same
+    /// client can also reach hosts outside of Nacos.
+    pub fn with_dns_fallback(mut self) -> Self {
+        self.dns_fallback = true;
+        self
+    }
+
+    /// Addresses of the available instances of a service, starting with a different one on
+    /// every call.
+    pub async fn resolve_service(&self, service_name: &str) -> Result<Vec<SocketAddr>> {
+        let instances = {
+            let mut subscriptions = self.subscriptions.lock().await;
+            match subscriptions.get(service_name) {
+                Some(subscription) => subscription.instances(),
+                None => {
+                    let subscription = self.nacos.subscribe_service(service_name).await?;
+                    let instances = subscription.instances();
+                    subscriptions.insert(service_name.to_string(), subscription);
+                    instances
+                }
+            }
+        };
+        let mut addrs: Vec<_> = instances
+            .iter()
+            .filter(|instance| instance.is_available())
+            .filter_map(|instance| {
+                let ip: IpAddr = instance.ip.parse().ok()?;
+                Some(SocketAddr::new(ip, instance.port))
+            })
+            .collect();
+        if !addrs.is_empty() {
+            let start = self.next.fetch_add(1, Ordering::Relaxed) % addrs.len();
+            addrs.rotate_left(start);
+        }
+        Ok(addrs)
+    }
+}
+
+impl Resolve for NacosResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let name = name.as_str();
+            let addrs = match resolver.resolve_service(name).await {
+                Ok(addrs) if !addrs.is_empty() => addrs,
+                _ if resolver.dns_fallback => runtime::lookup_host(name).await?,
+                Ok(_) => return Err(format!("no available instance of {name}").into()),
+                Err(e) => return Err(e.into()),
+            };
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::{live::AbortOnDrop, naming::ServiceState, Instance};
+
+    fn instance(ip: &str, healthy: bool) -> Instance {
+        Instance {
+            instance_id: None,
+            ip: ip.into(),
+            port: 8080,
+            weight: 1.0,
+            healthy,
+            enabled: true,
+            ephemeral: true,
+            cluster_name: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    async fn resolver(instances: Vec<Instance>) -> NacosResolver {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let resolver = NacosResolver::new(nacos);
+        let (_, receiver) = watch::channel(ServiceState::new(instances));
+        let subscription = ServiceSubscription::new(receiver, AbortOnDrop(vec![]));
+        let mut subscriptions = resolver.subscriptions.lock().await;
+        subscriptions.insert("orders".into(), subscription);
+        drop(subscriptions);
+        resolver
+    }
+
+    #[tokio::test]
+    async fn instances() {
+        let resolver = resolver(vec![
+            instance("10.0.0.1", true),
+            instance("10.0.0.2", true),
+            instance("10.0.0.3", false),
+            // Not an address, which a resolver can't return.
+            instance("orders.local", true),
+        ])
+        .await;
+        let first = SocketAddr::from_str("10.0.0.1:8080").unwrap();
+        let second = SocketAddr::from_str("10.0.0.2:8080").unwrap();
+        let addrs = resolver.resolve_service("orders").await.unwrap();
+        assert_eq!(addrs, [first, second]);
+        let addrs = resolver.resolve_service("orders").await.unwrap();
+        assert_eq!(addrs, [second, first]);
+
+        let addrs: Vec<_> = resolver
+            .resolve(Name::from_str("orders").unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert_eq!(addrs, [first, second]);
+    }
+
+    #[tokio::test]
+    async fn unavailable() {
+        let resolver = resolver(vec![instance("10.0.0.1", false)]).await;
+        let error = resolver.resolve(Name::from_str("orders").unwrap()).await;
+        assert!(error.is_err());
+        // Names unknown to an unreachable server fail too.
+        let error = resolver.resolve(Name::from_str("localhost").unwrap()).await;
+        assert!(error.is_err());
+
+        let resolver = resolver.with_dns_fallback();
+        let addrs: Vec<_> = resolver
+            .resolve(Name::from_str("localhost").unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback()));
+        assert!(!addrs.is_empty());
+    }
+}
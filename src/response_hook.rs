@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::Nacos;
+
+pub(crate) type ResponseHook = Arc<dyn Fn(&ResponseInfo<'_>) + Send + Sync>;
+
+/// A response of the server, as seen by [`Nacos::with_response_hook`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ResponseInfo<'a> {
+    /// What the request did, e.g. `nacos.config.fetch`.
+    pub operation: &'static str,
+    pub status: StatusCode,
+    pub headers: &'a HeaderMap,
+}
+
+impl Nacos {
+    /// Calls `hook` with the status and headers of every response, error statuses included,
+    /// e.g. to read rate limit hints added by a gateway. Hooks run in the order they were
+    /// added, before the body is read, and should return quickly.
+    pub fn with_response_hook(
+        mut self,
+        hook: impl Fn(&ResponseInfo<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.response_hooks.push(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn run_response_hooks(&self, operation: &'static str, response: &reqwest::Response) {
+        if self.response_hooks.is_empty() {
+            return;
+        }
+        let info = ResponseInfo {
+            operation,
+            status: response.status(),
+            headers: response.headers(),
+        };
+        for hook in &self.response_hooks {
+            hook(&info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Mutex};
+
+    use super::*;
+
+    #[test]
+    fn response_hook() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_response_hook(move |info| {
+            let remaining = info.headers["X-RateLimit-Remaining"].to_str().unwrap();
+            recorded
+                .lock()
+                .unwrap()
+                .push((info.operation, info.status, remaining.to_string()));
+        });
+        let response = hyper::Response::builder()
+            .status(429)
+            .header("X-RateLimit-Remaining", "0")
+            .body(hyper::Body::empty())
+            .unwrap();
+        nacos.run_response_hooks("nacos.config.fetch", &response.into());
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [(
+                "nacos.config.fetch",
+                StatusCode::TOO_MANY_REQUESTS,
+                "0".to_string()
+            )]
+        );
+    }
+}
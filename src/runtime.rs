@@ -0,0 +1,196 @@
+use std::{future::Future, io, pin::Pin, sync::Arc, time::Duration};
+
+use futures_util::future::{AbortHandle, Abortable};
+
+use crate::Nacos;
+
+/// std's clock panics on wasm32, where the browser's stands in.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type RuntimeFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+/// Browser futures can't be sent to other threads.
+#[cfg(target_arch = "wasm32")]
+pub type RuntimeFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+/// `Send`, except on wasm32 where tasks stay on the browser's thread.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+#[cfg(target_arch = "wasm32")]
+pub(crate) trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+/// Runs the background tasks and timers of a client, see [`Nacos::with_runtime`]. Tasks run
+/// on the current Tokio runtime by default, and on the browser's event loop on wasm32.
+///
+/// Implement it to run the client on another runtime, e.g. async-std or smol, along with a
+/// [`crate::Transport`] sending requests there. Files and DNS names are read on threads of
+/// their own, so the runtime needs no blocking pool.
+pub trait Runtime: Send + Sync + 'static {
+    /// Runs `task` in the background. Tasks stop themselves, the client never waits for one.
+    fn spawn(&self, task: RuntimeFuture);
+
+    /// Completes after `duration`, e.g. between retries.
+    fn sleep(&self, duration: Duration) -> RuntimeFuture;
+}
+
+/// Runs tasks on the current Tokio runtime, the default [`Runtime`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tokio;
+
+impl Runtime for Tokio {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn(&self, task: RuntimeFuture) {
+        tokio::spawn(task);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(&self, task: RuntimeFuture) {
+        tokio::task::spawn_local(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Runs tasks on the browser's event loop and sleeps with its timers, the default
+/// [`Runtime`] on wasm32.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wasm;
+
+#[cfg(target_arch = "wasm32")]
+impl Runtime for Wasm {
+    fn spawn(&self, task: RuntimeFuture) {
+        wasm_bindgen_futures::spawn_local(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> RuntimeFuture {
+        Box::pin(gloo_timers::future::sleep(duration))
+    }
+}
+
+impl Nacos {
+    /// Runs the background tasks and timers of this client with `runtime` instead of the
+    /// default one.
+    /// Requests still need a transport for that runtime, see [`Self::with_transport`].
+    pub fn with_runtime(mut self, runtime: impl Runtime) -> Self {
+        self.runtime = Arc::new(runtime);
+        self
+    }
+
+    /// Runs `task` on the client's runtime until it completes or is aborted.
+    pub(crate) fn spawn(
+        &self,
+        task: impl Future<Output = ()> + MaybeSend + 'static,
+    ) -> AbortHandle {
+        let (abort, registration) = AbortHandle::new_pair();
+        self.runtime.spawn(Box::pin(async move {
+            let _ = Abortable::new(task, registration).await;
+        }));
+        abort
+    }
+
+    pub(crate) async fn sleep(&self, duration: Duration) {
+        self.runtime.sleep(duration).await;
+    }
+}
+
+/// Runs blocking work on a thread of its own, which starts right away.
+pub(crate) fn unblock<T: Send + 'static>(
+    work: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> impl Future<Output = io::Result<T>> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let spawned = std::thread::Builder::new()
+        .name("nacos-blocking".into())
+        .spawn(move || {
+            let _ = sender.send(work());
+        });
+    async move {
+        spawned?;
+        receiver
+            .await
+            .unwrap_or_else(|_| Err(io::Error::other("blocking work panicked")))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Resolves a host name with the system's resolver, like `std::net::ToSocketAddrs`.
+pub(crate) async fn lookup_host(host: &str) -> io::Result<Vec<std::net::SocketAddr>> {
+    use std::net::ToSocketAddrs;
+
+    let host = host.to_string();
+    unblock(move || Ok((host.as_str(), 0).to_socket_addrs()?.collect())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    /// Counts spawned tasks and sleeps, running them on Tokio.
+    #[derive(Default)]
+    struct Counting {
+        spawned: Arc<AtomicUsize>,
+        slept: Arc<AtomicUsize>,
+    }
+
+    impl Runtime for Counting {
+        fn spawn(&self, task: RuntimeFuture) {
+            self.spawned.fetch_add(1, Ordering::Relaxed);
+            Tokio.spawn(task);
+        }
+
+        fn sleep(&self, duration: Duration) -> RuntimeFuture {
+            self.slept.fetch_add(1, Ordering::Relaxed);
+            Tokio.sleep(duration)
+        }
+    }
+
+    #[tokio::test]
+    async fn runtime() {
+        let runtime = Counting::default();
+        let (spawned, slept) = (runtime.spawned.clone(), runtime.slept.clone());
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_runtime(runtime);
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        nacos.spawn(async move {
+            let _ = sender.send(());
+        });
+        receiver.await.unwrap();
+        nacos.sleep(Duration::from_millis(1)).await;
+        assert_eq!(spawned.load(Ordering::Relaxed), 1);
+        assert_eq!(slept.load(Ordering::Relaxed), 1);
+
+        // Aborted tasks are dropped at their next await.
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        let task = nacos.spawn(async move {
+            std::future::pending::<()>().await;
+            let _ = sender.send(());
+        });
+        task.abort();
+        assert!(receiver.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unblocked() {
+        assert_eq!(unblock(|| Ok(1)).await.unwrap(), 1);
+        assert!(!lookup_host("localhost").await.unwrap().is_empty());
+    }
+}
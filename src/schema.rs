@@ -0,0 +1,67 @@
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+use crate::{value, ConfigFormat, Error, Nacos, Result};
+
+impl Nacos {
+    /// Validates every new value of a config against a JSON Schema, rejecting values that
+    /// don't conform as [`Self::set_validator`] does. The config is parsed with `format`, with
+    /// properties nested on `.` and all their values being strings.
+    pub fn set_schema(&self, data_id: &str, schema: &Value, format: ConfigFormat) -> Result<()> {
+        let schema =
+            JSONSchema::compile(schema).map_err(|e| Error::InvalidSchema(e.to_string()))?;
+        self.set_validator(data_id, move |content| {
+            let instance = value::parse_tree(content, format).map_err(|e| e.to_string())?;
+            schema.validate(&instance).map_err(|errors| {
+                errors
+                    .map(|error| format!("{} at {:?}", error, error.instance_path.to_string()))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn schema() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:8848").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        );
+        let schema = json!({
+            "type": "object",
+            "properties": {"port": {"type": "integer"}},
+            "required": ["port"],
+        });
+        nacos
+            .set_schema("app.json", &schema, ConfigFormat::Json)
+            .unwrap();
+        let key = nacos.config_key("DEFAULT_GROUP", "app.json");
+        let validate = |content: &'static [u8]| {
+            let (nacos, key) = (&nacos, &key);
+            async move { nacos.validate(key, &Bytes::from_static(content)).await }
+        };
+        validate(br#"{"port": 80}"#).await.unwrap();
+        let Err(Error::Rejected(reason)) = validate(br#"{"port": "80"}"#).await else {
+            panic!("expected a rejection");
+        };
+        assert!(reason.contains("/port"), "{reason}");
+        assert!(validate(b"not json").await.is_err());
+
+        assert!(nacos
+            .set_schema("app.json", &json!({"type": 1}), ConfigFormat::Json)
+            .is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    net::{Ipv6Addr, SocketAddr},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{protocol, Error, Result};
+
+/// Port of Nacos servers given without one.
+pub(crate) const DEFAULT_PORT: u16 = 8848;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Server {
+    pub(crate) https: bool,
+    /// Name or IP, IPv6 addresses in brackets.
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+impl Server {
+    pub(crate) fn base_url(&self) -> String {
+        self.url("")
+    }
+
+    /// The URL of `path` on this server.
+    pub(crate) fn url(&self, path: &str) -> String {
+        protocol::server_url(self.https, &self.host, self.port, path)
+    }
+
+    fn is_at(&self, url: &reqwest::Url) -> bool {
+        url.host_str() == Some(self.host.as_str()) && url.port_or_known_default() == Some(self.port)
+    }
+}
+
+impl From<(bool, SocketAddr)> for Server {
+    fn from((https, addr): (bool, SocketAddr)) -> Self {
+        let host = match addr {
+            SocketAddr::V4(addr) => addr.ip().to_string(),
+            SocketAddr::V6(addr) => format!("[{}]", addr.ip()),
+        };
+        Self {
+            https,
+            host,
+            port: addr.port(),
+        }
+    }
+}
+
+/// The servers of a cluster, used in turn when one can't be reached.
+#[derive(Debug)]
+pub(crate) struct ServerList {
+    servers: Vec<Server>,
+    current: AtomicUsize,
+}
+
+impl ServerList {
+    /// `servers` must not be empty.
+    pub(crate) fn new(servers: Vec<Server>) -> Self {
+        assert!(!servers.is_empty());
+        Self {
+            servers,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn current(&self) -> &Server {
+        &self.servers[self.current.load(Ordering::Relaxed) % self.servers.len()]
+    }
+
+    /// Switches to the server at `url`, which another one redirected to, if it is in the list.
+    pub(crate) fn redirected(&self, url: &reqwest::Url) -> Option<&Server> {
+        let https = url.scheme() == "https";
+        let index = self
+            .servers
+            .iter()
+            .position(|server| server.https == https && server.is_at(url))?;
+        self.current.store(index, Ordering::Relaxed);
+        Some(&self.servers[index])
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Moves on to the next server if the current one is at `url`, which could not be reached.
+    pub(crate) fn unreachable(&self, url: &reqwest::Url) {
+        let current = self.current.load(Ordering::Relaxed);
+        if self.servers[current % self.servers.len()].is_at(url) {
+            let _ = self.current.compare_exchange(
+                current,
+                current.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+/// Parses a server list like the Java client's `serverAddr`: comma-separated `host[:port]`
+/// entries, optionally prefixed with `http://` or `https://`.
+pub(crate) fn parse_server_list(servers: &str, https: bool) -> Result<Vec<Server>> {
+    let servers = servers
+        .split(',')
+        .map(str::trim)
+        .filter(|server| !server.is_empty())
+        .map(|server| parse_server(server, https))
+        .collect::<Result<Vec<_>>>()?;
+    if servers.is_empty() {
+        return Err(Error::InvalidServer(String::new()));
+    }
+    Ok(servers)
+}
+
+fn parse_server(server: &str, https: bool) -> Result<Server> {
+    let invalid = || Error::InvalidServer(server.to_string());
+    let (https, addr) = if let Some(addr) = server.strip_prefix("https://") {
+        (true, addr)
+    } else if let Some(addr) = server.strip_prefix("http://") {
+        (false, addr)
+    } else {
+        (https, server)
+    };
+    let addr = addr.strip_suffix('/').unwrap_or(addr);
+    if addr.is_empty() || addr.contains(['/', ' ', '@', '?', '#']) {
+        return Err(invalid());
+    }
+    let (host, port) = if let Some(rest) = addr.strip_prefix('[') {
+        let (ip, port) = rest.split_once(']').ok_or_else(invalid)?;
+        ip.parse::<Ipv6Addr>().map_err(|_| invalid())?;
+        let port = match port {
+            "" => None,
+            port => Some(port.strip_prefix(':').ok_or_else(invalid)?),
+        };
+        (format!("[{}]", ip), port)
+    } else if let Ok(ip) = addr.parse::<Ipv6Addr>() {
+        (format!("[{}]", ip), None)
+    } else {
+        match addr.split_once(':') {
+            Some((host, port)) => (host.to_string(), Some(port)),
+            None => (addr.to_string(), None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid())?,
+        None => DEFAULT_PORT,
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Server { https, host, port })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_list() {
+        let servers = parse_server_list(
+            "10.0.0.1:8848, nacos-1.internal,https://nacos-2.internal:443/,[::1]:9848,::2",
+            false,
+        )
+        .unwrap();
+        let urls: Vec<_> = servers.iter().map(Server::base_url).collect();
+        assert_eq!(
+            urls,
+            [
+                "http://10.0.0.1:8848",
+                "http://nacos-1.internal:8848",
+                "https://nacos-2.internal:443",
+                "http://[::1]:9848",
+                "http://[::2]:8848",
+            ]
+        );
+        assert_eq!(
+            parse_server_list("nacos", true).unwrap()[0].base_url(),
+            "https://nacos:8848"
+        );
+        for invalid in ["", " , ", "nacos:x", "nacos:8848/nacos", "[::1", ":8848"] {
+            assert!(parse_server_list(invalid, false).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn failover() {
+        let list = ServerList::new(parse_server_list("a,b:80", false).unwrap());
+        assert_eq!(list.current().host, "a");
+        // Failures of another server than the current one are old news.
+        list.unreachable(&"http://b/x".parse().unwrap());
+        assert_eq!(list.current().host, "a");
+        list.unreachable(&"http://a:8848/x".parse().unwrap());
+        assert_eq!(list.current().host, "b");
+        list.unreachable(&"http://b:80/x".parse().unwrap());
+        assert_eq!(list.current().host, "a");
+    }
+
+    #[test]
+    fn redirected() {
+        let list = ServerList::new(parse_server_list("a,b:80,https://c", false).unwrap());
+        let redirected = |url: &str| list.redirected(&url.parse().unwrap()).cloned();
+        assert_eq!(redirected("http://b/nacos/v1/cs/configs").unwrap().port, 80);
+        assert_eq!(list.current().host, "b");
+        assert_eq!(redirected("https://c:8848/x").unwrap().host, "c");
+        // Other hosts, ports and schemes are not of the cluster.
+        for url in [
+            "http://d:8848/x",
+            "http://a:80/x",
+            "https://a:8848/x",
+            "http://c:8848/x",
+        ] {
+            assert_eq!(redirected(url), None, "{}", url);
+        }
+        assert_eq!(list.current().host, "c");
+    }
+}
@@ -0,0 +1,138 @@
+use std::{future::Future, pin::Pin};
+
+use bytes::Bytes;
+
+#[cfg(feature = "naming")]
+use crate::Instance;
+use crate::{ConfigFormat, Nacos, Result};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type ServiceFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+/// Browser futures can't be sent to other threads.
+#[cfg(target_arch = "wasm32")]
+pub type ServiceFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// The config operations of [`Nacos`], for code that should also run against a fake in tests.
+pub trait ConfigService: Send + Sync {
+    /// See [`Nacos::get_config`].
+    fn get_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes>;
+
+    #[cfg(feature = "config")]
+    /// See [`Nacos::wait_for_new_config`].
+    fn wait_for_new_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes>;
+
+    /// See [`Nacos::publish_config`].
+    fn publish_config<'a>(
+        &'a self,
+        data_id: &'a str,
+        content: &'a str,
+        format: Option<ConfigFormat>,
+    ) -> ServiceFuture<'a, ()>;
+
+    /// See [`Nacos::delete_config`].
+    fn delete_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, ()>;
+}
+
+/// The naming operations of [`Nacos`], like [`ConfigService`].
+#[cfg(feature = "naming")]
+pub trait NamingService: Send + Sync {
+    /// See [`Nacos::list_instances`].
+    fn list_instances<'a>(
+        &'a self,
+        service_name: &'a str,
+        healthy_only: bool,
+    ) -> ServiceFuture<'a, Vec<Instance>>;
+}
+
+impl ConfigService for Nacos {
+    fn get_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes> {
+        Box::pin(Nacos::get_config(self, data_id))
+    }
+
+    #[cfg(feature = "config")]
+    fn wait_for_new_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes> {
+        Box::pin(Nacos::wait_for_new_config(self, data_id))
+    }
+
+    fn publish_config<'a>(
+        &'a self,
+        data_id: &'a str,
+        content: &'a str,
+        format: Option<ConfigFormat>,
+    ) -> ServiceFuture<'a, ()> {
+        Box::pin(Nacos::publish_config(self, data_id, content, format))
+    }
+
+    fn delete_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, ()> {
+        Box::pin(Nacos::delete_config(self, data_id))
+    }
+}
+
+#[cfg(feature = "naming")]
+impl NamingService for Nacos {
+    fn list_instances<'a>(
+        &'a self,
+        service_name: &'a str,
+        healthy_only: bool,
+    ) -> ServiceFuture<'a, Vec<Instance>> {
+        Box::pin(Nacos::list_instances(self, service_name, healthy_only))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+    use crate::Error;
+
+    #[derive(Default)]
+    struct Fake(Mutex<HashMap<String, String>>);
+
+    impl ConfigService for Fake {
+        fn get_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes> {
+            let content = self.0.lock().unwrap().get(data_id).cloned();
+            Box::pin(async move {
+                content
+                    .map(Bytes::from)
+                    .ok_or_else(|| Error::Io(std::io::ErrorKind::NotFound.into()))
+            })
+        }
+
+        #[cfg(feature = "config")]
+        fn wait_for_new_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, Bytes> {
+            self.get_config(data_id)
+        }
+
+        fn publish_config<'a>(
+            &'a self,
+            data_id: &'a str,
+            content: &'a str,
+            _: Option<ConfigFormat>,
+        ) -> ServiceFuture<'a, ()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(data_id.into(), content.into());
+            Box::pin(async { Ok(()) })
+        }
+
+        fn delete_config<'a>(&'a self, data_id: &'a str) -> ServiceFuture<'a, ()> {
+            self.0.lock().unwrap().remove(data_id);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn port(config: &dyn ConfigService) -> Result<String> {
+        let content = config.get_config("app.port").await?;
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    }
+
+    #[tokio::test]
+    async fn fake() {
+        let fake = Fake::default();
+        assert!(port(&fake).await.is_err());
+        fake.publish_config("app.port", "8080", None).await.unwrap();
+        assert_eq!(port(&fake).await.unwrap(), "8080");
+    }
+}
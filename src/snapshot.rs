@@ -0,0 +1,641 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    export::write_atomically_blocking, protocol, runtime, Error, FetchedConfig, Nacos, Result,
+};
+
+/// Starts compressed snapshots, followed by the name of the compression and a newline.
+const COMPRESSED_MAGIC: &[u8] = b"NACOS-COMPRESSED ";
+
+/// Starts snapshots of configs served with an encrypted data key, followed by the key and a
+/// newline. Other snapshots are the content as served.
+const DATA_KEY_MAGIC: &[u8] = b"NACOS-DATA-KEY ";
+
+/// Compresses snapshots, see [`Nacos::with_snapshot_compression`]. The `snapshot-gzip` and
+/// `snapshot-zstd` features provide `Gzip` and `Zstd`; implement it for others with the crate
+/// of your choice.
+pub trait SnapshotCompression: Send + Sync {
+    /// Identifies the compression in stored snapshots, e.g. `zstd`. Must not contain newlines.
+    fn name(&self) -> &str;
+
+    fn compress(&self, content: &[u8]) -> std::io::Result<Vec<u8>>;
+
+    fn decompress(&self, compressed: &[u8]) -> std::io::Result<Vec<u8>>;
+}
+
+/// How long snapshot writes are gathered before being written together.
+const BATCH_DELAY: Duration = Duration::from_millis(100);
+
+/// Returns the key that snapshots are encrypted with.
+#[cfg(feature = "snapshot-encryption")]
+pub(crate) type Keyring = std::sync::Arc<dyn Fn() -> Result<[u8; 32], String> + Send + Sync>;
+
+/// Starts encrypted snapshots, followed by the nonce and the AES-256-GCM ciphertext.
+#[cfg(feature = "snapshot-encryption")]
+const ENCRYPTED_MAGIC: &[u8] = b"NACOS-AES256GCM\n";
+
+/// Snapshot writes queued for a background task, shared by clones. Only the last write of
+/// each file in a batch is done, `None` deleting it.
+#[derive(Default)]
+pub(crate) struct SnapshotWrites {
+    pending: Mutex<Pending>,
+    /// Held while writing a batch, so that batches are written in order.
+    writing: tokio::sync::Mutex<()>,
+}
+
+#[derive(Default)]
+struct Pending {
+    writes: HashMap<PathBuf, Option<Arc<[u8]>>>,
+    scheduled: bool,
+}
+
+impl SnapshotWrites {
+    fn queue(&self, nacos: &Nacos, path: PathBuf, sealed: Option<Arc<[u8]>>) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.writes.insert(path, sealed);
+        if !pending.scheduled {
+            pending.scheduled = true;
+            let nacos = nacos.clone();
+            nacos.clone().spawn(async move {
+                nacos.sleep(BATCH_DELAY).await;
+                nacos.snapshot_writes.write_batch().await;
+            });
+        }
+    }
+
+    /// The queued content of a file, `Some(None)` if it is to be deleted.
+    fn queued(&self, path: &Path) -> Option<Option<Arc<[u8]>>> {
+        self.pending.lock().unwrap().writes.get(path).cloned()
+    }
+
+    async fn write_batch(&self) {
+        let _writing = self.writing.lock().await;
+        let writes = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.scheduled = false;
+            std::mem::take(&mut pending.writes)
+        };
+        let written = runtime::unblock(move || {
+            for (path, sealed) in writes {
+                let result = match sealed {
+                    Some(sealed) => write_snapshot(&path, &sealed),
+                    None => match std::fs::remove_file(&path) {
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                        result => result,
+                    },
+                };
+                if let Err(e) = result {
+                    tracing::warn!(path = %path.display(), error = %e, "Failed to write snapshot");
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = written.await {
+            tracing::warn!(error = %e, "Failed to write snapshots");
+        }
+    }
+}
+
+fn write_snapshot(path: &Path, sealed: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomically_blocking(path, sealed)
+}
+
+impl Nacos {
+    /// Keeps the last fetched value of every config in `dir` and serves it while the server
+    /// can't be reached, e.g. when a service restarts during an outage. Snapshots are stored as
+    /// served, so `cipher-` configs stay encrypted and are decrypted when read, and other
+    /// configs are stored in plaintext unless encrypted with [`Self::with_snapshot_key`].
+    ///
+    /// Snapshots are written in the background, in batches. See [`Self::flush_snapshots`].
+    /// They aren't kept on wasm32, which has no file system.
+    pub fn with_snapshot_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.snapshot_dir = Some(dir.into());
+        self
+    }
+
+    /// Encrypts snapshots with AES-256-GCM. Snapshots that aren't encrypted with the key are
+    /// then ignored.
+    #[cfg(feature = "snapshot-encryption")]
+    pub fn with_snapshot_key(self, key: [u8; 32]) -> Self {
+        self.with_snapshot_keyring(move || Ok(key))
+    }
+
+    /// Like [`Self::with_snapshot_key`], calling `keyring` for the key whenever a snapshot is
+    /// written or read, e.g. to get it from a secret store instead of keeping it in memory.
+    #[cfg(feature = "snapshot-encryption")]
+    pub fn with_snapshot_keyring(
+        mut self,
+        keyring: impl Fn() -> Result<[u8; 32], String> + Send + Sync + 'static,
+    ) -> Self {
+        self.snapshot_keyring = Some(std::sync::Arc::new(keyring));
+        self
+    }
+
+    /// Compresses snapshots before storing them, and before encrypting them if they are.
+    /// Snapshots stored without compression are still read, but compressed ones are ignored
+    /// once their compression is removed or replaced.
+    pub fn with_snapshot_compression(
+        mut self,
+        compression: impl SnapshotCompression + 'static,
+    ) -> Self {
+        self.snapshot_compression = Some(Arc::new(compression));
+        self
+    }
+
+    fn compress_snapshot(&self, content: &[u8]) -> std::io::Result<Vec<u8>> {
+        let Some(compression) = &self.snapshot_compression else {
+            return Ok(content.to_vec());
+        };
+        let name = compression.name().as_bytes();
+        let compressed = compression.compress(content)?;
+        Ok([COMPRESSED_MAGIC, name, b"\n", &compressed].concat())
+    }
+
+    fn decompress_snapshot(&self, stored: Vec<u8>) -> Result<Vec<u8>> {
+        let Some(compressed) = stored.strip_prefix(COMPRESSED_MAGIC) else {
+            return Ok(stored);
+        };
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let newline = compressed
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| invalid("snapshot compression is missing".into()))?;
+        let (name, compressed) = (&compressed[..newline], &compressed[newline + 1..]);
+        match &self.snapshot_compression {
+            Some(compression) if compression.name().as_bytes() == name => {
+                Ok(compression.decompress(compressed)?)
+            }
+            _ => Err(invalid(format!(
+                "snapshot is compressed with {}",
+                String::from_utf8_lossy(name)
+            ))
+            .into()),
+        }
+    }
+
+    /// Waits until the snapshots fetched so far are written, e.g. before exiting.
+    pub async fn flush_snapshots(&self) {
+        self.snapshot_writes.write_batch().await;
+    }
+
+    fn snapshot_path(&self, group: &str, data_id: &str) -> Option<PathBuf> {
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        let namespace = self.namespace.as_deref().unwrap_or("public");
+        Some(
+            self.snapshot_dir
+                .as_ref()?
+                .join(file_name(namespace))
+                .join(file_name(group))
+                .join(file_name(data_id)),
+        )
+    }
+
+    /// Queues the snapshot of a config as served by the server, before decryption and filters.
+    pub(crate) async fn save_snapshot(&self, group: &str, data_id: &str, served: &FetchedConfig) {
+        let Some(path) = self.snapshot_path(group, data_id) else {
+            return;
+        };
+        let key = self.config_key(group, data_id);
+        self.snapshots_served.lock().unwrap().remove(&key);
+        let sealed = self
+            .compress_snapshot(&encode_snapshot(served))
+            .map_err(Error::from)
+            .and_then(|compressed| self.seal(group, data_id, &compressed));
+        match sealed {
+            Ok(sealed) => self.snapshot_writes.queue(self, path, Some(sealed.into())),
+            Err(e) => tracing::warn!(data_id, error = %e, "Failed to save the snapshot of config"),
+        }
+    }
+
+    /// Serves the snapshot of a config that could not be fetched because of `error`, as it was
+    /// served, or deletes it if the config doesn't exist anymore.
+    pub(crate) async fn fetch_snapshot(
+        &self,
+        group: &str,
+        data_id: &str,
+        error: Error,
+    ) -> Result<FetchedConfig> {
+        // The server answered with a value that the client refuses.
+        if matches!(
+            error,
+            Error::TooLarge { .. } | Error::Rejected(_) | Error::Filter { .. }
+        ) {
+            return Err(error);
+        }
+        let Some(path) = self.snapshot_path(group, data_id) else {
+            return Err(error);
+        };
+        if error.is_not_found() {
+            self.snapshot_writes.queue(self, path, None);
+            return Err(error);
+        }
+        let read = match self.snapshot_writes.queued(&path) {
+            Some(Some(sealed)) => Ok(sealed.to_vec()),
+            Some(None) => return Err(error),
+            None => {
+                let path = path.clone();
+                runtime::unblock(move || std::fs::read(path)).await
+            }
+        };
+        let content = match read {
+            Ok(sealed) => self
+                .open(group, data_id, sealed)
+                .and_then(|stored| self.decompress_snapshot(stored))
+                .and_then(|stored| Ok(decode_snapshot(stored)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(error),
+            Err(e) => Err(e.into()),
+        };
+        let (content, encrypted_data_key) = match content {
+            Ok(content) => content,
+            Err(e) => {
+                tracing::warn!(data_id, error = %e, "Failed to read the snapshot of config");
+                return Err(error);
+            }
+        };
+        tracing::warn!(data_id, error = %error, "Serving config from its snapshot");
+        self.snapshots_served
+            .lock()
+            .unwrap()
+            .insert(self.config_key(group, data_id));
+        Ok(FetchedConfig {
+            md5: protocol::md5(&content),
+            content: content.into(),
+            encrypted_data_key,
+            from_snapshot: true,
+            #[cfg(feature = "charset")]
+            charset: self.charset,
+            ..Default::default()
+        })
+    }
+
+    #[cfg(not(feature = "snapshot-encryption"))]
+    fn seal(&self, _: &str, _: &str, content: &[u8]) -> Result<Vec<u8>> {
+        Ok(content.to_vec())
+    }
+
+    #[cfg(not(feature = "snapshot-encryption"))]
+    fn open(&self, _: &str, _: &str, sealed: Vec<u8>) -> Result<Vec<u8>> {
+        Ok(sealed)
+    }
+
+    /// Encrypts a snapshot, binding it to its config so that snapshots can't be swapped.
+    #[cfg(feature = "snapshot-encryption")]
+    fn seal(&self, group: &str, data_id: &str, content: &[u8]) -> Result<Vec<u8>> {
+        use aes_gcm::{
+            aead::{Aead, AeadCore, OsRng, Payload},
+            Aes256Gcm,
+        };
+
+        let Some(cipher) = self.snapshot_cipher()? else {
+            return Ok(content.to_vec());
+        };
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(group, data_id);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: content,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Encrypt("failed to encrypt snapshot".into()))?;
+        Ok([ENCRYPTED_MAGIC, &nonce, &ciphertext].concat())
+    }
+
+    #[cfg(feature = "snapshot-encryption")]
+    fn open(&self, group: &str, data_id: &str, sealed: Vec<u8>) -> Result<Vec<u8>> {
+        use aes_gcm::{
+            aead::{Aead, Payload},
+            Nonce,
+        };
+
+        let encrypted = sealed.strip_prefix(ENCRYPTED_MAGIC);
+        let cipher = self.snapshot_cipher()?;
+        let (cipher, encrypted) = match (cipher, encrypted) {
+            (None, None) => return Ok(sealed),
+            (Some(cipher), Some(encrypted)) if encrypted.len() >= 12 => (cipher, encrypted),
+            (Some(_), _) => return Err(Error::Decrypt("snapshot is not encrypted".into())),
+            (None, Some(_)) => return Err(Error::Decrypt("snapshot is encrypted".into())),
+        };
+        let (nonce, ciphertext) = encrypted.split_at(12);
+        let aad = associated_data(group, data_id);
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| Error::Decrypt("snapshot was encrypted with another key".into()))
+    }
+
+    #[cfg(feature = "snapshot-encryption")]
+    fn snapshot_cipher(&self) -> Result<Option<aes_gcm::Aes256Gcm>> {
+        use aes_gcm::{Aes256Gcm, Key, KeyInit};
+
+        let Some(keyring) = &self.snapshot_keyring else {
+            return Ok(None);
+        };
+        let key = keyring().map_err(Error::Decrypt)?;
+        Ok(Some(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))))
+    }
+}
+
+/// The name of the file or directory of an id in the snapshot directory, percent-encoding
+/// separators, `%` and ids made of dots so that every id stays a single component.
+fn file_name(id: &str) -> String {
+    if id.chars().all(|c| c == '.') {
+        return id.replace('.', "%2E");
+    }
+    let mut name = String::with_capacity(id.len());
+    for c in id.chars() {
+        match c {
+            '%' | '/' | '\\' | '\0' => name.push_str(&format!("%{:02X}", c as u32)),
+            c => name.push(c),
+        }
+    }
+    name
+}
+
+fn encode_snapshot(served: &FetchedConfig) -> Vec<u8> {
+    match &served.encrypted_data_key {
+        Some(data_key) => [DATA_KEY_MAGIC, data_key.as_bytes(), b"\n", &served.content].concat(),
+        None => served.content.to_vec(),
+    }
+}
+
+/// The content and encrypted data key of a stored snapshot.
+fn decode_snapshot(stored: Vec<u8>) -> std::io::Result<(Vec<u8>, Option<String>)> {
+    let Some(rest) = stored.strip_prefix(DATA_KEY_MAGIC) else {
+        return Ok((stored, None));
+    };
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid data key");
+    let newline = rest.iter().position(|&b| b == b'\n').ok_or_else(invalid)?;
+    let data_key = std::str::from_utf8(&rest[..newline]).map_err(|_| invalid())?;
+    Ok((rest[newline + 1..].to_vec(), Some(data_key.to_string())))
+}
+
+#[cfg(feature = "snapshot-encryption")]
+fn associated_data(group: &str, data_id: &str) -> Vec<u8> {
+    [group.as_bytes(), &[2], data_id.as_bytes()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn unreachable() -> Error {
+        Error::Io(std::io::ErrorKind::ConnectionRefused.into())
+    }
+
+    fn served(content: &'static [u8]) -> FetchedConfig {
+        FetchedConfig {
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn snapshot() {
+        let dir = std::env::temp_dir().join(format!("nacos-snapshot-{}", std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_snapshot_dir(&dir);
+        assert!(nacos
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .is_err());
+
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &served(b"a: 1"))
+            .await;
+        // Served before it is written.
+        let config = nacos
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, "a: 1");
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &served(b"a: 2"))
+            .await;
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &served(b"a: 1"))
+            .await;
+        nacos.flush_snapshots().await;
+        assert_eq!(
+            std::fs::read(dir.join("public/DEFAULT_GROUP/app.yaml")).unwrap(),
+            b"a: 1"
+        );
+        let config = nacos
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, "a: 1");
+        assert!(config.from_snapshot);
+
+        // Configs are kept as served, with their data key and md5.
+        let served = FetchedConfig {
+            content: b"ciphertext"[..].into(),
+            md5: protocol::md5(b"ciphertext"),
+            encrypted_data_key: Some("key".into()),
+            ..Default::default()
+        };
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "cipher-db.yaml", &served)
+            .await;
+        nacos.flush_snapshots().await;
+        let config = nacos
+            .fetch_snapshot("DEFAULT_GROUP", "cipher-db.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, served.content);
+        assert_eq!(config.md5, served.md5);
+        assert_eq!(config.encrypted_data_key.as_deref(), Some("key"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    struct Reversed;
+
+    impl SnapshotCompression for Reversed {
+        fn name(&self) -> &str {
+            "reversed"
+        }
+
+        fn compress(&self, content: &[u8]) -> std::io::Result<Vec<u8>> {
+            Ok(content.iter().rev().copied().collect())
+        }
+
+        fn decompress(&self, compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+            self.compress(compressed)
+        }
+    }
+
+    #[test]
+    fn snapshot_path() {
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_snapshot_dir("snapshots");
+        let path = |group, data_id| nacos.snapshot_path(group, data_id).unwrap();
+        assert_eq!(
+            path("DEFAULT_GROUP", "app.yaml"),
+            Path::new("snapshots/public/DEFAULT_GROUP/app.yaml")
+        );
+        assert_eq!(
+            path("..", "../../etc/passwd"),
+            Path::new("snapshots/public/%2E%2E/..%2F..%2Fetc%2Fpasswd")
+        );
+        assert_eq!(
+            path("DEFAULT_GROUP", r"..\50%"),
+            Path::new("snapshots/public/DEFAULT_GROUP/..%5C50%25")
+        );
+    }
+
+    #[tokio::test]
+    async fn compressed_snapshot() {
+        let dir = std::env::temp_dir().join(format!("nacos-compressed-{}", std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_snapshot_dir(&dir);
+        let compressed = nacos.clone().with_snapshot_compression(Reversed);
+        compressed
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &served(b"a: 1"))
+            .await;
+        compressed.flush_snapshots().await;
+        assert_eq!(
+            std::fs::read(dir.join("public/DEFAULT_GROUP/app.yaml")).unwrap(),
+            b"NACOS-COMPRESSED reversed\n1 :a"
+        );
+        let config = compressed
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, "a: 1");
+        assert!(nacos
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .is_err());
+
+        // Snapshots stored before compressing them are still served.
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "db.yaml", &served(b"b: 2"))
+            .await;
+        nacos.flush_snapshots().await;
+        let config = compressed
+            .fetch_snapshot("DEFAULT_GROUP", "db.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, "b: 2");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Stores a snapshot compressed with `compression`, checking that it starts with `magic`.
+    #[cfg(any(feature = "snapshot-gzip", feature = "snapshot-zstd"))]
+    async fn compressed_with(compression: impl SnapshotCompression + 'static, magic: &[u8]) {
+        let name = compression.name().to_string();
+        let dir = std::env::temp_dir().join(format!("nacos-{}-{}", name, std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_snapshot_dir(&dir)
+        .with_snapshot_compression(compression);
+        let content = "a: 1\n".repeat(100);
+        let served = FetchedConfig {
+            content: content.as_bytes().to_vec().into(),
+            ..Default::default()
+        };
+        nacos
+            .save_snapshot("DEFAULT_GROUP", "app.yaml", &served)
+            .await;
+        nacos.flush_snapshots().await;
+        let stored = std::fs::read(dir.join("public/DEFAULT_GROUP/app.yaml")).unwrap();
+        let prefix = [COMPRESSED_MAGIC, name.as_bytes(), b"\n", magic].concat();
+        assert!(stored.starts_with(&prefix));
+        assert!(stored.len() < content.len());
+        let config = nacos
+            .fetch_snapshot("DEFAULT_GROUP", "app.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, content);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "snapshot-gzip")]
+    #[tokio::test]
+    async fn gzip_snapshot() {
+        compressed_with(crate::Gzip, b"\x1f\x8b").await;
+        // Streams of several members, as concatenated by `cat`, are read whole.
+        let mut stream = crate::Gzip.compress(b"a: 1\n").unwrap();
+        stream.extend(crate::Gzip.compress(b"b: 2\n").unwrap());
+        assert_eq!(crate::Gzip.decompress(&stream).unwrap(), b"a: 1\nb: 2\n");
+    }
+
+    #[cfg(feature = "snapshot-zstd")]
+    #[tokio::test]
+    async fn zstd_snapshot() {
+        compressed_with(crate::Zstd, b"\x28\xb5\x2f\xfd").await;
+    }
+
+    #[cfg(feature = "snapshot-encryption")]
+    #[tokio::test]
+    async fn encrypted_snapshot() {
+        let dir = std::env::temp_dir().join(format!("nacos-encrypted-{}", std::process::id()));
+        let nacos = Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+        .with_snapshot_dir(&dir);
+        let encrypted = nacos.clone().with_snapshot_key([7; 32]);
+        encrypted
+            .save_snapshot("DEFAULT_GROUP", "db.yaml", &served(b"password: hunter2"))
+            .await;
+        encrypted.flush_snapshots().await;
+        let stored = std::fs::read(dir.join("public/DEFAULT_GROUP/db.yaml")).unwrap();
+        assert!(!stored.windows(7).any(|w| w == b"hunter2"));
+
+        let config = encrypted
+            .fetch_snapshot("DEFAULT_GROUP", "db.yaml", unreachable())
+            .await
+            .unwrap();
+        assert_eq!(config.content, "password: hunter2");
+        // Neither without the key nor with another one.
+        for other in [nacos, encrypted.with_snapshot_key([8; 32])] {
+            assert!(other
+                .fetch_snapshot("DEFAULT_GROUP", "db.yaml", unreachable())
+                .await
+                .is_err());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use tracing::{field::Empty, Instrument, Span};
+
+use crate::Nacos;
+
+/// The span of a request to the server, named `operation` for OpenTelemetry layers, with the
+/// config or service it is about.
+pub(crate) fn request_span(
+    nacos: &Nacos,
+    operation: &'static str,
+    attributes: &[(&'static str, &str)],
+) -> Span {
+    let attribute = |name| {
+        attributes
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    };
+    tracing::info_span!(
+        "nacos.request",
+        otel.name = operation,
+        operation,
+        group = attribute("nacos.group"),
+        data_id = attribute("nacos.data_id"),
+        service = attribute("nacos.service"),
+        tenant = nacos.namespace.as_deref(),
+        server = %nacos.servers.current().base_url(),
+        status = Empty,
+        elapsed_ms = Empty,
+    )
+}
+
+/// The span of a config fetch, including its retries and fallback to snapshots.
+pub(crate) fn fetch_span(nacos: &Nacos, group: &str, data_id: &str) -> Span {
+    tracing::info_span!(
+        "nacos.config.fetch",
+        group,
+        data_id,
+        tenant = nacos.namespace.as_deref(),
+        from_snapshot = Empty,
+        elapsed_ms = Empty,
+    )
+}
+
+#[cfg(feature = "config")]
+/// The span of a long poll of `configs` configs.
+pub(crate) fn listen_span(nacos: &Nacos, configs: usize, hang_up: bool) -> Span {
+    tracing::info_span!(
+        "nacos.config.listen",
+        configs,
+        hang_up,
+        tenant = nacos.namespace.as_deref(),
+        server = %nacos.servers.current().base_url(),
+        changed = Empty,
+        elapsed_ms = Empty,
+    )
+}
+
+/// Runs `future` in `span`, recording how long it took in its `elapsed_ms` field, except on
+/// wasm32 whose std has no clock.
+pub(crate) async fn timed<F: Future>(span: Span, future: F) -> F::Output {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let started = Instant::now();
+        let output = future.instrument(span.clone()).await;
+        span.record("elapsed_ms", started.elapsed().as_secs_f64() * 1000.0);
+        output
+    }
+    #[cfg(target_arch = "wasm32")]
+    future.instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, str::FromStr};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn spans() {
+        let nacos = Nacos::new(
+            false,
+            SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            Some("dev".into()),
+            "DEFAULT_GROUP".into(),
+        );
+        let span = request_span(
+            &nacos,
+            "nacos.config.fetch",
+            &[
+                ("nacos.group", "DEFAULT_GROUP"),
+                ("nacos.data_id", "app.yaml"),
+            ],
+        );
+        // Spans without a subscriber are disabled, and still run their future.
+        assert_eq!(timed(span, async { 1 }).await, 1);
+    }
+}
@@ -0,0 +1,312 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::{header::HeaderMap, Method, ResponseBuilderExt, StatusCode, Url};
+use reqwest::{RequestBuilder, Response};
+
+use crate::{redact, Error, Nacos, Result};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = std::result::Result<HttpResponse, TransportError>> + Send + 'a>>;
+
+/// Sends the HTTP requests of a client, see [`Nacos::with_transport`]. Requests are sent with
+/// the client's `reqwest::Client` by default.
+///
+/// Requests and responses are plain values, so an implementation can send them with any HTTP
+/// client or runtime. Error statuses are turned into errors by the client. Not available on
+/// wasm32, where responses can only come from fetch.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait Transport: Send + Sync + 'static {
+    /// Sends a request with its token, signature and headers already added.
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+/// A request to the server, sent by a [`Transport`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// How long the request may take, if limited by [`Nacos::with_timeout`] or a deadline.
+    pub timeout: Option<Duration>,
+}
+
+/// The answer of the server to an [`HttpRequest`], with its whole body.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpResponse {
+    /// A `200 OK` response with `body`.
+    pub fn ok(body: impl Into<Bytes>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+}
+
+/// A request a [`Transport`] failed to send, or to receive the answer of.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, thiserror::Error)]
+#[error("{source}")]
+pub struct TransportError {
+    kind: TransportErrorKind,
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransportErrorKind {
+    Connect,
+    Timeout,
+    Other,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TransportError {
+    /// The server could not be reached, so that the client tries the next one.
+    pub fn connect(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::new(TransportErrorKind::Connect, source)
+    }
+
+    /// The request took longer than its [`HttpRequest::timeout`].
+    pub fn timeout(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::new(TransportErrorKind::Timeout, source)
+    }
+
+    pub fn other(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::new(TransportErrorKind::Other, source)
+    }
+
+    fn new(
+        kind: TransportErrorKind,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self {
+            kind,
+            source: source.into(),
+        }
+    }
+
+    pub fn is_connect(&self) -> bool {
+        self.kind == TransportErrorKind::Connect
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind == TransportErrorKind::Timeout
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_connect() {
+            Self::connect(e)
+        } else if e.is_timeout() {
+            Self::timeout(e)
+        } else {
+            Self::other(e)
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Transport for reqwest::Client {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        let mut builder = self
+            .request(request.method, request.url)
+            .headers(request.headers)
+            .body(request.body);
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        Box::pin(async move {
+            let response = builder.send().await?;
+            Ok(HttpResponse {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: response.bytes().await?,
+            })
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Nacos {
+    /// Sends requests to the server with `transport` instead of the client's `reqwest::Client`,
+    /// e.g. to run them on another runtime or answer them in tests. Retries, redirects,
+    /// authentication and tracing still apply.
+    pub fn with_transport(mut self, transport: impl Transport) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+}
+
+impl Nacos {
+    /// Sends a request through the transport. Servers that can't be connected to are skipped
+    /// by the next requests.
+    pub(crate) async fn execute(
+        &self,
+        request: RequestBuilder,
+        request_id: &str,
+    ) -> Result<Response> {
+        let failed = |source| Error::Request {
+            request_id: request_id.to_string(),
+            source: redact::redact_url(source),
+        };
+        let request = request.build().map_err(failed)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let url = request.url().clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        let response = match &self.transport {
+            Some(transport) => send(transport.as_ref(), request, request_id).await,
+            None => self.client.execute(request).await.map_err(failed),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let response = self.client.execute(request).await.map_err(failed);
+        // Failed fetches on wasm32 don't tell failed connections apart.
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(e) = &response {
+            let unreachable = match e {
+                Error::Request { source, .. } => source.is_connect(),
+                Error::Transport { source, .. } => source.is_connect(),
+                _ => false,
+            };
+            if unreachable {
+                self.servers.unreachable(&url);
+                if let Some(host) = url.host_str() {
+                    self.dns.invalidate(host);
+                }
+            }
+        }
+        response
+    }
+}
+
+/// Sends a request through a custom transport, as a `reqwest::Response` for the rest of the
+/// client.
+#[cfg(not(target_arch = "wasm32"))]
+async fn send(
+    transport: &dyn Transport,
+    request: reqwest::Request,
+    request_id: &str,
+) -> Result<Response> {
+    let failed = |source| Error::Transport {
+        request_id: request_id.to_string(),
+        source,
+    };
+    let body = match request.body() {
+        Some(body) => match body.as_bytes() {
+            Some(body) => Bytes::copy_from_slice(body),
+            None => {
+                let e = TransportError::other("streamed bodies can't be sent through a transport");
+                return Err(failed(e));
+            }
+        },
+        None => Bytes::new(),
+    };
+    let url = request.url().clone();
+    let request = HttpRequest {
+        method: request.method().clone(),
+        url: url.clone(),
+        headers: request.headers().clone(),
+        body,
+        timeout: request.timeout().copied(),
+    };
+    let answered = transport.send(request).await.map_err(failed)?;
+    let mut response = hyper::Response::builder()
+        .status(answered.status)
+        .url(url)
+        .body(answered.body)
+        .map_err(|e| failed(TransportError::other(e)))?;
+    *response.headers_mut() = answered.headers;
+    Ok(Response::from(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Mutex};
+
+    use super::*;
+
+    /// Answers every request with the same config, keeping the requested URLs.
+    struct Fake {
+        content: &'static str,
+        urls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Transport for Fake {
+        fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+            self.urls.lock().unwrap().push(request.url.to_string());
+            let mut response = HttpResponse::ok(self.content);
+            response.headers.insert(
+                "Content-MD5",
+                crate::protocol::md5_hex(self.content.as_bytes())
+                    .parse()
+                    .unwrap(),
+            );
+            Box::pin(async { Ok(response) })
+        }
+    }
+
+    /// Fails to connect to every server.
+    struct Unreachable;
+
+    impl Transport for Unreachable {
+        fn send(&self, _: HttpRequest) -> TransportFuture<'_> {
+            Box::pin(async { Err(TransportError::connect("connection refused")) })
+        }
+    }
+
+    fn nacos() -> Nacos {
+        Nacos::new(
+            false,
+            std::net::SocketAddr::from_str("127.0.0.1:1").unwrap(),
+            None,
+            "DEFAULT_GROUP".into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn transport() {
+        let urls = Arc::new(Mutex::new(Vec::new()));
+        let nacos = nacos().with_transport(Fake {
+            content: "a: 1",
+            urls: urls.clone(),
+        });
+        assert_eq!(nacos.get_config("app.yaml").await.unwrap(), "a: 1");
+        let urls = urls.lock().unwrap();
+        assert_eq!(urls.len(), 1);
+        assert!(
+            urls[0].starts_with("http://127.0.0.1:1/nacos/v1/cs/configs?"),
+            "{}",
+            urls[0]
+        );
+        assert!(urls[0].contains("dataId=app.yaml"), "{}", urls[0]);
+    }
+
+    #[tokio::test]
+    async fn unreachable() {
+        let nacos = nacos().with_transport(Unreachable);
+        let error = nacos.get_config("app.yaml").await.unwrap_err();
+        assert!(
+            matches!(&error, Error::Transport { source, .. } if source.is_connect()),
+            "{error}"
+        );
+        assert!(error.request_id().is_some());
+        assert_eq!(error.status(), None);
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::{publish::expect_ok, Nacos, Page, Result};
+
+/// A user account, see [`Nacos::list_users`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct User {
+    pub username: String,
+}
+
+/// A role granted to a user, see [`Nacos::list_roles`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RoleBinding {
+    pub role: String,
+    pub username: String,
+}
+
+/// What a permission allows on its resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "r",
+            Self::Write => "w",
+            Self::ReadWrite => "rw",
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "r" => Ok(Self::Read),
+            "w" => Ok(Self::Write),
+            "rw" => Ok(Self::ReadWrite),
+            action => Err(serde::de::Error::unknown_variant(action, &["r", "w", "rw"])),
+        }
+    }
+}
+
+/// A permission of a role, see [`Nacos::list_permissions`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Permission {
+    pub role: String,
+    /// `<namespace id>:*:*` for a whole namespace.
+    pub resource: String,
+    pub action: Action,
+}
+
+impl Nacos {
+    /// Lists users, `page` starting from 1. Requires admin credentials.
+    pub async fn list_users(&self, page: u32, page_size: u32) -> Result<Page<User>> {
+        let url = self.make_url("/nacos/v1/auth/users");
+        let request = self.client.get(url).query(&[
+            ("pageNo", page.to_string()),
+            ("pageSize", page_size.to_string()),
+        ]);
+        let response = self.send("nacos.auth.users.list", request, &[]).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn create_user(&self, username: &str, password: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/users");
+        let request = self
+            .client
+            .post(url)
+            .form(&[("username", username), ("password", password)]);
+        self.send_user_change("nacos.auth.users.create", request, username)
+            .await
+    }
+
+    pub async fn delete_user(&self, username: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/users");
+        let request = self.client.delete(url).query(&[("username", username)]);
+        self.send_user_change("nacos.auth.users.delete", request, username)
+            .await
+    }
+
+    /// Changes any user's password as an admin, or the logged in user's own.
+    pub async fn change_password(&self, username: &str, new_password: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/users");
+        let request = self
+            .client
+            .put(url)
+            .form(&[("username", username), ("newPassword", new_password)]);
+        self.send_user_change("nacos.auth.users.update", request, username)
+            .await
+    }
+
+    /// Lists role bindings, of one user if `username` is given.
+    pub async fn list_roles(
+        &self,
+        username: Option<&str>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<Page<RoleBinding>> {
+        let url = self.make_url("/nacos/v1/auth/roles");
+        let mut request = self.client.get(url).query(&[
+            ("pageNo", page.to_string()),
+            ("pageSize", page_size.to_string()),
+        ]);
+        if let Some(username) = username {
+            request = request.query(&[("username", username)]);
+        }
+        let response = self.send("nacos.auth.roles.list", request, &[]).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Grants `role` to a user, creating the role if needed.
+    pub async fn add_role(&self, role: &str, username: &str) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/roles");
+        let request = self
+            .client
+            .post(url)
+            .form(&[("role", role), ("username", username)]);
+        self.send_user_change("nacos.auth.roles.create", request, username)
+            .await
+    }
+
+    /// Revokes `role` from a user, or deletes it for everyone without a `username`.
+    pub async fn delete_role(&self, role: &str, username: Option<&str>) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/roles");
+        let mut request = self.client.delete(url).query(&[("role", role)]);
+        if let Some(username) = username {
+            request = request.query(&[("username", username)]);
+        }
+        self.send_user_change(
+            "nacos.auth.roles.delete",
+            request,
+            username.unwrap_or_default(),
+        )
+        .await
+    }
+
+    pub async fn list_permissions(
+        &self,
+        role: &str,
+        page: u32,
+        page_size: u32,
+    ) -> Result<Page<Permission>> {
+        let url = self.make_url("/nacos/v1/auth/permissions");
+        let request = self.client.get(url).query(&[
+            ("role", role.to_string()),
+            ("pageNo", page.to_string()),
+            ("pageSize", page_size.to_string()),
+        ]);
+        let response = self
+            .send("nacos.auth.permissions.list", request, &[])
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn add_permission(&self, role: &str, resource: &str, action: Action) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/permissions");
+        let request = self.client.post(url).form(&[
+            ("role", role),
+            ("resource", resource),
+            ("action", action.as_str()),
+        ]);
+        let response = self
+            .send(
+                "nacos.auth.permissions.create",
+                request,
+                &[("nacos.role", role)],
+            )
+            .await?;
+        expect_ok(&response.text().await?)
+    }
+
+    pub async fn delete_permission(
+        &self,
+        role: &str,
+        resource: &str,
+        action: Action,
+    ) -> Result<()> {
+        let url = self.make_url("/nacos/v1/auth/permissions");
+        let request = self.client.delete(url).query(&[
+            ("role", role),
+            ("resource", resource),
+            ("action", action.as_str()),
+        ]);
+        let response = self
+            .send(
+                "nacos.auth.permissions.delete",
+                request,
+                &[("nacos.role", role)],
+            )
+            .await?;
+        expect_ok(&response.text().await?)
+    }
+
+    async fn send_user_change(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+        username: &str,
+    ) -> Result<()> {
+        let response = self
+            .send(operation, request, &[("nacos.username", username)])
+            .await?;
+        expect_ok(&response.text().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn users() {
+        let page: Page<User> = serde_json::from_str(
+            r#"{"totalCount": 1, "pageNumber": 1, "pagesAvailable": 1, "pageItems": [
+                {"username": "nacos", "password": "$2a$10$EuWPZHzz32dJN7jexM34MOeYirDdFAZm2kuWj7VEOJhhZkDrxfvUu"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(page.page_items[0].username, "nacos");
+    }
+
+    #[test]
+    fn permissions() {
+        let page: Page<Permission> = serde_json::from_str(
+            r#"{"totalCount": 1, "pageNumber": 1, "pagesAvailable": 1, "pageItems": [
+                {"role": "team-a", "resource": "8a1c:*:*", "action": "rw"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(page.page_items[0].action, Action::ReadWrite);
+        assert!(serde_json::from_str::<Action>(r#""x""#).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+use std::{fmt::Display, future::Future, pin::Pin, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::{ConfigEvent, Error, Nacos, Result};
+
+type ValidationFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+pub(crate) type Validator = Arc<dyn Fn(Bytes) -> ValidationFuture + Send + Sync>;
+
+impl Nacos {
+    /// Validates every new value of a config before it is delivered by the watch APIs.
+    /// Rejected values are logged, reported as [`ConfigEvent::Rejected`] and skipped, so
+    /// watchers keep the previous value. Replaces any previous validator of the config.
+    pub fn set_validator<F, E>(&self, data_id: &str, validator: F)
+    where
+        F: Fn(&Bytes) -> Result<(), E> + Send + Sync + 'static,
+        E: Display,
+    {
+        self.set_async_validator(data_id, move |content: Bytes| {
+            let result = validator(&content).map_err(|e| e.to_string());
+            async move { result }
+        })
+    }
+
+    /// Like [`Self::set_validator`] with an async validator.
+    pub fn set_async_validator<F, Fut, E>(&self, data_id: &str, validator: F)
+    where
+        F: Fn(Bytes) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        let validator: Validator = Arc::new(move |content| {
+            let validation = validator(content);
+            Box::pin(async move { validation.await.map_err(|e| e.to_string()) })
+        });
+        self.validators
+            .lock()
+            .unwrap()
+            .insert(self.config_key(&self.group, data_id), validator);
+    }
+
+    pub fn remove_validator(&self, data_id: &str) {
+        self.validators
+            .lock()
+            .unwrap()
+            .remove(&self.config_key(&self.group, data_id));
+    }
+
+    pub(crate) async fn validate(
+        &self,
+        key: &crate::key::ConfigKey,
+        content: &Bytes,
+    ) -> Result<()> {
+        let validator = self.validators.lock().unwrap().get(key).cloned();
+        let Some(validator) = validator else {
+            return Ok(());
+        };
+        validator(content.clone()).await.map_err(|reason| {
+            let crate::key::ConfigKey { group, data_id, .. } = key.clone();
+            tracing::warn!(data_id, reason, "Rejected new value of config");
+            #[cfg(feature = "metrics")]
+            crate::meter::record_rejection(key);
+            self.emit(ConfigEvent::Rejected {
+                group,
+                data_id,
+                reason: reason.clone(),
+            });
+            Error::Rejected(reason)
+        })
+    }
+}
@@ -0,0 +1,380 @@
+//! Config trees used to merge configs of different formats.
+
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, Deserializer, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+use serde_json::{Map, Value};
+
+use crate::{ConfigFormat, Error, Properties, Result};
+
+/// Parses a config into a tree, nesting properties with [`nest_properties`].
+pub(crate) fn parse_tree(content: &[u8], format: ConfigFormat) -> Result<Value> {
+    match format {
+        ConfigFormat::Properties => {
+            let content = std::str::from_utf8(content).map_err(|e| Error::Parse {
+                format,
+                message: e.to_string(),
+            })?;
+            nest_properties(Properties::parse(content)?)
+        }
+        format => format.deserialize(content),
+    }
+}
+
+/// Recursively merges `overlay` into `base`: objects are merged key by key, anything else is
+/// replaced.
+pub(crate) fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Nests properties on `.`, so `server.port=80` becomes `{"server": {"port": "80"}}`.
+///
+/// Fails if a key is both a value and a prefix of another, like `a=1` and `a.b=2`, which a
+/// tree can't hold.
+pub(crate) fn nest_properties(properties: Properties) -> Result<Value> {
+    let conflict = |key: &str| Error::Parse {
+        format: ConfigFormat::Properties,
+        message: format!(
+            "property {} is both a value and a prefix of other properties",
+            key
+        ),
+    };
+    let mut root = Value::Object(Map::new());
+    for (key, value) in properties {
+        let mut node = &mut root;
+        let mut prefix_len = 0usize;
+        for part in key.split('.') {
+            if node.is_null() {
+                *node = Value::Object(Map::new());
+            }
+            let Value::Object(map) = node else {
+                return Err(conflict(&key[..prefix_len.saturating_sub(1)]));
+            };
+            prefix_len += part.len() + 1;
+            node = map.entry(part).or_insert(Value::Null);
+        }
+        if !node.is_null() {
+            return Err(conflict(&key));
+        }
+        *node = Value::String(value);
+    }
+    Ok(root)
+}
+
+/// Placeholders nested deeper than this are assumed to be circular.
+const MAX_PLACEHOLDER_DEPTH: usize = 32;
+
+/// Resolves `${key}` and `${key:default}` placeholders in string values, as Spring does. Keys
+/// are looked up in `tree` on `.`-separated paths, then in environment variables.
+pub(crate) fn resolve_placeholders(tree: &mut Value) -> std::result::Result<(), String> {
+    let root = tree.clone();
+    resolve_in(tree, &root)
+}
+
+fn resolve_in(node: &mut Value, root: &Value) -> std::result::Result<(), String> {
+    match node {
+        Value::String(s) if s.contains("${") => *s = resolve_str(s, root, 0)?,
+        Value::Array(items) => {
+            for item in items {
+                resolve_in(item, root)?;
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                resolve_in(value, root)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn resolve_str(s: &str, root: &Value, depth: usize) -> std::result::Result<String, String> {
+    if depth > MAX_PLACEHOLDER_DEPTH {
+        return Err(format!("circular placeholder reference in {s:?}"));
+    }
+    let mut resolved = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        resolved.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let Some(end) = closing_brace(placeholder) else {
+            // Not a placeholder, keep it as is.
+            resolved.push_str(&rest[start..]);
+            return Ok(resolved);
+        };
+        let (key, default) = split_default(&placeholder[..end]);
+        let key = resolve_str(key, root, depth + 1)?;
+        let value = match (lookup(root, &key), default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => default.to_string(),
+            (None, None) => return Err(format!("could not resolve placeholder ${{{key}}}")),
+        };
+        resolved.push_str(&resolve_str(&value, root, depth + 1)?);
+        rest = &placeholder[end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+/// Finds the `}` closing a placeholder, skipping nested ones.
+fn closing_brace(placeholder: &str) -> Option<usize> {
+    let bytes = placeholder.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' if depth == 0 => return Some(i),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Splits `key:default` on the first `:` outside nested placeholders.
+fn split_default(placeholder: &str) -> (&str, Option<&str>) {
+    let bytes = placeholder.as_bytes();
+    let mut depth = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'{' if i > 0 && bytes[i - 1] == b'$' => depth += 1,
+            b'}' => depth -= 1,
+            b':' if depth == 0 => return (&placeholder[..i], Some(&placeholder[i + 1..])),
+            _ => {}
+        }
+    }
+    (placeholder, None)
+}
+
+fn lookup(root: &Value, key: &str) -> Option<String> {
+    let value = key
+        .split('.')
+        .try_fold(root, |node, part| node.as_object()?.get(part))
+        .or_else(|| root.as_object()?.get(key));
+    match value {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(value @ (Value::Number(_) | Value::Bool(_))) => Some(value.to_string()),
+        _ => std::env::var(key).ok(),
+    }
+}
+
+/// Deserializes a tree, parsing strings into the numbers and booleans that `T` expects and
+/// comma-separated strings into sequences, as values from properties are all strings.
+pub(crate) fn from_tree<T: DeserializeOwned>(tree: Value) -> serde_json::Result<T> {
+    T::deserialize(Lenient(tree))
+}
+
+struct Lenient(Value);
+
+macro_rules! parse_str {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0 {
+                Value::String(s) => match s.trim().parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&s), &visitor)),
+                },
+                other => other.$method(visitor),
+            }
+        }
+    )*};
+}
+
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Array(items) => visit_seq(items.into_iter(), visitor),
+            Value::Object(map) => {
+                let mut map = MapDeserializer::new(map.into_iter().map(|(k, v)| (k, Lenient(v))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    parse_str! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::String(s) if s.is_empty() => visit_seq(std::iter::empty(), visitor),
+            Value::String(s) => visit_seq(
+                s.split(',')
+                    .map(|item| Value::String(item.trim().to_string())),
+                visitor,
+            ),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Lenient {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_seq<'de, V: Visitor<'de>>(
+    items: impl Iterator<Item = Value>,
+    visitor: V,
+) -> Result<V::Value, serde_json::Error> {
+    let mut seq = SeqDeserializer::new(items.map(Lenient));
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_layers() {
+        let mut base = json!({"server": {"port": 80, "host": "a"}, "tags": [1, 2]});
+        merge(
+            &mut base,
+            json!({"server": {"port": 81}, "tags": [3], "debug": true}),
+        );
+        assert_eq!(
+            base,
+            json!({"server": {"port": 81, "host": "a"}, "tags": [3], "debug": true})
+        );
+    }
+
+    #[test]
+    fn nested_properties() {
+        let properties = Properties::parse("a.b=2\nc.d.e=3\nc.f=4").unwrap();
+        assert_eq!(
+            nest_properties(properties).unwrap(),
+            json!({"a": {"b": "2"}, "c": {"d": {"e": "3"}, "f": "4"}})
+        );
+
+        // Neither the value nor the nested properties are dropped silently.
+        for content in ["a=1\na.b=2", "a.b=2\na=1", "c.d.e=3\nc.d=4"] {
+            let error = nest_properties(Properties::parse(content).unwrap()).unwrap_err();
+            assert!(matches!(error, Error::Parse { .. }), "{}", content);
+        }
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut tree = json!({
+            "server": {"host": "localhost", "port": 80},
+            "url": "http://${server.host}:${server.port}/${path:api}",
+            "nested": "${missing:${server.host}}",
+            "chained": "${url}",
+            "literal": "${not closed",
+            "list": ["${server.port}"],
+        });
+        resolve_placeholders(&mut tree).unwrap();
+        assert_eq!(tree["url"], "http://localhost:80/api");
+        assert_eq!(tree["nested"], "localhost");
+        assert_eq!(tree["chained"], "http://localhost:80/api");
+        assert_eq!(tree["literal"], "${not closed");
+        assert_eq!(tree["list"][0], "80");
+
+        assert!(resolve_placeholders(&mut json!({"a": "${missing}"})).is_err());
+        assert!(resolve_placeholders(&mut json!({"a": "${b}", "b": "${a}"})).is_err());
+    }
+
+    #[test]
+    fn lenient() {
+        #[derive(Deserialize)]
+        struct Server {
+            port: u16,
+            debug: bool,
+            ratio: Option<f64>,
+            name: String,
+            hosts: Vec<String>,
+            ports: Vec<u16>,
+        }
+        let tree = json!({
+            "port": "8080",
+            "debug": "true",
+            "ratio": "0.5",
+            "name": "42",
+            "hosts": "a, b",
+            "ports": [1, "2"],
+        });
+        let server: Server = from_tree(tree).unwrap();
+        assert_eq!(server.port, 8080);
+        assert!(server.debug);
+        assert_eq!(server.ratio, Some(0.5));
+        assert_eq!(server.name, "42");
+        assert_eq!(server.hosts, ["a", "b"]);
+        assert_eq!(server.ports, [1, 2]);
+        assert!(from_tree::<Server>(json!({"port": "x"})).is_err());
+    }
+}